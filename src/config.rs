@@ -1,21 +1,265 @@
 use std::env;
 
+/// An external OpenID Connect provider users can sign in with.
+#[derive(Debug, Clone)]
+pub struct OidcProviderConfig {
+    /// Used in the login and callback paths.
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: String,
+    /// Whether a verified email from this provider may point a first login
+    /// at an existing user. That user still has to confirm the link.
+    pub link_by_email: bool,
+}
+
+impl OidcProviderConfig {
+    /// Reads `OIDC_<NAME>_ISSUER`, `_CLIENT_ID`, `_CLIENT_SECRET`, `_SCOPES`
+    /// and `_LINK_BY_EMAIL`.
+    fn from_env(name: &str) -> Self {
+        let var = |suffix: &str| env::var(format!("OIDC_{}_{}", name.to_uppercase().replace('-', "_"), suffix));
+        Self {
+            name: name.to_string(),
+            issuer: var("ISSUER")
+                .map(|i| i.trim_end_matches('/').to_string())
+                .unwrap_or_else(|_| panic!("OIDC_{}_ISSUER must be set", name.to_uppercase())),
+            client_id: var("CLIENT_ID").unwrap_or_else(|_| panic!("OIDC_{}_CLIENT_ID must be set", name.to_uppercase())),
+            client_secret: var("CLIENT_SECRET").unwrap_or_default(),
+            scopes: var("SCOPES").unwrap_or_else(|_| "openid email profile".to_string()),
+            link_by_email: var("LINK_BY_EMAIL").is_ok_and(|v| v == "true" || v == "1"),
+        }
+    }
+}
+
+/// Password rules applied when a password is set; read from `PASSWORD_*`.
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    /// In characters, after Unicode normalization.
+    pub min_length: usize,
+    /// In characters, after Unicode normalization.
+    pub max_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub disallow_username: bool,
+    /// Rejects passwords from the bundled common-password list.
+    pub reject_common: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+            disallow_username: true,
+            reject_common: true,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    fn from_env() -> Self {
+        let defaults = Self::default();
+        let flag = |name: &str, default: bool| {
+            env::var(name)
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(default)
+        };
+        let length = |name: &str, default: usize| {
+            env::var(name)
+                .map(|v| v.parse().unwrap_or_else(|_| panic!("{} must be a number", name)))
+                .unwrap_or(default)
+        };
+        Self {
+            min_length: length("PASSWORD_MIN_LENGTH", defaults.min_length),
+            max_length: length("PASSWORD_MAX_LENGTH", defaults.max_length),
+            require_uppercase: flag("PASSWORD_REQUIRE_UPPERCASE", defaults.require_uppercase),
+            require_lowercase: flag("PASSWORD_REQUIRE_LOWERCASE", defaults.require_lowercase),
+            require_digit: flag("PASSWORD_REQUIRE_DIGIT", defaults.require_digit),
+            require_symbol: flag("PASSWORD_REQUIRE_SYMBOL", defaults.require_symbol),
+            disallow_username: flag("PASSWORD_DISALLOW_USERNAME", defaults.disallow_username),
+            reject_common: flag("PASSWORD_REJECT_COMMON", defaults.reject_common),
+        }
+    }
+}
+
+/// Limits of JSON request bodies; read from `MAX_BODY_BYTES` and
+/// `MAX_JSON_DEPTH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimits {
+    /// Largest accepted body, in bytes.
+    pub max_bytes: usize,
+    /// Deepest accepted nesting of objects and arrays.
+    pub max_depth: usize,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 1024 * 1024,
+            max_depth: 32,
+        }
+    }
+}
+
+impl BodyLimits {
+    fn from_env() -> Self {
+        let defaults = Self::default();
+        let limit = |name: &str, default: usize| {
+            env::var(name)
+                .map(|v| v.parse().unwrap_or_else(|_| panic!("{} must be a number", name)))
+                .unwrap_or(default)
+        };
+        Self {
+            max_bytes: limit("MAX_BODY_BYTES", defaults.max_bytes),
+            max_depth: limit("MAX_JSON_DEPTH", defaults.max_depth),
+        }
+    }
+}
+
 pub struct Config {
     pub port: u16,
     pub api_base: String,
     pub static_dir: String,
+    pub audit_log_path: Option<String>,
+    pub session_cookies: bool,
+    pub session_cookie_secure: bool,
+    pub session_cookie_same_site: String,
+    pub issuer: String,
+    pub token_audience: String,
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    /// `starttls`, `tls` or `none`.
+    pub smtp_tls: String,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub mail_from: String,
+    pub email_verification: bool,
+    /// How far the timestamp of a signed request may be from the server clock.
+    pub signature_max_skew_seconds: i64,
+    /// Domain passkeys are scoped to; defaults to the issuer's host.
+    pub webauthn_rp_id: String,
+    pub webauthn_rp_name: String,
+    /// Origin browsers report in `clientDataJSON`; defaults to the issuer.
+    pub webauthn_origin: String,
+    /// Providers listed in `OIDC_PROVIDERS`, comma separated.
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    /// How long a request to an identity provider may take.
+    pub oidc_timeout_seconds: u64,
+    /// Lifetime of a device code and its user code.
+    pub device_code_ttl_seconds: i64,
+    /// Minimum seconds between device token polls.
+    pub device_poll_interval_seconds: i64,
+    pub password_policy: PasswordPolicy,
+    /// htpasswd-style `username:hash[:roles]` file. When this or the clients
+    /// file is set, credentials come from the files instead of the seeded store.
+    pub credentials_users_file: Option<String>,
+    /// `client_id:hash[:scopes]` file.
+    pub credentials_clients_file: Option<String>,
+    /// How often the credential files are checked for changes.
+    pub credentials_reload_seconds: u64,
+    /// Secret of a `scim` client added to the seeded store, which otherwise
+    /// has no client allowed to provision.
+    pub scim_client_secret: Option<String>,
+    /// Language of error messages when `Accept-Language` names none that is
+    /// supported.
+    pub default_locale: String,
+    /// Directory of `<locale>.json` message catalogs loaded at startup.
+    pub locales_dir: Option<String>,
+    pub body_limits: BodyLimits,
 }
 
 impl Config {
     pub fn from_env() -> Self {
+        let port: u16 = env::var("PORT")
+            .unwrap_or_else(|_| "3030".to_string())
+            .parse()
+            .expect("PORT must be a number");
+        let issuer = env::var("ISSUER")
+            .map(|i| i.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| format!("http://127.0.0.1:{}", port));
+        let issuer_host = issuer
+            .split("://")
+            .last()
+            .and_then(|rest| rest.split(['/', ':']).next())
+            .unwrap_or("localhost")
+            .to_string();
         Self {
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "3030".to_string())
-                .parse()
-                .expect("PORT must be a number"),
+            port,
             api_base: env::var("API_BASE")
                 .unwrap_or_else(|_| "/api/v1".trim_matches('/').to_string()),
             static_dir: env::var("STATIC_DIR").unwrap_or_else(|_| "public".to_string()),
+            audit_log_path: env::var("AUDIT_LOG_PATH").ok().filter(|p| !p.is_empty()),
+            session_cookies: env::var("SESSION_COOKIES")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            session_cookie_secure: env::var("SESSION_COOKIE_SECURE")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+            session_cookie_same_site: env::var("SESSION_COOKIE_SAME_SITE")
+                .unwrap_or_else(|_| "Strict".to_string()),
+            webauthn_rp_id: env::var("WEBAUTHN_RP_ID").unwrap_or(issuer_host),
+            webauthn_rp_name: env::var("WEBAUTHN_RP_NAME").unwrap_or_else(|_| "Rust Base Backend".to_string()),
+            webauthn_origin: env::var("WEBAUTHN_ORIGIN").unwrap_or_else(|_| issuer.clone()),
+            oidc_providers: env::var("OIDC_PROVIDERS")
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(OidcProviderConfig::from_env)
+                .collect(),
+            issuer,
+            token_audience: env::var("TOKEN_AUDIENCE")
+                .unwrap_or_else(|_| "rust-base-backend".to_string()),
+            smtp_host: env::var("SMTP_HOST").ok().filter(|h| !h.is_empty()),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .expect("SMTP_PORT must be a number"),
+            smtp_tls: env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string()),
+            smtp_username: env::var("SMTP_USERNAME").ok().filter(|u| !u.is_empty()),
+            smtp_password: env::var("SMTP_PASSWORD").ok(),
+            mail_from: env::var("MAIL_FROM").unwrap_or_else(|_| "no-reply@localhost".to_string()),
+            email_verification: env::var("EMAIL_VERIFICATION")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            signature_max_skew_seconds: env::var("SIGNATURE_MAX_SKEW_SECONDS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .expect("SIGNATURE_MAX_SKEW_SECONDS must be a number"),
+            oidc_timeout_seconds: env::var("OIDC_TIMEOUT_SECONDS")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .expect("OIDC_TIMEOUT_SECONDS must be a number"),
+            device_code_ttl_seconds: env::var("DEVICE_CODE_TTL_SECONDS")
+                .unwrap_or_else(|_| "600".to_string())
+                .parse()
+                .expect("DEVICE_CODE_TTL_SECONDS must be a number"),
+            device_poll_interval_seconds: env::var("DEVICE_POLL_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("DEVICE_POLL_INTERVAL_SECONDS must be a number"),
+            password_policy: PasswordPolicy::from_env(),
+            credentials_users_file: env::var("CREDENTIALS_USERS_FILE").ok().filter(|p| !p.is_empty()),
+            credentials_clients_file: env::var("CREDENTIALS_CLIENTS_FILE").ok().filter(|p| !p.is_empty()),
+            credentials_reload_seconds: env::var("CREDENTIALS_RELOAD_SECONDS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .expect("CREDENTIALS_RELOAD_SECONDS must be a number"),
+            scim_client_secret: env::var("SCIM_CLIENT_SECRET").ok().filter(|s| !s.is_empty()),
+            default_locale: env::var("DEFAULT_LOCALE")
+                .ok()
+                .filter(|l| !l.is_empty())
+                .map(|l| l.to_lowercase())
+                .unwrap_or_else(|| "en".to_string()),
+            locales_dir: env::var("LOCALES_DIR").ok().filter(|p| !p.is_empty()),
+            body_limits: BodyLimits::from_env(),
         }
     }
 }
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use warp::http::StatusCode;
+
+use crate::models::account_model::{ForgotPasswordDto, ResetPasswordDto, VerifyEmailDto};
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::session_model::ClientContext;
+use crate::services::account_service::AccountService;
+use crate::services::audit_service::AuditService;
+use crate::services::auth_service::AuthService;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/password/forgot",
+    tag = "Account",
+    request_body(content = ForgotPasswordDto, description = "Address the reset code is sent to", content_type = "application/json"),
+    responses(
+        (status = 202, description = "A reset code was mailed if the address belongs to an active account"),
+        (status = 400, description = "Missing email", body = ErrorResponse)
+    )
+)]
+pub async fn forgot_password<A: AccountService + Send + Sync>(
+    request: ForgotPasswordDto,
+    accounts: Arc<A>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    accounts
+        .forgot_password(request.email.as_deref().unwrap_or_default())
+        .await;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/password/reset",
+    tag = "Account",
+    request_body(content = ResetPasswordDto, description = "Reset code and the new password", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Password changed; existing sessions were signed out"),
+        (status = 400, description = "Missing fields or invalid/expired code", body = ErrorResponse)
+    )
+)]
+pub async fn reset_password<A: AccountService + Send + Sync, S: AuthService + Send + Sync>(
+    request: ResetPasswordDto,
+    accounts: Arc<A>,
+    auth: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let username = accounts
+        .reset_password(
+            request.token.as_deref().unwrap_or_default(),
+            request.password.as_deref().unwrap_or_default(),
+        )
+        .await
+        .map_err(warp::reject::custom)?;
+    let revoked = auth.revoke_all_sessions(&username, "user").await;
+    audit
+        .record(AuditEvent {
+            action: AuditAction::PasswordReset,
+            outcome: AuditOutcome::Success,
+            actor: Some(username),
+            client_ip: context.client_ip,
+            target: Some(format!("sessions:{}", revoked)),
+        })
+        .await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/email/verify",
+    tag = "Account",
+    request_body(content = VerifyEmailDto, description = "Verification code from the welcome email", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Email verified; the account can log in"),
+        (status = 400, description = "Missing, invalid or expired code", body = ErrorResponse)
+    )
+)]
+pub async fn verify_email<A: AccountService + Send + Sync>(
+    request: VerifyEmailDto,
+    accounts: Arc<A>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let username = accounts
+        .verify_email(request.token.as_deref().unwrap_or_default())
+        .await
+        .map_err(warp::reject::custom)?;
+    audit
+        .record(AuditEvent {
+            action: AuditAction::EmailVerified,
+            outcome: AuditOutcome::Success,
+            actor: Some(username),
+            client_ip: context.client_ip,
+            target: None,
+        })
+        .await;
+    Ok(StatusCode::NO_CONTENT)
+}
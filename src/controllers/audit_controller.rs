@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use crate::models::audit_model::{AuditEntry, AuditQuery, AuditVerificationDto};
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::session_model::Principal;
+use crate::services::audit_service::AuditService;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/audit",
+    tag = "Audit",
+    security(("api_key" = [])),
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Audit entries matching the filter, oldest first", body = Vec<AuditEntry>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse)
+    )
+)]
+pub async fn query_audit_log(
+    _principal: Principal,
+    query: AuditQuery,
+    audit: Arc<dyn AuditService>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let entries = audit.query(&query).await;
+    Ok(warp::reply::json(&entries))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/audit/verify",
+    tag = "Audit",
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Result of re-computing the hash chain", body = AuditVerificationDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse)
+    )
+)]
+pub async fn verify_audit_log(
+    _principal: Principal,
+    audit: Arc<dyn AuditService>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (valid, entries) = audit.verify().await;
+    Ok(warp::reply::json(&AuditVerificationDto { valid, entries }))
+}
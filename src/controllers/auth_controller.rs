@@ -1,9 +1,20 @@
 use std::sync::Arc;
+use warp::http::{header::SET_COOKIE, HeaderValue, StatusCode};
+use warp::Reply;
 
 #[allow(unused_imports)]
 use crate::models::{
-    auth_request::AuthRequestDto, error_response::ErrorResponse, token_model::TokenResponseDto,
+    audit_model::{AuditAction, AuditEvent, AuditOutcome},
+    auth_request::AuthRequestDto,
+    error_response::{ErrorResponse, OAuthErrorResponse},
+    session_model::{ClientContext, LoginResponseDto, Principal},
+    token_model::TokenResponseDto,
 };
+use crate::config::Config;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::middleware::auth::{build_cookie, CSRF_COOKIE, SESSION_COOKIE};
+use crate::services::audit_service::AuditService;
 use crate::services::auth_service::AuthService;
 
 #[utoipa::path(
@@ -12,21 +23,162 @@ use crate::services::auth_service::AuthService;
     tag = "Authentication",
     request_body(
         content = AuthRequestDto,
-        description = "User/password or client credentials used to request a token",
+        description = "User/password, client credentials, passkey assertion or device code used to request a token",
         content_type = "application/json"
     ),
+    params(
+        ("DPoP" = Option<String>, Header, description = "DPoP proof JWT; binds the issued token to the proof's key")
+    ),
     responses(
         (status = 200, description = "Token generated", body = TokenResponseDto),
-        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 400, description = "Device code pending, polled too fast, expired or denied; RFC 6749 `error` body", body = OAuthErrorResponse),
+        (status = 401, description = "Unauthorized or invalid DPoP proof", body = ErrorResponse),
         (status = 500, description = "Internal server error", body = ErrorResponse)
     )
 )]
 pub async fn generate_token<S: AuthService + Send + Sync>(
     service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
     request: AuthRequestDto,
 ) -> Result<impl warp::Reply, warp::Rejection> {
-    match service.generate_token(request).await {
+    let subject = request.subject().to_string();
+    let client_ip = context.client_ip.clone();
+    let result = service.generate_token(request, context).await;
+    let (action, outcome) = match result {
+        Ok(_) => (AuditAction::TokenIssued, AuditOutcome::Success),
+        Err(_) => (AuditAction::LoginFailed, AuditOutcome::Failure),
+    };
+    // A device polling for its code is not a failed login.
+    let polling = matches!(
+        &result,
+        Err(ApiError::DeviceGrant(failure, _))
+            if matches!(failure.code, ErrorCodes::AuthorizationPending | ErrorCodes::SlowDown)
+    );
+    if !polling {
+        audit
+            .record(AuditEvent {
+                action,
+                outcome,
+                actor: Some(subject),
+                client_ip,
+                target: None,
+            })
+            .await;
+    }
+
+    match result {
         Ok(token) => Ok(warp::reply::json(&token)),
         Err(e) => Err(warp::reject::custom(e)),
     }
 }
+
+fn with_cookies(mut response: warp::reply::Response, cookies: Vec<String>) -> warp::reply::Response {
+    for cookie in cookies {
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "Authentication",
+    request_body(
+        content = AuthRequestDto,
+        description = "Credentials used to open a cookie based browser session",
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Session cookie set; send `csrf_token` back in the `X-CSRF-Token` header on state-changing requests", body = LoginResponseDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Cookie sessions are disabled", body = ErrorResponse)
+    )
+)]
+pub async fn login<S: AuthService + Send + Sync>(
+    config: Arc<Config>,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    request: AuthRequestDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let subject = request.subject().to_string();
+    let client_ip = context.client_ip.clone();
+    let result = service.create_browser_session(request, context).await;
+    let (action, outcome) = match result {
+        Ok(_) => (AuditAction::TokenIssued, AuditOutcome::Success),
+        Err(_) => (AuditAction::LoginFailed, AuditOutcome::Failure),
+    };
+    audit
+        .record(AuditEvent {
+            action,
+            outcome,
+            actor: Some(subject),
+            client_ip,
+            target: Some("cookie_session".to_string()),
+        })
+        .await;
+
+    let session = result.map_err(warp::reject::custom)?;
+    let max_age = (session.expires_at - chrono::Utc::now()).num_seconds().max(0);
+    let response = warp::reply::json(&LoginResponseDto {
+        csrf_token: session.csrf_token.clone(),
+        expires_at: session.expires_at,
+    })
+    .into_response();
+    Ok(with_cookies(
+        response,
+        vec![
+            build_cookie(&config, SESSION_COOKIE, &session.token, true, max_age),
+            build_cookie(&config, CSRF_COOKIE, &session.csrf_token, false, max_age),
+        ],
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/logout",
+    tag = "Authentication",
+    security(("api_key" = [])),
+    responses(
+        (status = 204, description = "Current session revoked and cookies cleared"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse)
+    )
+)]
+pub async fn logout<S: AuthService + Send + Sync>(
+    config: Arc<Config>,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = service
+        .revoke_session(&principal.subject, &principal.grant_type, &principal.session_id)
+        .await;
+    audit
+        .record(AuditEvent {
+            action: AuditAction::SessionRevoked,
+            outcome: if result.is_ok() {
+                AuditOutcome::Success
+            } else {
+                AuditOutcome::Failure
+            },
+            actor: Some(principal.subject.clone()),
+            client_ip: context.client_ip,
+            target: Some(format!("session:{}", principal.session_id)),
+        })
+        .await;
+    result.map_err(warp::reject::custom)?;
+
+    let response = StatusCode::NO_CONTENT.into_response();
+    Ok(with_cookies(
+        response,
+        vec![
+            build_cookie(&config, SESSION_COOKIE, "", true, 0),
+            build_cookie(&config, CSRF_COOKIE, "", false, 0),
+        ],
+    ))
+}
@@ -1,10 +1,13 @@
 use warp::reply::with_status;
 
-use crate::models::message_model::{CreateMessageModelDto, MessageResponseDto};
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+use crate::models::message_model::{CreateMessageModelDto, MessageResponseDto};
+use crate::models::session_model::ClientContext;
+use crate::services::audit_service::AuditService;
 use crate::services::base_service::BaseService;
 use std::sync::Arc;
-#[allow(unused_imports)]
-use crate::models::error_response::ErrorResponse;
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
 
 #[utoipa::path(
     get,
@@ -45,8 +48,19 @@ pub async fn handle_get_messages<S: BaseService + Send + Sync>(
 pub async fn handle_create_message<S: BaseService + Send + Sync>(
     dto: CreateMessageModelDto,
     service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let message = service.create_message(dto).await;
+    audit
+        .record(AuditEvent {
+            action: AuditAction::MessageCreated,
+            outcome: AuditOutcome::Success,
+            actor: None,
+            client_ip: context.client_ip,
+            target: Some(format!("message:{}", message.id)),
+        })
+        .await;
     let response = MessageResponseDto {
         id: message.id,
         content: message.content,
@@ -60,11 +74,11 @@ pub async fn handle_create_message<S: BaseService + Send + Sync>(
     tag = "Search messages",
     responses(
         (status = 200, body = Vec<MessageResponseDto>),
-        (status = 400, description="Bad request", body = ErrorResponse),
+        (status = 400, description="Bad request. Error codes: 1002 NotEmpty, 1003 MaxSize", body = ErrorResponse),
         (status = 500, body = ErrorResponse)
     ),
     params(
-        ("message"= String, description = "Query to search for")
+        ("message"= String, description = "Query to search for", min_length = 1, max_length = 32)
     )
 )]
 pub async fn handle_search_messages<S: BaseService + Send + Sync>(
@@ -80,4 +94,4 @@ pub async fn handle_search_messages<S: BaseService + Send + Sync>(
         })
         .collect();
     Ok(warp::reply::json(&response))
-}
\ No newline at end of file
+}
\ No newline at end of file
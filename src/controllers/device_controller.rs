@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use warp::http::StatusCode;
+
+use crate::errors::ApiError;
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+use crate::models::device_model::{
+    DeviceAuthorizationRequestDto, DeviceAuthorizationResponseDto, DeviceVerificationDto, DeviceVerificationQuery,
+};
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::session_model::{ClientContext, Principal};
+use crate::services::audit_service::AuditService;
+use crate::services::device_service::DeviceService;
+
+/// Approval form for signed-in browser users. It posts to the same path with
+/// the session cookie and the CSRF token read from its cookie, so it only
+/// works with `SESSION_COOKIES=true`; without cookie sessions, users approve
+/// through `POST /auth/device/verify` with a bearer token instead.
+const VERIFICATION_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>Connect a device</title>
+</head>
+<body>
+    <h1>Connect a device</h1>
+    <p>Enter the code shown on your device.</p>
+    <form id="device">
+        <input id="user_code" name="user_code" autocomplete="off" required value="{{user_code}}">
+        <button type="submit" value="approve">Approve</button>
+        <button type="submit" value="deny">Deny</button>
+    </form>
+    <p id="result" role="status"></p>
+    <script>
+        document.getElementById("device").addEventListener("submit", async (event) => {
+            event.preventDefault();
+            const csrf = document.cookie.split("; ").find((c) => c.startsWith("csrf_token="));
+            const response = await fetch(window.location.pathname, {
+                method: "POST",
+                credentials: "same-origin",
+                headers: {
+                    "Content-Type": "application/json",
+                    "X-CSRF-Token": csrf ? csrf.split("=")[1] : ""
+                },
+                body: JSON.stringify({
+                    user_code: document.getElementById("user_code").value,
+                    approve: event.submitter.value === "approve"
+                })
+            });
+            const result = document.getElementById("result");
+            if (response.status === 204) {
+                result.textContent = "Done. You can return to your device.";
+            } else if (response.status === 401) {
+                result.textContent = "Sign in with a session cookie first, then reload this page.";
+            } else {
+                result.textContent = (await response.json()).title;
+            }
+        });
+    </script>
+</body>
+</html>
+"#;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/device",
+    tag = "Device Authorization",
+    request_body(content = DeviceAuthorizationRequestDto, description = "Client starting the device flow", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Codes to show the user; poll /auth/token with the device_code grant", body = DeviceAuthorizationResponseDto),
+        (status = 401, description = "Unknown client", body = ErrorResponse),
+        (status = 400, description = "Invalid scope", body = ErrorResponse)
+    )
+)]
+pub async fn authorize<D: DeviceService + Send + Sync>(
+    device: Arc<D>,
+    request: DeviceAuthorizationRequestDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let response = device
+        .authorize(&request.client_id, request.scope)
+        .await
+        .map_err(warp::reject::custom)?;
+    Ok(warp::reply::json(&response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/device/verify",
+    tag = "Device Authorization",
+    params(DeviceVerificationQuery),
+    responses(
+        (status = 200, description = "HTML page where a user signed in with a session cookie (`SESSION_COOKIES=true`) approves or denies a user code; it cannot be framed", body = String, content_type = "text/html")
+    )
+)]
+pub async fn verification_page(query: DeviceVerificationQuery) -> Result<impl warp::Reply, warp::Rejection> {
+    let user_code: String = query
+        .user_code
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    // The code comes prefilled from the link, so a framing page must not be
+    // able to trick the user into clicking Approve.
+    let page = warp::reply::html(VERIFICATION_PAGE.replace("{{user_code}}", &user_code));
+    let page = warp::reply::with_header(page, "X-Frame-Options", "DENY");
+    Ok(warp::reply::with_header(page, "Content-Security-Policy", "frame-ancestors 'none'"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/device/verify",
+    tag = "Device Authorization",
+    security(("api_key" = [])),
+    request_body(content = DeviceVerificationDto, description = "User code shown on the device and the user's decision", content_type = "application/json"),
+    responses(
+        (status = 204, description = "Decision recorded"),
+        (status = 400, description = "The user code is invalid, expired or already used", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only users can approve devices", body = ErrorResponse)
+    )
+)]
+pub async fn verify<D: DeviceService + Send + Sync>(
+    principal: Principal,
+    device: Arc<D>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    request: DeviceVerificationDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if principal.grant_type != "user" {
+        return Err(warp::reject::custom(ApiError::Forbidden));
+    }
+    let result = device
+        .verify(&request.user_code, &principal.subject, request.approve)
+        .await;
+    audit
+        .record(AuditEvent {
+            action: if request.approve {
+                AuditAction::DeviceApproved
+            } else {
+                AuditAction::DeviceDenied
+            },
+            outcome: if result.is_ok() {
+                AuditOutcome::Success
+            } else {
+                AuditOutcome::Failure
+            },
+            actor: Some(principal.subject),
+            client_ip: context.client_ip,
+            target: Some(format!("device:{}", request.user_code)),
+        })
+        .await;
+    result.map_err(warp::reject::custom)?;
+    Ok(StatusCode::NO_CONTENT)
+}
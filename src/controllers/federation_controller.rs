@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use warp::http::{
+    header::{LOCATION, SET_COOKIE},
+    HeaderValue, StatusCode,
+};
+use warp::Reply;
+
+use crate::config::Config;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::federation_model::{LinkRedirectDto, OidcCallbackQuery};
+use crate::models::session_model::{ClientContext, Principal};
+#[allow(unused_imports)]
+use crate::models::token_model::TokenResponseDto;
+use crate::services::audit_service::AuditService;
+use crate::services::auth_service::AuthService;
+use crate::services::federation_service::{FederationService, LOGIN_BINDING_COOKIE, LOGIN_STATE_TTL_SECONDS};
+
+/// The cookie binding a login to the browser, scoped to the provider's
+/// callback and kept as long as the login state. It is `SameSite=Lax`
+/// whatever the session cookies use, since the provider redirects back with
+/// a cross-site navigation that `Strict` cookies are not sent on.
+fn binding_cookie(config: &Config, provider: &str, value: &str) -> Result<HeaderValue, warp::Rejection> {
+    let mut cookie = format!(
+        "{}={}; Path=/{}/auth/oidc/{}/callback; Max-Age={}; HttpOnly; SameSite=Lax",
+        LOGIN_BINDING_COOKIE,
+        value,
+        config.api_base.trim_matches('/'),
+        provider,
+        LOGIN_STATE_TTL_SECONDS
+    );
+    if config.session_cookie_secure {
+        cookie.push_str("; Secure");
+    }
+    HeaderValue::from_str(&cookie).map_err(|_| warp::reject::custom(ApiError::InternalServerError))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/oidc/{provider}/login",
+    tag = "Federation",
+    params(("provider" = String, Path, description = "Configured identity provider name")),
+    responses(
+        (status = 302, description = "Redirect to the provider's authorization endpoint; sets the `oidc_login` cookie the callback checks"),
+        (status = 404, description = "Unknown identity provider", body = ErrorResponse),
+        (status = 502, description = "The provider's discovery document could not be fetched", body = ErrorResponse)
+    )
+)]
+pub async fn login<F: FederationService + Send + Sync>(
+    provider: String,
+    federation: Arc<F>,
+    config: Arc<Config>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let redirect = federation.login_url(&provider, None).await.map_err(warp::reject::custom)?;
+    let mut response = warp::reply::with_status(warp::reply(), StatusCode::FOUND).into_response();
+    let location = redirect
+        .url
+        .parse()
+        .map_err(|_| warp::reject::custom(ApiError::InternalServerError))?;
+    response.headers_mut().insert(LOCATION, location);
+    response
+        .headers_mut()
+        .insert(SET_COOKIE, binding_cookie(&config, &provider, &redirect.binding)?);
+    Ok(response)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/oidc/{provider}/link",
+    tag = "Federation",
+    security(("api_key" = [])),
+    params(("provider" = String, Path, description = "Configured identity provider name")),
+    responses(
+        (status = 200, description = "Provider URL to open; the callback links the provider account to the caller and sets the `oidc_login` cookie", body = LinkRedirectDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only users can link providers", body = ErrorResponse),
+        (status = 404, description = "Unknown identity provider", body = ErrorResponse),
+        (status = 502, description = "The provider's discovery document could not be fetched", body = ErrorResponse)
+    )
+)]
+pub async fn link<F: FederationService + Send + Sync>(
+    provider: String,
+    principal: Principal,
+    federation: Arc<F>,
+    config: Arc<Config>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    if principal.grant_type != "user" {
+        return Err(warp::reject::custom(ApiError::Forbidden));
+    }
+    let redirect = federation
+        .login_url(&provider, Some(&principal.subject))
+        .await
+        .map_err(warp::reject::custom)?;
+    let mut response = warp::reply::json(&LinkRedirectDto { url: redirect.url }).into_response();
+    response
+        .headers_mut()
+        .insert(SET_COOKIE, binding_cookie(&config, &provider, &redirect.binding)?);
+    Ok(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/oidc/{provider}/callback",
+    tag = "Federation",
+    params(
+        ("provider" = String, Path, description = "Configured identity provider name"),
+        OidcCallbackQuery
+    ),
+    responses(
+        (status = 200, description = "Login completed; token issued for the linked user", body = TokenResponseDto),
+        (status = 400, description = "Missing, unknown or expired state, or a browser that did not start the login", body = ErrorResponse),
+        (status = 401, description = "The provider refused the login or its ID token is invalid", body = ErrorResponse),
+        (status = 404, description = "Unknown identity provider", body = ErrorResponse),
+        (status = 409, description = "The email belongs to an existing user who must link the provider, or the provider account is linked to another user", body = ErrorResponse),
+        (status = 502, description = "The provider could not be reached", body = ErrorResponse)
+    )
+)]
+pub async fn callback<F: FederationService + Send + Sync, S: AuthService + Send + Sync>(
+    provider: String,
+    query: OidcCallbackQuery,
+    binding: Option<String>,
+    federation: Arc<F>,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let client_ip = context.client_ip.clone();
+    let result = match (&query.error, &query.code, &query.state) {
+        (Some(error), _, _) => {
+            eprintln!(
+                "identity provider {} refused the login: {} {}",
+                provider,
+                error,
+                query.error_description.as_deref().unwrap_or_default()
+            );
+            Err(ApiError::ErrorCode(ErrorCodes::FederatedLoginFailed))
+        }
+        (None, Some(code), Some(state)) => match federation
+            .complete_login(&provider, code, state, binding.as_deref())
+            .await
+        {
+            Ok(user) => service
+                .issue_for_user(&user.username, context)
+                .await
+                .map(|token| (user.username, token)),
+            Err(e) => Err(e),
+        },
+        _ => Err(ApiError::ErrorCode(ErrorCodes::InvalidLoginState)),
+    };
+    let (action, outcome) = match result {
+        Ok(_) => (AuditAction::TokenIssued, AuditOutcome::Success),
+        Err(_) => (AuditAction::LoginFailed, AuditOutcome::Failure),
+    };
+    audit
+        .record(AuditEvent {
+            action,
+            outcome,
+            actor: result.as_ref().ok().map(|(username, _)| username.clone()),
+            client_ip,
+            target: Some(format!("oidc:{}", provider)),
+        })
+        .await;
+
+    let (_, token) = result.map_err(warp::reject::custom)?;
+    Ok(warp::reply::json(&token))
+}
@@ -1,41 +1,238 @@
+pub mod account_controller;
+pub mod audit_controller;
 pub mod auth_controller;
 pub mod base_controller;
+pub mod device_controller;
+pub mod federation_controller;
+pub mod oidc_controller;
 pub mod protected_controller;
+pub mod scim_controller;
+pub mod session_controller;
+pub mod webauthn_controller;
 
 use std::convert::Infallible;
 use std::sync::Arc;
-use warp::{Filter, Rejection};
+use std::time::Duration;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Rejection, Reply};
 
 use crate::config::Config;
-use crate::errors::ApiError;
+use crate::middleware::auth::{
+    authenticate, authorize, client_context, require_admin, require_scope, require_signature, signed_json,
+    session_cookies_enabled, token_request_context,
+};
+use crate::middleware::body::json_body;
+use crate::middleware::validator::validated_json;
+use crate::models::account_model::{ForgotPasswordDto, ResetPasswordDto, VerifyEmailDto};
+use crate::models::audit_model::AuditQuery;
+use crate::models::device_model::DeviceVerificationQuery;
+use crate::models::federation_model::OidcCallbackQuery;
+use crate::models::scim_model::ScimListQuery;
+use crate::repositories::action_token_repository::InMemoryActionTokenRepository;
+use crate::repositories::audit_repository::{InMemoryAuditRepository, JsonlAuditRepository};
 use crate::repositories::base_repository::InMemoryBaseRepository;
-use crate::repositories::credentials_repository::InMemoryCredentialRepository;
+use crate::repositories::credentials_repository::{CredentialRepository, InMemoryCredentialRepository};
+use crate::repositories::file_credentials_repository::FileCredentialRepository;
+use crate::repositories::group_repository::InMemoryGroupRepository;
 use crate::repositories::token_repository::InMemoryTokenRepository;
 use crate::router::Router;
+use crate::services::account_service::{AccountService, AccountServiceImpl};
+use crate::services::audit_service::{AuditService, AuditServiceImpl};
 use crate::services::auth_service::{AuthService, AuthServiceImpl};
 use crate::services::base_service::BaseServiceImpl;
+use crate::services::device_service::{DeviceService, DeviceServiceImpl};
+use crate::services::federation_service::{FederationService, FederationServiceImpl, LOGIN_BINDING_COOKIE};
+use crate::services::jwt_service::{Es256JwtService, JwtService};
+use crate::services::mail_service::{LogMailer, Mailer, SmtpMailer};
+use crate::services::scim_service::{ScimService, ScimServiceImpl, SCIM_SCOPE};
+use crate::services::webauthn_service::{WebauthnService, WebauthnServiceImpl};
+use crate::validators::scim_validator::{validate_new_scim_user, validate_scim_group};
 
+/// Builds every route, or returns the errors found in the mail settings and
+/// the credential files.
 pub fn routes(
     config: Arc<Config>,
-) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+) -> Result<impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone, Vec<String>> {
+    let mailer = mailer(&config)?;
+    routes_with_mailer(config, mailer)
+}
+
+/// `routes` sending mail through `mailer` instead of the one configured by
+/// `SMTP_*`.
+pub fn routes_with_mailer(
+    config: Arc<Config>,
+    mailer: Arc<dyn Mailer>,
+) -> Result<BoxedFilter<(warp::reply::Response,)>, Vec<String>> {
+    if config.credentials_users_file.is_none() && config.credentials_clients_file.is_none() {
+        let credential_repository = InMemoryCredentialRepository::new();
+        if let Some(secret) = &config.scim_client_secret {
+            credential_repository.add_client("scim", secret, &[SCIM_SCOPE]);
+        }
+        return Ok(build_routes(config, credential_repository, mailer));
+    }
+    let credential_repository = FileCredentialRepository::open(
+        config.credentials_users_file.as_deref(),
+        config.credentials_clients_file.as_deref(),
+    )?;
+    credential_repository.watch(Duration::from_secs(config.credentials_reload_seconds));
+    Ok(build_routes(config, credential_repository, mailer))
+}
+
+/// The SMTP relay when `SMTP_HOST` is set, or a `LogMailer` otherwise.
+fn mailer(config: &Config) -> Result<Arc<dyn Mailer>, Vec<String>> {
+    let Some(host) = &config.smtp_host else {
+        return Ok(Arc::new(LogMailer));
+    };
+    let mailer = SmtpMailer::new(
+        host,
+        config.smtp_port,
+        &config.smtp_tls,
+        config
+            .smtp_username
+            .clone()
+            .map(|username| (username, config.smtp_password.clone().unwrap_or_default())),
+        &config.mail_from,
+    )
+    .map_err(|e| vec![format!("invalid SMTP configuration: {}", e)])?;
+    Ok(Arc::new(mailer))
+}
+
+/// Wires every service on top of `credential_repository`. The routes are
+/// boxed so stores of different types yield the same filter.
+fn build_routes<C: CredentialRepository + Clone + 'static>(
+    config: Arc<Config>,
+    credential_repository: C,
+    mailer: Arc<dyn Mailer>,
+) -> BoxedFilter<(warp::reply::Response,)> {
+    let audit_service: Arc<dyn AuditService> = match &config.audit_log_path {
+        Some(path) => Arc::new(AuditServiceImpl::new(JsonlAuditRepository::new(path))),
+        None => Arc::new(AuditServiceImpl::new(InMemoryAuditRepository::new())),
+    };
+
     let base_repository = InMemoryBaseRepository::new();
     let base_service = BaseServiceImpl::new(base_repository);
-    let base_router = Router::new(base_service, Arc::clone(&config)).routes();
+    let base_router = Router::new(base_service, Arc::clone(&audit_service), Arc::clone(&config)).routes();
 
     let token_repository = InMemoryTokenRepository::new();
-    let credential_repository = InMemoryCredentialRepository::new();
-    let auth_service = Arc::new(AuthServiceImpl::new(
-        token_repository,
-        credential_repository,
+    let scim_credentials = credential_repository.clone();
+    let account_service = Arc::new(AccountServiceImpl::new(
+        credential_repository.clone(),
+        InMemoryActionTokenRepository::new(),
+        mailer,
+        format!("{}/{}", config.issuer, config.api_base.trim_matches('/')),
+    )
+    .with_password_policy(config.password_policy.clone()));
+    let webauthn_service = Arc::new(WebauthnServiceImpl::new(
+        credential_repository.clone(),
+        config.webauthn_rp_id.clone(),
+        config.webauthn_rp_name.clone(),
+        config.webauthn_origin.clone(),
+    ));
+    let mut scim_service = ScimServiceImpl::new(
+        credential_repository.clone(),
+        InMemoryGroupRepository::new(),
+        format!("{}/scim/v2", config.issuer),
+    )
+    .with_password_policy(config.password_policy.clone());
+    let federation_service = Arc::new(FederationServiceImpl::new(
+        credential_repository.clone(),
+        config.oidc_providers.clone(),
+        format!("{}/{}/auth/oidc", config.issuer, config.api_base.trim_matches('/')),
+    )
+    .with_timeout(Duration::from_secs(config.oidc_timeout_seconds)));
+    let device_service = Arc::new(DeviceServiceImpl::new(
+        credential_repository.clone(),
+        format!("{}/{}/auth/device/verify", config.issuer, config.api_base.trim_matches('/')),
+        config.device_code_ttl_seconds,
+        config.device_poll_interval_seconds,
     ));
-    let auth_routes = build_auth_routes(Arc::clone(&auth_service), Arc::clone(&config));
+    let jwt_service: Arc<dyn JwtService> = Arc::new(Es256JwtService::new());
+    let mut auth_service = AuthServiceImpl::new(token_repository, credential_repository)
+        .with_id_tokens(
+            Arc::clone(&jwt_service),
+            config.issuer.clone(),
+            config.token_audience.clone(),
+        )
+        .with_signature_max_skew(config.signature_max_skew_seconds)
+        .with_webauthn(webauthn_service.clone())
+        .with_device_flow(device_service.clone());
+    if config.email_verification {
+        auth_service = auth_service.with_email_verification();
+        scim_service = scim_service.with_email_verification(account_service.clone());
+    }
+    let scim_service = Arc::new(scim_service);
+    let auth_service = Arc::new(auth_service);
+    let auth_routes = build_auth_routes(
+        Arc::clone(&auth_service),
+        Arc::clone(&audit_service),
+        Arc::clone(&config),
+    );
     let protected_routes = build_protected_routes(Arc::clone(&auth_service), Arc::clone(&config));
+    let admin_routes = build_admin_routes(
+        Arc::clone(&auth_service),
+        Arc::clone(&audit_service),
+        Arc::clone(&config),
+    );
+    let oidc_routes = build_oidc_routes(
+        Arc::clone(&auth_service),
+        Arc::clone(&audit_service),
+        Arc::clone(&jwt_service),
+        Arc::clone(&config),
+    );
+
+    let account_routes = build_account_routes(
+        Arc::clone(&auth_service),
+        account_service,
+        Arc::clone(&audit_service),
+        Arc::clone(&config),
+    );
+
+    let webauthn_routes = build_webauthn_routes(
+        Arc::clone(&auth_service),
+        webauthn_service,
+        Arc::clone(&audit_service),
+        Arc::clone(&config),
+    );
+
+    let device_routes = build_device_routes(
+        Arc::clone(&auth_service),
+        device_service,
+        Arc::clone(&audit_service),
+        Arc::clone(&config),
+    );
 
-    base_router.or(auth_routes).or(protected_routes)
+    let federation_routes = build_federation_routes(
+        Arc::clone(&auth_service),
+        federation_service,
+        Arc::clone(&audit_service),
+        Arc::clone(&config),
+    );
+
+    let scim_routes = build_scim_routes(
+        Arc::clone(&auth_service),
+        scim_service,
+        scim_credentials,
+        Arc::clone(&audit_service),
+        Arc::clone(&config),
+    );
+
+    base_router
+        .or(auth_routes)
+        .or(protected_routes)
+        .or(admin_routes)
+        .or(oidc_routes)
+        .or(account_routes)
+        .or(webauthn_routes)
+        .or(device_routes)
+        .or(federation_routes)
+        .or(scim_routes)
+        .map(Reply::into_response)
+        .boxed()
 }
 
 fn build_auth_routes<S: AuthService + Send + Sync + 'static>(
     service: Arc<S>,
+    audit: Arc<dyn AuditService>,
     config: Arc<Config>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
     let api_base = config.api_base.trim_matches('/').to_string();
@@ -52,10 +249,73 @@ fn build_auth_routes<S: AuthService + Send + Sync + 'static>(
         .and(warp::path("token"))
         .and(warp::path::end())
         .and(with_auth_service(Arc::clone(&service)))
-        .and(warp::body::json())
+        .and(with_audit(Arc::clone(&audit)))
+        .and(token_request_context(Arc::clone(&service), Arc::clone(&config)))
+        .and(json_body(config.body_limits))
         .and_then(auth_controller::generate_token);
 
+    let list_sessions = warp::get()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("sessions"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and_then(session_controller::list_sessions);
+
+    let revoke_session = warp::delete()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("sessions"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(session_controller::revoke_session);
+
+    let logout_all = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("logout-all"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(session_controller::logout_all);
+
+    let login = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("login"))
+        .and(warp::path::end())
+        .and(session_cookies_enabled(Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(json_body(config.body_limits))
+        .and_then(auth_controller::login);
+
+    let logout = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("logout"))
+        .and(warp::path::end())
+        .and(with_config(Arc::clone(&config)))
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(auth_controller::logout);
+
     auth_token
+        .or(login)
+        .or(logout)
+        .or(list_sessions)
+        .or(revoke_session)
+        .or(logout_all)
 }
 
 fn build_protected_routes<S: AuthService + Send + Sync + 'static>(
@@ -70,12 +330,567 @@ fn build_protected_routes<S: AuthService + Send + Sync + 'static>(
         api_path = api_path.and(warp::path(seg.clone())).boxed();
     }
 
-    warp::get()
+    let protected = warp::get()
+        .and(api_path.clone())
+        .and(warp::path("protected"))
+        .and(warp::path::end())
+        .and(
+            authorize(Arc::clone(&service), Arc::clone(&config))
+                .or(require_signature(Arc::clone(&service), config.body_limits).map(|_, _| ()).untuple_one())
+                .unify(),
+        )
+        .and_then(protected_controller::protected_endpoint);
+
+    let signed_echo = warp::post()
         .and(api_path.clone())
         .and(warp::path("protected"))
         .and(warp::path::end())
-        .and(authorize(Arc::clone(&service)))
-        .and_then(protected_controller::protected_endpoint)
+        .and(signed_json(Arc::clone(&service), config.body_limits))
+        .and_then(protected_controller::signed_echo);
+
+    protected.or(signed_echo)
+}
+
+fn build_admin_routes<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let api_base = config.api_base.trim_matches('/').to_string();
+    let segments: Vec<String> = api_base.split('/').map(|s| s.to_string()).collect();
+
+    let mut api_path = warp::path(segments[0].clone()).boxed();
+    for seg in &segments[1..] {
+        api_path = api_path.and(warp::path(seg.clone())).boxed();
+    }
+
+    let admin_users = api_path
+        .clone()
+        .and(warp::path("admin"))
+        .and(warp::path("users"))
+        .and(warp::path::param::<String>());
+
+    let list_sessions = warp::get()
+        .and(admin_users.clone())
+        .and(warp::path("sessions"))
+        .and(warp::path::end())
+        .and(require_admin(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and_then(session_controller::admin_list_sessions);
+
+    let revoke_session = warp::delete()
+        .and(admin_users.clone())
+        .and(warp::path("sessions"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(require_admin(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(session_controller::admin_revoke_session);
+
+    let logout_all = warp::post()
+        .and(admin_users)
+        .and(warp::path("logout-all"))
+        .and(warp::path::end())
+        .and(require_admin(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(session_controller::admin_logout_all);
+
+    let query_audit = warp::get()
+        .and(api_path.clone())
+        .and(warp::path("admin"))
+        .and(warp::path("audit"))
+        .and(warp::path::end())
+        .and(require_admin(Arc::clone(&service), Arc::clone(&config)))
+        .and(warp::query::<AuditQuery>())
+        .and(with_audit(Arc::clone(&audit)))
+        .and_then(audit_controller::query_audit_log);
+
+    let verify_audit = warp::get()
+        .and(api_path.clone())
+        .and(warp::path("admin"))
+        .and(warp::path("audit"))
+        .and(warp::path("verify"))
+        .and(warp::path::end())
+        .and(require_admin(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and_then(audit_controller::verify_audit_log);
+
+    list_sessions
+        .or(revoke_session)
+        .or(logout_all)
+        .or(query_audit)
+        .or(verify_audit)
+}
+
+fn build_oidc_routes<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    jwt: Arc<dyn JwtService>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let api_base = config.api_base.trim_matches('/').to_string();
+    let segments: Vec<String> = api_base.split('/').map(|s| s.to_string()).collect();
+
+    let mut api_path = warp::path(segments[0].clone()).boxed();
+    for seg in &segments[1..] {
+        api_path = api_path.and(warp::path(seg.clone())).boxed();
+    }
+
+    let openid_configuration = warp::get()
+        .and(warp::path(".well-known"))
+        .and(warp::path("openid-configuration"))
+        .and(warp::path::end())
+        .and(with_config(Arc::clone(&config)))
+        .and_then(oidc_controller::openid_configuration);
+
+    let jwks = warp::get()
+        .and(warp::path(".well-known"))
+        .and(warp::path("jwks.json"))
+        .and(warp::path::end())
+        .and(with_jwt(Arc::clone(&jwt)))
+        .and_then(oidc_controller::jwks);
+
+    let revoke = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("revoke"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(json_body(config.body_limits))
+        .and_then(oidc_controller::revoke_token);
+
+    let introspect = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("introspect"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(json_body(config.body_limits))
+        .and_then(oidc_controller::introspect_token);
+
+    let rotate_keys = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("admin"))
+        .and(warp::path("keys"))
+        .and(warp::path("rotate"))
+        .and(warp::path::end())
+        .and(require_admin(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_jwt(Arc::clone(&jwt)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(oidc_controller::rotate_keys);
+
+    openid_configuration
+        .or(jwks)
+        .or(revoke)
+        .or(introspect)
+        .or(rotate_keys)
+}
+
+fn build_account_routes<S: AuthService + Send + Sync + 'static, A: AccountService + Send + Sync + 'static>(
+    service: Arc<S>,
+    accounts: Arc<A>,
+    audit: Arc<dyn AuditService>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let api_base = config.api_base.trim_matches('/').to_string();
+    let segments: Vec<String> = api_base.split('/').map(|s| s.to_string()).collect();
+
+    let mut api_path = warp::path(segments[0].clone()).boxed();
+    for seg in &segments[1..] {
+        api_path = api_path.and(warp::path(seg.clone())).boxed();
+    }
+
+    let forgot_password = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("password"))
+        .and(warp::path("forgot"))
+        .and(warp::path::end())
+        .and(validated_json::<ForgotPasswordDto>(config.body_limits))
+        .and(with_account_service(Arc::clone(&accounts)))
+        .and_then(account_controller::forgot_password);
+
+    let reset_password = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("password"))
+        .and(warp::path("reset"))
+        .and(warp::path::end())
+        .and(validated_json::<ResetPasswordDto>(config.body_limits))
+        .and(with_account_service(Arc::clone(&accounts)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(account_controller::reset_password);
+
+    let verify_email = warp::post()
+        .and(api_path.clone())
+        .and(warp::path("auth"))
+        .and(warp::path("email"))
+        .and(warp::path("verify"))
+        .and(warp::path::end())
+        .and(validated_json::<VerifyEmailDto>(config.body_limits))
+        .and(with_account_service(Arc::clone(&accounts)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(account_controller::verify_email);
+
+    forgot_password.or(reset_password).or(verify_email)
+}
+
+fn build_webauthn_routes<S: AuthService + Send + Sync + 'static, W: WebauthnService + Send + Sync + 'static>(
+    service: Arc<S>,
+    webauthn: Arc<W>,
+    audit: Arc<dyn AuditService>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let api_base = config.api_base.trim_matches('/').to_string();
+    let segments: Vec<String> = api_base.split('/').map(|s| s.to_string()).collect();
+
+    let mut api_path = warp::path(segments[0].clone()).boxed();
+    for seg in &segments[1..] {
+        api_path = api_path.and(warp::path(seg.clone())).boxed();
+    }
+    let webauthn_path = api_path.and(warp::path("auth")).and(warp::path("webauthn")).boxed();
+
+    let registration_options = warp::post()
+        .and(webauthn_path.clone())
+        .and(warp::path("register"))
+        .and(warp::path("options"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_webauthn_service(Arc::clone(&webauthn)))
+        .and_then(webauthn_controller::registration_options);
+
+    let register = warp::post()
+        .and(webauthn_path.clone())
+        .and(warp::path("register"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_webauthn_service(Arc::clone(&webauthn)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(json_body(config.body_limits))
+        .and_then(webauthn_controller::register);
+
+    let login_options = warp::post()
+        .and(webauthn_path.clone())
+        .and(warp::path("login"))
+        .and(warp::path("options"))
+        .and(warp::path::end())
+        .and(with_webauthn_service(Arc::clone(&webauthn)))
+        .and(json_body(config.body_limits))
+        .and_then(webauthn_controller::login_options);
+
+    registration_options.or(register).or(login_options)
+}
+
+fn build_device_routes<S: AuthService + Send + Sync + 'static, D: DeviceService + Send + Sync + 'static>(
+    service: Arc<S>,
+    device: Arc<D>,
+    audit: Arc<dyn AuditService>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let api_base = config.api_base.trim_matches('/').to_string();
+    let segments: Vec<String> = api_base.split('/').map(|s| s.to_string()).collect();
+
+    let mut api_path = warp::path(segments[0].clone()).boxed();
+    for seg in &segments[1..] {
+        api_path = api_path.and(warp::path(seg.clone())).boxed();
+    }
+    let device_path = api_path.and(warp::path("auth")).and(warp::path("device")).boxed();
+
+    let authorize = warp::post()
+        .and(device_path.clone())
+        .and(warp::path::end())
+        .and(with_device_service(Arc::clone(&device)))
+        .and(json_body(config.body_limits))
+        .and_then(device_controller::authorize);
+
+    let verification_page = warp::get()
+        .and(device_path.clone())
+        .and(warp::path("verify"))
+        .and(warp::path::end())
+        .and(warp::query::<DeviceVerificationQuery>())
+        .and_then(device_controller::verification_page);
+
+    let verify = warp::post()
+        .and(device_path.clone())
+        .and(warp::path("verify"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_device_service(Arc::clone(&device)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(json_body(config.body_limits))
+        .and_then(device_controller::verify);
+
+    authorize.or(verification_page).or(verify)
+}
+
+fn build_federation_routes<S: AuthService + Send + Sync + 'static, F: FederationService + Send + Sync + 'static>(
+    service: Arc<S>,
+    federation: Arc<F>,
+    audit: Arc<dyn AuditService>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let api_base = config.api_base.trim_matches('/').to_string();
+    let segments: Vec<String> = api_base.split('/').map(|s| s.to_string()).collect();
+
+    let mut api_path = warp::path(segments[0].clone()).boxed();
+    for seg in &segments[1..] {
+        api_path = api_path.and(warp::path(seg.clone())).boxed();
+    }
+    let provider_path = api_path
+        .and(warp::path("auth"))
+        .and(warp::path("oidc"))
+        .and(warp::path::param::<String>())
+        .boxed();
+
+    let login = warp::get()
+        .and(provider_path.clone())
+        .and(warp::path("login"))
+        .and(warp::path::end())
+        .and(with_federation_service(Arc::clone(&federation)))
+        .and(with_config(Arc::clone(&config)))
+        .and_then(federation_controller::login);
+
+    let link = warp::post()
+        .and(provider_path.clone())
+        .and(warp::path("link"))
+        .and(warp::path::end())
+        .and(authenticate(Arc::clone(&service), Arc::clone(&config)))
+        .and(with_federation_service(Arc::clone(&federation)))
+        .and(with_config(Arc::clone(&config)))
+        .and_then(federation_controller::link);
+
+    let callback = warp::get()
+        .and(provider_path.clone())
+        .and(warp::path("callback"))
+        .and(warp::path::end())
+        .and(warp::query::<OidcCallbackQuery>())
+        .and(warp::cookie::optional::<String>(LOGIN_BINDING_COOKIE))
+        .and(with_federation_service(Arc::clone(&federation)))
+        .and(with_auth_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(federation_controller::callback);
+
+    login.or(link).or(callback)
+}
+
+fn build_scim_routes<
+    S: AuthService + Send + Sync + 'static,
+    P: ScimService + Send + Sync + 'static,
+    C: CredentialRepository + Clone + 'static,
+>(
+    service: Arc<S>,
+    scim: Arc<P>,
+    credentials: C,
+    audit: Arc<dyn AuditService>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    let scim_path = warp::path("scim").and(warp::path("v2")).boxed();
+    let principal = require_scope(Arc::clone(&service), Arc::clone(&config), SCIM_SCOPE);
+
+    let service_provider_config = warp::get()
+        .and(scim_path.clone())
+        .and(warp::path("ServiceProviderConfig"))
+        .and(warp::path::end())
+        .and(with_config(Arc::clone(&config)))
+        .and_then(scim_controller::service_provider_config);
+
+    let schemas = warp::get()
+        .and(scim_path.clone())
+        .and(warp::path("Schemas"))
+        .and(warp::path::end())
+        .and_then(scim_controller::schemas);
+
+    let list_users = warp::get()
+        .and(scim_path.clone())
+        .and(warp::path("Users"))
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(warp::query::<ScimListQuery>())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and_then(scim_controller::list_users);
+
+    let get_user = warp::get()
+        .and(scim_path.clone())
+        .and(warp::path("Users"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and_then(scim_controller::get_user);
+
+    let create_user = warp::post()
+        .and(scim_path.clone())
+        .and(warp::path("Users"))
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(validate_new_scim_user(credentials.clone(), config.body_limits))
+        .and_then(scim_controller::create_user);
+
+    let replace_user = warp::put()
+        .and(scim_path.clone())
+        .and(warp::path("Users"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(json_body(config.body_limits))
+        .and_then(scim_controller::replace_user);
+
+    let patch_user = warp::patch()
+        .and(scim_path.clone())
+        .and(warp::path("Users"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(json_body(config.body_limits))
+        .and_then(scim_controller::patch_user);
+
+    let delete_user = warp::delete()
+        .and(scim_path.clone())
+        .and(warp::path("Users"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(scim_controller::delete_user);
+
+    let list_groups = warp::get()
+        .and(scim_path.clone())
+        .and(warp::path("Groups"))
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(warp::query::<ScimListQuery>())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and_then(scim_controller::list_groups);
+
+    let get_group = warp::get()
+        .and(scim_path.clone())
+        .and(warp::path("Groups"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and_then(scim_controller::get_group);
+
+    let create_group = warp::post()
+        .and(scim_path.clone())
+        .and(warp::path("Groups"))
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(validate_scim_group(credentials.clone(), config.body_limits))
+        .and_then(scim_controller::create_group);
+
+    let replace_group = warp::put()
+        .and(scim_path.clone())
+        .and(warp::path("Groups"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(validate_scim_group(credentials, config.body_limits))
+        .and_then(scim_controller::replace_group);
+
+    let patch_group = warp::patch()
+        .and(scim_path.clone())
+        .and(warp::path("Groups"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and(json_body(config.body_limits))
+        .and_then(scim_controller::patch_group);
+
+    let delete_group = warp::delete()
+        .and(scim_path.clone())
+        .and(warp::path("Groups"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(principal.clone())
+        .and(with_scim_service(Arc::clone(&scim)))
+        .and(with_audit(Arc::clone(&audit)))
+        .and(client_context())
+        .and_then(scim_controller::delete_group);
+
+    service_provider_config
+        .or(schemas)
+        .or(list_users)
+        .or(get_user)
+        .or(create_user)
+        .or(replace_user)
+        .or(patch_user)
+        .or(delete_user)
+        .or(list_groups)
+        .or(get_group)
+        .or(create_group)
+        .or(replace_group)
+        .or(patch_group)
+        .or(delete_group)
+}
+
+fn with_scim_service<P: ScimService + Send + Sync>(
+    service: Arc<P>,
+) -> impl Filter<Extract = (Arc<P>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&service))
+}
+
+fn with_account_service<A: AccountService + Send + Sync>(
+    service: Arc<A>,
+) -> impl Filter<Extract = (Arc<A>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&service))
+}
+
+fn with_webauthn_service<W: WebauthnService + Send + Sync>(
+    service: Arc<W>,
+) -> impl Filter<Extract = (Arc<W>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&service))
+}
+
+fn with_device_service<D: DeviceService + Send + Sync>(
+    service: Arc<D>,
+) -> impl Filter<Extract = (Arc<D>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&service))
+}
+
+fn with_federation_service<F: FederationService + Send + Sync>(
+    service: Arc<F>,
+) -> impl Filter<Extract = (Arc<F>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&service))
+}
+
+fn with_jwt(jwt: Arc<dyn JwtService>) -> impl Filter<Extract = (Arc<dyn JwtService>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&jwt))
 }
 
 fn with_auth_service<S: AuthService + Send + Sync + 'static>(
@@ -84,22 +899,12 @@ fn with_auth_service<S: AuthService + Send + Sync + 'static>(
     warp::any().map(move || Arc::clone(&service))
 }
 
-fn authorize<S: AuthService + Send + Sync + 'static>(
-    service: Arc<S>,
-) -> impl Filter<Extract = (), Error = Rejection> + Clone {
-    warp::header::optional::<String>("authorization")
-        .and_then(move |header: Option<String>| {
-            let svc = Arc::clone(&service);
-            async move {
-                if let Some(h) = header {
-                    if let Some(token) = h.strip_prefix("Bearer ") {
-                        if svc.validate_token(token).await {
-                            return Ok(());
-                        }
-                    }
-                }
-                Err(warp::reject::custom(ApiError::Unauthorized))
-            }
-        })
-        .untuple_one()
+fn with_config(config: Arc<Config>) -> impl Filter<Extract = (Arc<Config>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&config))
+}
+
+pub fn with_audit(
+    audit: Arc<dyn AuditService>,
+) -> impl Filter<Extract = (Arc<dyn AuditService>,), Error = Infallible> + Clone {
+    warp::any().map(move || Arc::clone(&audit))
 }
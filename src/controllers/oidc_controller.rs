@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use warp::http::StatusCode;
+
+use crate::config::Config;
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+use crate::models::device_model::DEVICE_CODE_GRANT;
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::oidc_model::{
+    DiscoveryDocumentDto, IntrospectionResponseDto, JwksDto, TokenRequestDto,
+};
+use crate::models::session_model::{ClientContext, Principal};
+use crate::services::audit_service::AuditService;
+use crate::errors::ApiError;
+use crate::services::auth_service::{AuthService, INTROSPECT_SCOPE, OPENID_SCOPE};
+use crate::services::jwt_service::{JwtService, ES256};
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// Whether `token` was issued to the caller itself.
+fn owns(principal: &Principal, token: &IntrospectionResponseDto) -> bool {
+    token.sub.as_deref() == Some(principal.subject.as_str())
+        && token.grant_type.as_deref() == Some(principal.grant_type.as_str())
+}
+
+pub fn discovery_document(config: &Config) -> DiscoveryDocumentDto {
+    let api = format!("{}/{}", config.issuer, config.api_base.trim_matches('/'));
+    DiscoveryDocumentDto {
+        issuer: config.issuer.clone(),
+        token_endpoint: format!("{}/auth/token", api),
+        revocation_endpoint: format!("{}/auth/revoke", api),
+        introspection_endpoint: format!("{}/auth/introspect", api),
+        device_authorization_endpoint: format!("{}/auth/device", api),
+        jwks_uri: format!("{}/.well-known/jwks.json", config.issuer),
+        grant_types_supported: strings(&["user", "client", "webauthn", DEVICE_CODE_GRANT]),
+        scopes_supported: strings(&[OPENID_SCOPE]),
+        response_types_supported: strings(&["token"]),
+        subject_types_supported: strings(&["public"]),
+        id_token_signing_alg_values_supported: strings(&[ES256]),
+        token_endpoint_auth_methods_supported: strings(&["client_secret_post"]),
+        claims_supported: strings(&["iss", "sub", "aud", "iat", "exp", "auth_time", "sid"]),
+        dpop_signing_alg_values_supported: strings(&[ES256]),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/openid-configuration",
+    tag = "OpenID Connect",
+    responses(
+        (status = 200, description = "OpenID Provider metadata", body = DiscoveryDocumentDto)
+    )
+)]
+pub async fn openid_configuration(config: Arc<Config>) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&discovery_document(&config)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "OpenID Connect",
+    responses(
+        (status = 200, description = "Current and previous public signing keys", body = JwksDto)
+    )
+)]
+pub async fn jwks(jwt: Arc<dyn JwtService>) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&jwt.jwks()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/revoke",
+    tag = "OpenID Connect",
+    security(("api_key" = [])),
+    request_body(content = TokenRequestDto, description = "Token to revoke", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Token revoked, or it was already invalid"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "The token belongs to someone else", body = ErrorResponse)
+    )
+)]
+pub async fn revoke_token<S: AuthService + Send + Sync>(
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    request: TokenRequestDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // RFC 7009 §2.1: only the holder of a token, or an admin, revokes it.
+    let token = service.introspect(&request.token).await;
+    if token.active && !owns(&principal, &token) && !principal.is_admin {
+        return Err(warp::reject::custom(ApiError::Forbidden));
+    }
+    let revoked = service.revoke_token(&request.token).await;
+    audit
+        .record(AuditEvent {
+            action: AuditAction::SessionRevoked,
+            outcome: if revoked {
+                AuditOutcome::Success
+            } else {
+                AuditOutcome::Failure
+            },
+            actor: Some(principal.subject),
+            client_ip: context.client_ip,
+            target: Some("token".to_string()),
+        })
+        .await;
+    // RFC 7009: unknown tokens are not an error for the caller.
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/introspect",
+    tag = "OpenID Connect",
+    security(("api_key" = [])),
+    request_body(content = TokenRequestDto, description = "Token to inspect", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Token metadata; only `active` is present for unknown tokens and for tokens of others unless the caller is an admin or a client with the `introspect` scope", body = IntrospectionResponseDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn introspect_token<S: AuthService + Send + Sync>(
+    principal: Principal,
+    service: Arc<S>,
+    request: TokenRequestDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    // RFC 7662 §2.1: tokens of others are only described to callers allowed
+    // to see them; to anyone else they look inactive.
+    let token = service.introspect(&request.token).await;
+    let privileged = principal.is_admin || (principal.grant_type == "client" && principal.has_scope(INTROSPECT_SCOPE));
+    if privileged || owns(&principal, &token) {
+        Ok(warp::reply::json(&token))
+    } else {
+        Ok(warp::reply::json(&IntrospectionResponseDto::default()))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/keys/rotate",
+    tag = "OpenID Connect",
+    security(("api_key" = [])),
+    responses(
+        (status = 204, description = "New signing key generated; the old one stays in the JWKS"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse)
+    )
+)]
+pub async fn rotate_keys(
+    principal: Principal,
+    jwt: Arc<dyn JwtService>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    jwt.rotate();
+    audit
+        .record(AuditEvent {
+            action: AuditAction::KeysRotated,
+            outcome: AuditOutcome::Success,
+            actor: Some(principal.subject),
+            client_ip: context.client_ip,
+            target: None,
+        })
+        .await;
+    Ok(StatusCode::NO_CONTENT)
+}
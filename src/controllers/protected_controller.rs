@@ -1,13 +1,15 @@
+use serde_json::Value;
 use warp::{http::StatusCode, reply::with_status};
 
 #[allow(unused_imports)]
 use crate::models::error_response::ErrorResponse;
+use crate::models::session_model::Principal;
 
 #[utoipa::path(
     get,
     path = "/api/v1/protected",
     tag = "Protected",
-    security(("api_key" = [])),
+    security(("api_key" = []), ("hmac_signature" = [])),
     responses(
         (status = 200, body = String),
         (status = 401, description = "Unauthorized", body = ErrorResponse)
@@ -19,3 +21,23 @@ pub async fn protected_endpoint() -> Result<impl warp::Reply, warp::Rejection> {
         StatusCode::OK,
     ))
 }
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/protected",
+    tag = "Protected",
+    security(("hmac_signature" = [])),
+    request_body(content = Object, description = "Any JSON document; it is covered by the signature", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The signing client and the document it sent", body = Object),
+        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 415, description = "The body is not JSON", body = ErrorResponse)
+    )
+)]
+pub async fn signed_echo(principal: Principal, document: Value) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&serde_json::json!({
+        "client": principal.subject,
+        "document": document,
+    })))
+}
@@ -0,0 +1,455 @@
+use serde::Serialize;
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::Reply;
+
+use crate::config::Config;
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::scim_model::{
+    ScimAttributeDto, ScimAuthenticationSchemeDto, ScimFilterSupportDto, ScimGroupDto,
+    ScimGroupListDto, ScimListQuery, ScimPatchRequestDto, ScimSchemaDto, ScimSchemaListDto,
+    ScimSupportedDto, ScimUserDto, ScimUserListDto, ServiceProviderConfigDto, GROUP_SCHEMA,
+    LIST_RESPONSE_SCHEMA, SCHEMA_SCHEMA, SERVICE_PROVIDER_CONFIG_SCHEMA, USER_SCHEMA,
+};
+use crate::models::session_model::{ClientContext, Principal};
+use crate::services::audit_service::AuditService;
+use crate::services::scim_service::{ScimService, MAX_RESULTS};
+
+pub const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+fn scim_reply<T: Serialize>(body: &T, status: StatusCode) -> warp::reply::Response {
+    let reply = warp::reply::with_header(warp::reply::json(body), "content-type", SCIM_CONTENT_TYPE);
+    warp::reply::with_status(reply, status).into_response()
+}
+
+fn created<T: Serialize>(body: &T, location: Option<&str>) -> warp::reply::Response {
+    let mut response = scim_reply(body, StatusCode::CREATED);
+    if let Some(location) = location.and_then(|l| l.parse().ok()) {
+        response.headers_mut().insert("location", location);
+    }
+    response
+}
+
+async fn record(
+    audit: &Arc<dyn AuditService>,
+    action: AuditAction,
+    principal: Principal,
+    context: ClientContext,
+    target: String,
+) {
+    audit
+        .record(AuditEvent {
+            action,
+            outcome: AuditOutcome::Success,
+            actor: Some(principal.subject),
+            client_ip: context.client_ip,
+            target: Some(target),
+        })
+        .await;
+}
+
+fn attribute(name: &str, attribute_type: &str, multi_valued: bool, required: bool, mutability: &str, uniqueness: &str) -> ScimAttributeDto {
+    ScimAttributeDto {
+        name: name.to_string(),
+        attribute_type: attribute_type.to_string(),
+        multi_valued,
+        required,
+        case_exact: matches!(name, "id" | "externalId"),
+        mutability: mutability.to_string(),
+        returned: if name == "password" { "never" } else { "default" }.to_string(),
+        uniqueness: uniqueness.to_string(),
+    }
+}
+
+pub fn supported_schemas() -> Vec<ScimSchemaDto> {
+    vec![
+        ScimSchemaDto {
+            schemas: vec![SCHEMA_SCHEMA.to_string()],
+            id: USER_SCHEMA.to_string(),
+            name: "User".to_string(),
+            description: "User Account".to_string(),
+            attributes: vec![
+                attribute("userName", "string", false, true, "readWrite", "server"),
+                attribute("externalId", "string", false, false, "readWrite", "none"),
+                attribute("name", "complex", false, false, "readWrite", "none"),
+                attribute("displayName", "string", false, false, "readWrite", "none"),
+                attribute("emails", "complex", true, false, "readWrite", "none"),
+                attribute("active", "boolean", false, false, "readWrite", "none"),
+                attribute("password", "string", false, false, "writeOnly", "none"),
+                attribute("groups", "complex", true, false, "readOnly", "none"),
+            ],
+        },
+        ScimSchemaDto {
+            schemas: vec![SCHEMA_SCHEMA.to_string()],
+            id: GROUP_SCHEMA.to_string(),
+            name: "Group".to_string(),
+            description: "Group".to_string(),
+            attributes: vec![
+                attribute("displayName", "string", false, true, "readWrite", "server"),
+                attribute("externalId", "string", false, false, "readWrite", "none"),
+                attribute("members", "complex", true, false, "readWrite", "none"),
+            ],
+        },
+    ]
+}
+
+#[utoipa::path(
+    get,
+    path = "/scim/v2/ServiceProviderConfig",
+    tag = "SCIM",
+    responses(
+        (status = 200, description = "Supported SCIM features", body = ServiceProviderConfigDto)
+    )
+)]
+pub async fn service_provider_config(config: Arc<Config>) -> Result<impl warp::Reply, warp::Rejection> {
+    let body = ServiceProviderConfigDto {
+        schemas: vec![SERVICE_PROVIDER_CONFIG_SCHEMA.to_string()],
+        patch: ScimSupportedDto { supported: true },
+        bulk: ScimSupportedDto { supported: false },
+        filter: ScimFilterSupportDto {
+            supported: true,
+            max_results: MAX_RESULTS,
+        },
+        change_password: ScimSupportedDto { supported: true },
+        sort: ScimSupportedDto { supported: false },
+        etag: ScimSupportedDto { supported: false },
+        authentication_schemes: vec![ScimAuthenticationSchemeDto {
+            scheme_type: "oauthbearertoken".to_string(),
+            name: "OAuth Bearer Token".to_string(),
+            description: format!(
+                "Client credentials token from {}/{}/auth/token with the `scim` scope",
+                config.issuer,
+                config.api_base.trim_matches('/')
+            ),
+        }],
+    };
+    Ok(scim_reply(&body, StatusCode::OK))
+}
+
+#[utoipa::path(
+    get,
+    path = "/scim/v2/Schemas",
+    tag = "SCIM",
+    responses(
+        (status = 200, description = "User and Group schema definitions", body = ScimSchemaListDto)
+    )
+)]
+pub async fn schemas() -> Result<impl warp::Reply, warp::Rejection> {
+    let resources = supported_schemas();
+    let body = ScimSchemaListDto {
+        schemas: vec![LIST_RESPONSE_SCHEMA.to_string()],
+        total_results: resources.len(),
+        start_index: 1,
+        items_per_page: resources.len(),
+        resources,
+    };
+    Ok(scim_reply(&body, StatusCode::OK))
+}
+
+#[utoipa::path(
+    get,
+    path = "/scim/v2/Users",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(ScimListQuery),
+    responses(
+        (status = 200, description = "Matching users", body = ScimUserListDto),
+        (status = 400, description = "Invalid filter", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse)
+    )
+)]
+pub async fn list_users<S: ScimService + Send + Sync>(
+    _principal: Principal,
+    query: ScimListQuery,
+    service: Arc<S>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let users = service.list_users(&query).await.map_err(warp::reject::custom)?;
+    Ok(scim_reply(&users, StatusCode::OK))
+}
+
+#[utoipa::path(
+    get,
+    path = "/scim/v2/Users/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User", body = ScimUserDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_user<S: ScimService + Send + Sync>(
+    id: String,
+    _principal: Principal,
+    service: Arc<S>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let user = service.get_user(&id).await.map_err(warp::reject::custom)?;
+    Ok(scim_reply(&user, StatusCode::OK))
+}
+
+#[utoipa::path(
+    post,
+    path = "/scim/v2/Users",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    request_body(content = ScimUserDto, description = "User to provision", content_type = "application/scim+json"),
+    responses(
+        (status = 201, description = "User created", body = ScimUserDto),
+        (status = 400, description = "Invalid user", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 409, description = "userName already taken", body = ErrorResponse)
+    )
+)]
+pub async fn create_user<S: ScimService + Send + Sync>(
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    user: ScimUserDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let user = service.create_user(user).await.map_err(warp::reject::custom)?;
+    let id = user.id.clone().unwrap_or_default();
+    record(&audit, AuditAction::UserCreated, principal, context, format!("user:{}", id)).await;
+    Ok(created(&user, user.meta.as_ref().map(|m| m.location.as_str())))
+}
+
+#[utoipa::path(
+    put,
+    path = "/scim/v2/Users/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "User id")),
+    request_body(content = ScimUserDto, description = "Full replacement of the user", content_type = "application/scim+json"),
+    responses(
+        (status = 200, description = "User replaced", body = ScimUserDto),
+        (status = 400, description = "Invalid user", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse),
+        (status = 409, description = "userName already taken", body = ErrorResponse)
+    )
+)]
+pub async fn replace_user<S: ScimService + Send + Sync>(
+    id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    user: ScimUserDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let user = service.replace_user(&id, user).await.map_err(warp::reject::custom)?;
+    record(&audit, AuditAction::UserUpdated, principal, context, format!("user:{}", id)).await;
+    Ok(scim_reply(&user, StatusCode::OK))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/scim/v2/Users/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "User id")),
+    request_body(content = ScimPatchRequestDto, description = "PatchOp request", content_type = "application/scim+json"),
+    responses(
+        (status = 200, description = "User updated", body = ScimUserDto),
+        (status = 400, description = "Invalid patch", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    )
+)]
+pub async fn patch_user<S: ScimService + Send + Sync>(
+    id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    patch: ScimPatchRequestDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let user = service.patch_user(&id, patch).await.map_err(warp::reject::custom)?;
+    record(&audit, AuditAction::UserUpdated, principal, context, format!("user:{}", id)).await;
+    Ok(scim_reply(&user, StatusCode::OK))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/scim/v2/Users/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "User id")),
+    responses(
+        (status = 204, description = "User deleted"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "User not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_user<S: ScimService + Send + Sync>(
+    id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    service.delete_user(&id).await.map_err(warp::reject::custom)?;
+    record(&audit, AuditAction::UserDeleted, principal, context, format!("user:{}", id)).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/scim/v2/Groups",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(ScimListQuery),
+    responses(
+        (status = 200, description = "Matching groups", body = ScimGroupListDto),
+        (status = 400, description = "Invalid filter", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse)
+    )
+)]
+pub async fn list_groups<S: ScimService + Send + Sync>(
+    _principal: Principal,
+    query: ScimListQuery,
+    service: Arc<S>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let groups = service.list_groups(&query).await.map_err(warp::reject::custom)?;
+    Ok(scim_reply(&groups, StatusCode::OK))
+}
+
+#[utoipa::path(
+    get,
+    path = "/scim/v2/Groups/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "Group id")),
+    responses(
+        (status = 200, description = "Group", body = ScimGroupDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "Group not found", body = ErrorResponse)
+    )
+)]
+pub async fn get_group<S: ScimService + Send + Sync>(
+    id: String,
+    _principal: Principal,
+    service: Arc<S>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let group = service.get_group(&id).await.map_err(warp::reject::custom)?;
+    Ok(scim_reply(&group, StatusCode::OK))
+}
+
+#[utoipa::path(
+    post,
+    path = "/scim/v2/Groups",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    request_body(content = ScimGroupDto, description = "Group to provision", content_type = "application/scim+json"),
+    responses(
+        (status = 201, description = "Group created", body = ScimGroupDto),
+        (status = 400, description = "Invalid group or unknown member", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 409, description = "displayName already taken", body = ErrorResponse)
+    )
+)]
+pub async fn create_group<S: ScimService + Send + Sync>(
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    group: ScimGroupDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let group = service.create_group(group).await.map_err(warp::reject::custom)?;
+    let id = group.id.clone().unwrap_or_default();
+    record(&audit, AuditAction::GroupCreated, principal, context, format!("group:{}", id)).await;
+    Ok(created(&group, group.meta.as_ref().map(|m| m.location.as_str())))
+}
+
+#[utoipa::path(
+    put,
+    path = "/scim/v2/Groups/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "Group id")),
+    request_body(content = ScimGroupDto, description = "Full replacement of the group", content_type = "application/scim+json"),
+    responses(
+        (status = 200, description = "Group replaced", body = ScimGroupDto),
+        (status = 400, description = "Invalid group or unknown member", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "Group not found", body = ErrorResponse),
+        (status = 409, description = "displayName already taken", body = ErrorResponse)
+    )
+)]
+pub async fn replace_group<S: ScimService + Send + Sync>(
+    id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    group: ScimGroupDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let group = service.replace_group(&id, group).await.map_err(warp::reject::custom)?;
+    record(&audit, AuditAction::GroupUpdated, principal, context, format!("group:{}", id)).await;
+    Ok(scim_reply(&group, StatusCode::OK))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/scim/v2/Groups/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "Group id")),
+    request_body(content = ScimPatchRequestDto, description = "PatchOp request", content_type = "application/scim+json"),
+    responses(
+        (status = 200, description = "Group updated", body = ScimGroupDto),
+        (status = 400, description = "Invalid patch or unknown member", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "Group not found", body = ErrorResponse)
+    )
+)]
+pub async fn patch_group<S: ScimService + Send + Sync>(
+    id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    patch: ScimPatchRequestDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let group = service.patch_group(&id, patch).await.map_err(warp::reject::custom)?;
+    record(&audit, AuditAction::GroupUpdated, principal, context, format!("group:{}", id)).await;
+    Ok(scim_reply(&group, StatusCode::OK))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/scim/v2/Groups/{id}",
+    tag = "SCIM",
+    security(("api_key" = [])),
+    params(("id" = String, Path, description = "Group id")),
+    responses(
+        (status = 204, description = "Group deleted"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Token lacks the scim scope", body = ErrorResponse),
+        (status = 404, description = "Group not found", body = ErrorResponse)
+    )
+)]
+pub async fn delete_group<S: ScimService + Send + Sync>(
+    id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    service.delete_group(&id).await.map_err(warp::reject::custom)?;
+    record(&audit, AuditAction::GroupDeleted, principal, context, format!("group:{}", id)).await;
+    Ok(StatusCode::NO_CONTENT)
+}
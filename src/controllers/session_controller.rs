@@ -0,0 +1,228 @@
+use std::sync::Arc;
+use warp::{http::StatusCode, reply::with_status};
+
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+use crate::models::session_model::{
+    ClientContext, Principal, RevokedSessionsDto, SessionModel, SessionResponseDto,
+};
+use crate::services::audit_service::AuditService;
+use crate::services::auth_service::AuthService;
+
+fn to_response(sessions: Vec<SessionModel>, current_session: &str) -> Vec<SessionResponseDto> {
+    sessions
+        .into_iter()
+        .map(|s| SessionResponseDto {
+            current: s.id == current_session,
+            id: s.id,
+            grant_type: s.grant_type,
+            issued_at: s.issued_at,
+            expires_at: s.expires_at,
+            user_agent: s.user_agent,
+            client_ip: s.client_ip,
+        })
+        .collect()
+}
+
+async fn record_revocation(
+    audit: &Arc<dyn AuditService>,
+    action: AuditAction,
+    succeeded: bool,
+    principal: &Principal,
+    context: ClientContext,
+    target: String,
+) {
+    audit
+        .record(AuditEvent {
+            action,
+            outcome: if succeeded {
+                AuditOutcome::Success
+            } else {
+                AuditOutcome::Failure
+            },
+            actor: Some(principal.subject.clone()),
+            client_ip: context.client_ip,
+            target: Some(target),
+        })
+        .await;
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/sessions",
+    tag = "Sessions",
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Active sessions of the caller", body = Vec<SessionResponseDto>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn list_sessions<S: AuthService + Send + Sync>(
+    principal: Principal,
+    service: Arc<S>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let sessions = service
+        .list_sessions(&principal.subject, &principal.grant_type)
+        .await;
+    Ok(warp::reply::json(&to_response(sessions, &principal.session_id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/auth/sessions/{id}",
+    tag = "Sessions",
+    security(("api_key" = [])),
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse)
+    ),
+    params(
+        ("id" = String, description = "Session identifier")
+    )
+)]
+pub async fn revoke_session<S: AuthService + Send + Sync>(
+    session_id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = service
+        .revoke_session(&principal.subject, &principal.grant_type, &session_id)
+        .await;
+    record_revocation(
+        &audit,
+        AuditAction::SessionRevoked,
+        result.is_ok(),
+        &principal,
+        context,
+        format!("session:{}", session_id),
+    )
+    .await;
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/logout-all",
+    tag = "Sessions",
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "All sessions of the caller revoked", body = RevokedSessionsDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse)
+    )
+)]
+pub async fn logout_all<S: AuthService + Send + Sync>(
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let revoked = service
+        .revoke_all_sessions(&principal.subject, &principal.grant_type)
+        .await;
+    let target = format!("{}:{}", principal.grant_type, principal.subject);
+    record_revocation(&audit, AuditAction::SessionsRevokedAll, true, &principal, context, target).await;
+    Ok(with_status(
+        warp::reply::json(&RevokedSessionsDto { revoked }),
+        StatusCode::OK,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/users/{username}/sessions",
+    tag = "Sessions",
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Active sessions of the user", body = Vec<SessionResponseDto>),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse)
+    ),
+    params(
+        ("username" = String, description = "User whose sessions are listed")
+    )
+)]
+pub async fn admin_list_sessions<S: AuthService + Send + Sync>(
+    username: String,
+    principal: Principal,
+    service: Arc<S>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let sessions = service.list_sessions(&username, "user").await;
+    Ok(warp::reply::json(&to_response(sessions, &principal.session_id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/users/{username}/sessions/{id}",
+    tag = "Sessions",
+    security(("api_key" = [])),
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse),
+        (status = 404, description = "Session not found", body = ErrorResponse)
+    ),
+    params(
+        ("username" = String, description = "Owner of the session"),
+        ("id" = String, description = "Session identifier")
+    )
+)]
+pub async fn admin_revoke_session<S: AuthService + Send + Sync>(
+    username: String,
+    session_id: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let result = service.revoke_session(&username, "user", &session_id).await;
+    record_revocation(
+        &audit,
+        AuditAction::SessionRevoked,
+        result.is_ok(),
+        &principal,
+        context,
+        format!("user:{}/session:{}", username, session_id),
+    )
+    .await;
+    match result {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err(warp::reject::custom(e)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/users/{username}/logout-all",
+    tag = "Sessions",
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "All sessions of the user revoked", body = RevokedSessionsDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Forbidden", body = ErrorResponse)
+    ),
+    params(
+        ("username" = String, description = "User whose sessions are revoked")
+    )
+)]
+pub async fn admin_logout_all<S: AuthService + Send + Sync>(
+    username: String,
+    principal: Principal,
+    service: Arc<S>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let revoked = service.revoke_all_sessions(&username, "user").await;
+    let target = format!("user:{}", username);
+    record_revocation(&audit, AuditAction::SessionsRevokedAll, true, &principal, context, target).await;
+    Ok(with_status(
+        warp::reply::json(&RevokedSessionsDto { revoked }),
+        StatusCode::OK,
+    ))
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use warp::http::StatusCode;
+
+use crate::errors::ApiError;
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome};
+#[allow(unused_imports)]
+use crate::models::error_response::ErrorResponse;
+use crate::models::session_model::{ClientContext, Principal};
+use crate::models::webauthn_model::{
+    CreationOptionsDto, LoginOptionsRequestDto, RegisteredCredentialDto, RegistrationCredentialDto,
+    RequestOptionsDto,
+};
+use crate::services::audit_service::AuditService;
+use crate::services::webauthn_service::WebauthnService;
+
+fn require_user(principal: &Principal) -> Result<(), warp::Rejection> {
+    if principal.grant_type == "user" {
+        Ok(())
+    } else {
+        Err(warp::reject::custom(ApiError::Forbidden))
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/webauthn/register/options",
+    tag = "Passkeys",
+    security(("api_key" = [])),
+    responses(
+        (status = 200, description = "Options for navigator.credentials.create()", body = CreationOptionsDto),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only users can register passkeys", body = ErrorResponse)
+    )
+)]
+pub async fn registration_options<W: WebauthnService + Send + Sync>(
+    principal: Principal,
+    webauthn: Arc<W>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    require_user(&principal)?;
+    let options = webauthn
+        .registration_options(&principal.subject)
+        .await
+        .map_err(warp::reject::custom)?;
+    Ok(warp::reply::json(&options))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/webauthn/register",
+    tag = "Passkeys",
+    security(("api_key" = [])),
+    request_body(content = RegistrationCredentialDto, description = "Credential returned by navigator.credentials.create()", content_type = "application/json"),
+    responses(
+        (status = 201, description = "Passkey registered", body = RegisteredCredentialDto),
+        (status = 400, description = "The attestation could not be verified", body = ErrorResponse),
+        (status = 401, description = "Unauthorized", body = ErrorResponse),
+        (status = 403, description = "Only users can register passkeys", body = ErrorResponse)
+    )
+)]
+pub async fn register<W: WebauthnService + Send + Sync>(
+    principal: Principal,
+    webauthn: Arc<W>,
+    audit: Arc<dyn AuditService>,
+    context: ClientContext,
+    credential: RegistrationCredentialDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    require_user(&principal)?;
+    let result = webauthn.register(&principal.subject, credential).await;
+    audit
+        .record(AuditEvent {
+            action: AuditAction::PasskeyRegistered,
+            outcome: if result.is_ok() {
+                AuditOutcome::Success
+            } else {
+                AuditOutcome::Failure
+            },
+            actor: Some(principal.subject),
+            client_ip: context.client_ip,
+            target: result.as_ref().ok().map(|c| format!("passkey:{}", c.id)),
+        })
+        .await;
+    let credential = result.map_err(warp::reject::custom)?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&RegisteredCredentialDto {
+            id: credential.id,
+            created: credential.created,
+        }),
+        StatusCode::CREATED,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/webauthn/login/options",
+    tag = "Passkeys",
+    request_body(content = LoginOptionsRequestDto, description = "Optional username to list its passkeys", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Options for navigator.credentials.get(); answer with the webauthn grant", body = RequestOptionsDto)
+    )
+)]
+pub async fn login_options<W: WebauthnService + Send + Sync>(
+    webauthn: Arc<W>,
+    request: LoginOptionsRequestDto,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    Ok(warp::reply::json(&webauthn.login_options(request.username.as_deref()).await))
+}
@@ -1,45 +1,479 @@
-use std::collections::HashMap;
-use std::sync::RwLock;
-use lazy_static::lazy_static;
-use warp::http::StatusCode;
-
-#[derive(Debug)]
-pub struct Errorcode {
-  pub code: u16,
-  pub status_code: StatusCode,
-  pub message: String
-}
-
-#[derive(Hash, Eq, PartialEq, Debug, Clone)]
-pub enum ErrorCodes {
-    NotNull = 1001,
-    NotEmpty = 1002,
-    MaxSize = 1003,
-    Nodeclared = 6000
-}
-
-lazy_static! {
-    pub static ref ERROR_CODES: RwLock<HashMap<ErrorCodes, Errorcode>> = {
-        let mut m = HashMap::new();
-
-        m.insert(ErrorCodes::NotNull, Errorcode {
-            code: ErrorCodes::NotNull as u16,
-            status_code: StatusCode::BAD_REQUEST,
-            message: String::from("Content must not be null"),
-        });
-
-        m.insert(ErrorCodes::NotEmpty, Errorcode {
-            code: ErrorCodes::NotEmpty as u16,
-            status_code: StatusCode::BAD_REQUEST,
-            message: String::from("Content must not be empty"),
-        });
-
-        m.insert(ErrorCodes::MaxSize, Errorcode {
-            code: ErrorCodes::MaxSize as u16,
-            status_code: StatusCode::BAD_REQUEST,
-            message: String::from("Content must be maximum size 32"),
-        });
-
-        RwLock::new(m)
-    };
+use std::collections::HashMap;
+use std::sync::RwLock;
+use lazy_static::lazy_static;
+use warp::http::StatusCode;
+
+#[derive(Debug)]
+pub struct Errorcode {
+  pub code: u16,
+  pub status_code: StatusCode,
+  pub message: String
+}
+
+#[derive(Hash, Eq, PartialEq, Debug, Clone)]
+pub enum ErrorCodes {
+    NotNull = 1001,
+    NotEmpty = 1002,
+    MaxSize = 1003,
+    MinSize = 1004,
+    PatternMismatch = 1005,
+    InvalidEmail = 1006,
+    InvalidUrl = 1007,
+    InvalidUuid = 1008,
+    NotAllowedValue = 1009,
+    InvalidDate = 1010,
+    InvalidDateTime = 1011,
+    MissingPrefix = 1012,
+    MissingSuffix = 1013,
+    MissingSubstring = 1014,
+    TooFewItems = 1015,
+    TooManyItems = 1016,
+    DuplicateItems = 1017,
+    DateTooLate = 1018,
+    DateTooEarly = 1019,
+    DateOutOfRange = 1020,
+    InvalidQueryString = 1021,
+    MissingHeader = 1022,
+    BodyTooLarge = 1023,
+    UnsupportedMediaType = 1024,
+    MalformedJson = 1025,
+    InvalidJsonBody = 1026,
+    JsonTooDeep = 1027,
+    UnknownField = 1028,
+    FieldMismatch = 1029,
+    FieldNotDifferent = 1030,
+    NotLessThanField = 1031,
+    NotGreaterThanField = 1032,
+    AboveField = 1033,
+    BelowField = 1034,
+    NotBeforeField = 1035,
+    NotAfterField = 1036,
+    PasswordTooShort = 1101,
+    PasswordTooLong = 1102,
+    PasswordMissingUppercase = 1103,
+    PasswordMissingLowercase = 1104,
+    PasswordMissingDigit = 1105,
+    PasswordMissingSymbol = 1106,
+    PasswordContainsUsername = 1107,
+    PasswordTooCommon = 1108,
+    InvalidResetToken = 2001,
+    InvalidVerificationToken = 2002,
+    EmailNotVerified = 2003,
+    WebauthnRegistrationFailed = 2004,
+    UnknownIdentityProvider = 2005,
+    InvalidLoginState = 2006,
+    FederatedLoginFailed = 2007,
+    IdentityProviderUnavailable = 2008,
+    AuthorizationPending = 2009,
+    SlowDown = 2010,
+    ExpiredDeviceCode = 2011,
+    DeviceAccessDenied = 2012,
+    InvalidDeviceCode = 2013,
+    InvalidUserCode = 2014,
+    AccountLinkRequired = 2015,
+    IdentityAlreadyLinked = 2016,
+    ScimInvalidFilter = 3001,
+    ScimInvalidPatch = 3002,
+    ScimInvalidValue = 3003,
+    ScimUniqueness = 3004,
+    ScimResourceNotFound = 3005,
+    Nodeclared = 6000
+}
+
+lazy_static! {
+    pub static ref ERROR_CODES: RwLock<HashMap<ErrorCodes, Errorcode>> = {
+        let mut m = HashMap::new();
+
+        m.insert(ErrorCodes::NotNull, Errorcode {
+            code: ErrorCodes::NotNull as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must not be null"),
+        });
+
+        m.insert(ErrorCodes::NotEmpty, Errorcode {
+            code: ErrorCodes::NotEmpty as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must not be empty"),
+        });
+
+        m.insert(ErrorCodes::MaxSize, Errorcode {
+            code: ErrorCodes::MaxSize as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must not exceed {max}"),
+        });
+
+        m.insert(ErrorCodes::MinSize, Errorcode {
+            code: ErrorCodes::MinSize as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be at least {min} characters long"),
+        });
+
+        m.insert(ErrorCodes::PatternMismatch, Errorcode {
+            code: ErrorCodes::PatternMismatch as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} does not match the expected pattern"),
+        });
+
+        m.insert(ErrorCodes::InvalidEmail, Errorcode {
+            code: ErrorCodes::InvalidEmail as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} is not a valid email address"),
+        });
+
+        m.insert(ErrorCodes::InvalidUrl, Errorcode {
+            code: ErrorCodes::InvalidUrl as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} is not a valid URL"),
+        });
+
+        m.insert(ErrorCodes::InvalidUuid, Errorcode {
+            code: ErrorCodes::InvalidUuid as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} is not a valid UUID"),
+        });
+
+        m.insert(ErrorCodes::NotAllowedValue, Errorcode {
+            code: ErrorCodes::NotAllowedValue as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} is not one of the allowed values"),
+        });
+
+        m.insert(ErrorCodes::InvalidDate, Errorcode {
+            code: ErrorCodes::InvalidDate as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} is not an ISO 8601 date (YYYY-MM-DD)"),
+        });
+
+        m.insert(ErrorCodes::InvalidDateTime, Errorcode {
+            code: ErrorCodes::InvalidDateTime as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} is not an ISO 8601 date and time with offset"),
+        });
+
+        m.insert(ErrorCodes::MissingPrefix, Errorcode {
+            code: ErrorCodes::MissingPrefix as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} does not start with the required prefix"),
+        });
+
+        m.insert(ErrorCodes::MissingSuffix, Errorcode {
+            code: ErrorCodes::MissingSuffix as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} does not end with the required suffix"),
+        });
+
+        m.insert(ErrorCodes::MissingSubstring, Errorcode {
+            code: ErrorCodes::MissingSubstring as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} does not contain the required text"),
+        });
+
+        m.insert(ErrorCodes::TooFewItems, Errorcode {
+            code: ErrorCodes::TooFewItems as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must have at least {min} items"),
+        });
+
+        m.insert(ErrorCodes::TooManyItems, Errorcode {
+            code: ErrorCodes::TooManyItems as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must have at most {max} items"),
+        });
+
+        m.insert(ErrorCodes::DuplicateItems, Errorcode {
+            code: ErrorCodes::DuplicateItems as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} contains duplicate items"),
+        });
+
+        m.insert(ErrorCodes::DateTooLate, Errorcode {
+            code: ErrorCodes::DateTooLate as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be before {max}"),
+        });
+
+        m.insert(ErrorCodes::DateTooEarly, Errorcode {
+            code: ErrorCodes::DateTooEarly as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be after {min}"),
+        });
+
+        m.insert(ErrorCodes::DateOutOfRange, Errorcode {
+            code: ErrorCodes::DateOutOfRange as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be between {min} and {max}"),
+        });
+
+        m.insert(ErrorCodes::InvalidQueryString, Errorcode {
+            code: ErrorCodes::InvalidQueryString as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The query string has missing or invalid parameters"),
+        });
+
+        m.insert(ErrorCodes::MissingHeader, Errorcode {
+            code: ErrorCodes::MissingHeader as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The {field} header is required"),
+        });
+
+        m.insert(ErrorCodes::BodyTooLarge, Errorcode {
+            code: ErrorCodes::BodyTooLarge as u16,
+            status_code: StatusCode::PAYLOAD_TOO_LARGE,
+            message: String::from("The body exceeds {max} bytes"),
+        });
+
+        m.insert(ErrorCodes::UnsupportedMediaType, Errorcode {
+            code: ErrorCodes::UnsupportedMediaType as u16,
+            status_code: StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            message: String::from("The body must be sent as application/json"),
+        });
+
+        m.insert(ErrorCodes::MalformedJson, Errorcode {
+            code: ErrorCodes::MalformedJson as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The body is not valid JSON at line {line}, column {column}"),
+        });
+
+        m.insert(ErrorCodes::InvalidJsonBody, Errorcode {
+            code: ErrorCodes::InvalidJsonBody as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The body does not match the expected format at line {line}, column {column}: {reason}"),
+        });
+
+        m.insert(ErrorCodes::JsonTooDeep, Errorcode {
+            code: ErrorCodes::JsonTooDeep as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The body nests objects and arrays deeper than {max} levels"),
+        });
+
+        m.insert(ErrorCodes::UnknownField, Errorcode {
+            code: ErrorCodes::UnknownField as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} is not a known field (line {line}, column {column})"),
+        });
+
+        m.insert(ErrorCodes::FieldMismatch, Errorcode {
+            code: ErrorCodes::FieldMismatch as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must match {other}"),
+        });
+
+        m.insert(ErrorCodes::FieldNotDifferent, Errorcode {
+            code: ErrorCodes::FieldNotDifferent as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must differ from {other}"),
+        });
+
+        m.insert(ErrorCodes::NotLessThanField, Errorcode {
+            code: ErrorCodes::NotLessThanField as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be less than {other}"),
+        });
+
+        m.insert(ErrorCodes::NotGreaterThanField, Errorcode {
+            code: ErrorCodes::NotGreaterThanField as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be greater than {other}"),
+        });
+
+        m.insert(ErrorCodes::AboveField, Errorcode {
+            code: ErrorCodes::AboveField as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must not be greater than {other}"),
+        });
+
+        m.insert(ErrorCodes::BelowField, Errorcode {
+            code: ErrorCodes::BelowField as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must not be less than {other}"),
+        });
+
+        m.insert(ErrorCodes::NotBeforeField, Errorcode {
+            code: ErrorCodes::NotBeforeField as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be before {other}"),
+        });
+
+        m.insert(ErrorCodes::NotAfterField, Errorcode {
+            code: ErrorCodes::NotAfterField as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("{field} must be after {other}"),
+        });
+
+        m.insert(ErrorCodes::PasswordTooShort, Errorcode {
+            code: ErrorCodes::PasswordTooShort as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password must be at least {min} characters long"),
+        });
+
+        m.insert(ErrorCodes::PasswordTooLong, Errorcode {
+            code: ErrorCodes::PasswordTooLong as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password must be at most {max} characters long"),
+        });
+
+        m.insert(ErrorCodes::PasswordMissingUppercase, Errorcode {
+            code: ErrorCodes::PasswordMissingUppercase as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password must contain an uppercase letter"),
+        });
+
+        m.insert(ErrorCodes::PasswordMissingLowercase, Errorcode {
+            code: ErrorCodes::PasswordMissingLowercase as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password must contain a lowercase letter"),
+        });
+
+        m.insert(ErrorCodes::PasswordMissingDigit, Errorcode {
+            code: ErrorCodes::PasswordMissingDigit as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password must contain a digit"),
+        });
+
+        m.insert(ErrorCodes::PasswordMissingSymbol, Errorcode {
+            code: ErrorCodes::PasswordMissingSymbol as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password must contain a symbol"),
+        });
+
+        m.insert(ErrorCodes::PasswordContainsUsername, Errorcode {
+            code: ErrorCodes::PasswordContainsUsername as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password must not contain the username"),
+        });
+
+        m.insert(ErrorCodes::PasswordTooCommon, Errorcode {
+            code: ErrorCodes::PasswordTooCommon as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The password is too common"),
+        });
+
+        m.insert(ErrorCodes::InvalidResetToken, Errorcode {
+            code: ErrorCodes::InvalidResetToken as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The reset token is invalid or has expired"),
+        });
+
+        m.insert(ErrorCodes::InvalidVerificationToken, Errorcode {
+            code: ErrorCodes::InvalidVerificationToken as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The verification token is invalid or has expired"),
+        });
+
+        m.insert(ErrorCodes::EmailNotVerified, Errorcode {
+            code: ErrorCodes::EmailNotVerified as u16,
+            status_code: StatusCode::FORBIDDEN,
+            message: String::from("The email address has not been verified"),
+        });
+
+        m.insert(ErrorCodes::WebauthnRegistrationFailed, Errorcode {
+            code: ErrorCodes::WebauthnRegistrationFailed as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The passkey registration could not be verified"),
+        });
+
+        m.insert(ErrorCodes::UnknownIdentityProvider, Errorcode {
+            code: ErrorCodes::UnknownIdentityProvider as u16,
+            status_code: StatusCode::NOT_FOUND,
+            message: String::from("Unknown identity provider"),
+        });
+
+        m.insert(ErrorCodes::InvalidLoginState, Errorcode {
+            code: ErrorCodes::InvalidLoginState as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The login state is missing, unknown or has expired"),
+        });
+
+        m.insert(ErrorCodes::FederatedLoginFailed, Errorcode {
+            code: ErrorCodes::FederatedLoginFailed as u16,
+            status_code: StatusCode::UNAUTHORIZED,
+            message: String::from("The identity provider did not authenticate the user"),
+        });
+
+        m.insert(ErrorCodes::IdentityProviderUnavailable, Errorcode {
+            code: ErrorCodes::IdentityProviderUnavailable as u16,
+            status_code: StatusCode::BAD_GATEWAY,
+            message: String::from("The identity provider could not be reached"),
+        });
+
+        m.insert(ErrorCodes::AuthorizationPending, Errorcode {
+            code: ErrorCodes::AuthorizationPending as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The user has not approved the device yet"),
+        });
+
+        m.insert(ErrorCodes::SlowDown, Errorcode {
+            code: ErrorCodes::SlowDown as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("Polling too fast, wait {seconds} more seconds between requests"),
+        });
+
+        m.insert(ErrorCodes::ExpiredDeviceCode, Errorcode {
+            code: ErrorCodes::ExpiredDeviceCode as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The device code has expired"),
+        });
+
+        m.insert(ErrorCodes::DeviceAccessDenied, Errorcode {
+            code: ErrorCodes::DeviceAccessDenied as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The user denied the device"),
+        });
+
+        m.insert(ErrorCodes::InvalidDeviceCode, Errorcode {
+            code: ErrorCodes::InvalidDeviceCode as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The device code is unknown or was issued to another client"),
+        });
+
+        m.insert(ErrorCodes::InvalidUserCode, Errorcode {
+            code: ErrorCodes::InvalidUserCode as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The user code is invalid, expired or already used"),
+        });
+
+        m.insert(ErrorCodes::AccountLinkRequired, Errorcode {
+            code: ErrorCodes::AccountLinkRequired as u16,
+            status_code: StatusCode::CONFLICT,
+            message: String::from("An account with this email already exists; sign in to it and link the provider from there"),
+        });
+
+        m.insert(ErrorCodes::IdentityAlreadyLinked, Errorcode {
+            code: ErrorCodes::IdentityAlreadyLinked as u16,
+            status_code: StatusCode::CONFLICT,
+            message: String::from("This provider account is already linked to another user"),
+        });
+
+        m.insert(ErrorCodes::ScimInvalidFilter, Errorcode {
+            code: ErrorCodes::ScimInvalidFilter as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The filter is not supported or malformed"),
+        });
+
+        m.insert(ErrorCodes::ScimInvalidPatch, Errorcode {
+            code: ErrorCodes::ScimInvalidPatch as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("The patch operation is not supported or malformed"),
+        });
+
+        m.insert(ErrorCodes::ScimInvalidValue, Errorcode {
+            code: ErrorCodes::ScimInvalidValue as u16,
+            status_code: StatusCode::BAD_REQUEST,
+            message: String::from("A required value is missing or has the wrong type"),
+        });
+
+        m.insert(ErrorCodes::ScimUniqueness, Errorcode {
+            code: ErrorCodes::ScimUniqueness as u16,
+            status_code: StatusCode::CONFLICT,
+            message: String::from("A resource with the same name already exists"),
+        });
+
+        m.insert(ErrorCodes::ScimResourceNotFound, Errorcode {
+            code: ErrorCodes::ScimResourceNotFound as u16,
+            status_code: StatusCode::NOT_FOUND,
+            message: String::from("Resource not found"),
+        });
+
+        RwLock::new(m)
+    };
 }
\ No newline at end of file
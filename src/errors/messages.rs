@@ -0,0 +1,157 @@
+//! Localized error messages.
+//!
+//! The messages in `ERROR_CODES` are the English templates. Other languages
+//! come from per-locale catalogs: JSON files named after the locale, such as
+//! `es.json`, with the word used when a problem has no field and the
+//! translated templates by error code:
+//!
+//! ```json
+//! { "value": "El valor", "messages": { "1001": "{field} no puede ser nulo" } }
+//! ```
+//!
+//! Templates may use `{field}`, `{min}`, `{max}` and `{value}`, cross-field
+//! rules also `{other}`, and body errors also `{line}`, `{column}` and
+//! `{reason}`; codes missing from a catalog fall back to English.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::errors::error_codes::Errorcode;
+
+/// The language of the templates in `ERROR_CODES`.
+pub const BASE_LOCALE: &str = "en";
+
+/// Catalogs that ship with the server.
+const BUNDLED: &[(&str, &str)] = &[("es", include_str!("locales/es.json"))];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Catalog {
+    /// Replaces `{field}` when a problem names no field.
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default)]
+    messages: HashMap<u16, String>,
+}
+
+lazy_static! {
+    static ref CATALOGS: RwLock<HashMap<String, Catalog>> = RwLock::new(
+        BUNDLED
+            .iter()
+            .map(|(locale, json)| {
+                let catalog = serde_json::from_str(json).unwrap_or_else(|e| panic!("invalid bundled catalog {}: {}", locale, e));
+                (locale.to_string(), catalog)
+            })
+            .collect()
+    );
+}
+
+/// Loads every `<locale>.json` catalog in `dir`, replacing bundled catalogs
+/// of the same locale. Returns the loaded locales, or one error per file
+/// that could not be read.
+pub fn load_dir(dir: &str) -> Result<Vec<String>, Vec<String>> {
+    let entries = std::fs::read_dir(dir).map_err(|e| vec![format!("{}: {}", dir, e)])?;
+    let mut loaded = Vec::new();
+    let mut errors = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(locale) = path.file_stem().and_then(|s| s.to_str()).map(str::to_lowercase) else {
+            continue;
+        };
+        let catalog = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<Catalog>(&json).map_err(|e| e.to_string()));
+        match catalog {
+            Ok(catalog) => {
+                CATALOGS.write().unwrap().insert(locale.clone(), catalog);
+                loaded.push(locale);
+            }
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    if errors.is_empty() {
+        loaded.sort();
+        Ok(loaded)
+    } else {
+        Err(errors)
+    }
+}
+
+fn supported(locale: &str) -> bool {
+    locale == BASE_LOCALE || CATALOGS.read().unwrap().contains_key(locale)
+}
+
+/// Picks the supported locale the client prefers most from an
+/// `Accept-Language` header. A region falls back to its language, so `es-MX`
+/// matches `es`; without a match the answer is `fallback`.
+pub fn negotiate(accept_language: Option<&str>, fallback: &str) -> String {
+    let mut ranges: Vec<(String, f32)> = accept_language
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim().to_lowercase();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+            (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+        })
+        .collect();
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranges
+        .iter()
+        .find_map(|(tag, _)| {
+            let language = tag.split('-').next().unwrap_or(tag);
+            [tag.as_str(), language]
+                .into_iter()
+                .find(|candidate| supported(candidate))
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// The message of `errorcode` in `locale`, with its placeholders filled.
+/// Placeholders without a value are left as they are.
+pub fn render(errorcode: &Errorcode, locale: &str, field: Option<&str>, args: &[(&'static str, String)]) -> String {
+    let catalogs = CATALOGS.read().unwrap();
+    let catalog = catalogs.get(locale);
+    let template = catalog
+        .and_then(|c| c.messages.get(&errorcode.code))
+        .unwrap_or(&errorcode.message);
+    let field = match field {
+        Some(field) => field.trim_start_matches('/').to_string(),
+        None => catalog
+            .and_then(|c| c.value.clone())
+            .unwrap_or_else(|| "The value".to_string()),
+    };
+
+    let value_of = |name: &str| match name {
+        "field" => Some(field.as_str()),
+        _ => args.iter().find(|(arg, _)| *arg == name).map(|(_, value)| value.as_str()),
+    };
+
+    // One pass, so values that look like placeholders are not expanded.
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        match placeholder.find('}').and_then(|end| value_of(&placeholder[1..end]).map(|v| (v, end))) {
+            Some((value, end)) => {
+                message.push_str(value);
+                rest = &placeholder[end + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = &placeholder[1..];
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
@@ -1,33 +1,134 @@
 pub mod error_codes;
+pub mod messages;
 
 use crate::errors::error_codes::ErrorCodes;
 use crate::errors::error_codes::ERROR_CODES;
 use crate::models::error_response::ErrorResponse;
+use crate::models::error_response::OAuthErrorResponse;
 use crate::models::error_response::ValidationProblem;
 use std::convert::Infallible;
 use thiserror::Error;
-use warp::{http::StatusCode, reject::Reject, Rejection, Reply};
+use warp::filters::BoxedFilter;
+use warp::http::header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE, CONTENT_LANGUAGE};
+use warp::reply::Response;
+use warp::{http::StatusCode, reject::Reject, Filter, Rejection, Reply};
 
 #[derive(Error, Debug)]
 #[allow(dead_code)]
 pub enum ApiError {
     #[error("Message not found")]
     NotFound,
+    #[error("Not Found")]
+    RouteNotFound,
     #[error("Invalid input: {0}")]
     BadRequest(String, u16),
     #[error("Internal server error")]
     InternalServerError,
     #[error("Unauthorized")]
     Unauthorized,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("Invalid CSRF token")]
+    InvalidCsrfToken,
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Invalid DPoP proof")]
+    InvalidDpopProof,
+    #[error("Invalid request signature")]
+    InvalidSignature,
+    #[error("Invalid scope")]
+    InvalidScope,
     #[error("custom")]
     ErrorCode(ErrorCodes),
     #[error("Multiple validation errors")]
-    MultipleErrors(Option<Vec<ErrorCodes>>, Option<String>, Option<String>),
+    MultipleErrors(Option<Vec<Failure>>, Option<String>, Option<String>),
+    /// Failures of several fields, each with its own codes, and the instance.
+    #[error("Multiple validation errors")]
+    FieldErrors(Vec<(Option<String>, Vec<Failure>)>, Option<String>),
+    /// A failed device code poll and, for `slow_down`, the new interval.
+    /// Answered as an `OAuthErrorResponse` instead of a problem.
+    #[error("Device code grant failed")]
+    DeviceGrant(Failure, Option<i64>),
 }
 
 impl Reject for ApiError {}
 
-pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+/// A failed rule: its error code and the values of the placeholders in its
+/// message, such as `max` for `max_length(32)`.
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub code: ErrorCodes,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl From<ErrorCodes> for Failure {
+    fn from(code: ErrorCodes) -> Self {
+        Failure { code, args: Vec::new() }
+    }
+}
+
+impl PartialEq<ErrorCodes> for Failure {
+    fn eq(&self, code: &ErrorCodes) -> bool {
+        self.code == *code
+    }
+}
+
+/// Answers the rejections of `filter` with problem responses, with messages
+/// in the language the `Accept-Language` header prefers, or in `fallback`.
+pub fn recover_localized<F, T>(filter: F, fallback: String) -> BoxedFilter<(Response,)>
+where
+    F: Filter<Extract = (T,), Error = Rejection> + Clone + Send + Sync + 'static,
+    T: Reply,
+{
+    let attempt = filter
+        .map(|reply: T| Ok(reply.into_response()))
+        .or_else(|rejection| async move { Ok::<_, Infallible>((Err::<Response, _>(rejection),)) });
+    warp::header::headers_cloned()
+        .and(attempt)
+        .map(move |headers: HeaderMap, result: Result<Response, Rejection>| match result {
+            Ok(response) => response,
+            Err(rejection) => {
+                let accept_language = headers.get(ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok());
+                let locale = messages::negotiate(accept_language, &fallback);
+                let mut response = problem_response(&rejection, &locale);
+                if let Ok(language) = HeaderValue::from_str(&locale) {
+                    response.headers_mut().insert(CONTENT_LANGUAGE, language);
+                }
+                response
+            }
+        })
+        .boxed()
+}
+
+/// The RFC 8628 `error` value of a device code failure.
+fn oauth_error(code: &ErrorCodes) -> &'static str {
+    match code {
+        ErrorCodes::AuthorizationPending => "authorization_pending",
+        ErrorCodes::SlowDown => "slow_down",
+        ErrorCodes::ExpiredDeviceCode => "expired_token",
+        ErrorCodes::DeviceAccessDenied => "access_denied",
+        _ => "invalid_grant",
+    }
+}
+
+fn oauth_error_response(failure: &Failure, interval: Option<i64>, locale: &str) -> Response {
+    let dict = ERROR_CODES.read().unwrap();
+    let (status, error_description) = match dict.get(&failure.code) {
+        Some(errorcode) => (errorcode.status_code, messages::render(errorcode, locale, None, &failure.args)),
+        None => (StatusCode::BAD_REQUEST, String::new()),
+    };
+    let body = OAuthErrorResponse {
+        error: oauth_error(&failure.code).to_string(),
+        error_description,
+        interval,
+    };
+    warp::reply::with_status(warp::reply::json(&body), status).into_response()
+}
+
+pub(crate) fn problem_response(err: &Rejection, locale: &str) -> Response {
+    if let Some(ApiError::DeviceGrant(failure, interval)) = err.find::<ApiError>() {
+        return oauth_error_response(failure, *interval, locale);
+    }
     let dict = ERROR_CODES.read().unwrap();
     let errors: ErrorResponse = if err.is_not_found() {
         ErrorResponse {
@@ -44,6 +145,12 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
                 instance: None,
                 details: None,
             },
+            ApiError::RouteNotFound => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::NOT_FOUND.as_u16(),
+                instance: None,
+                details: None,
+            },
             ApiError::BadRequest(details, code) => ErrorResponse {
                 title: "Bad request".to_string(),
                 status: StatusCode::BAD_REQUEST.as_u16(),
@@ -51,7 +158,7 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
                 details: Some(vec![ValidationProblem {
                     field: None,
                     message: details.clone(),
-                    error_code: code.clone(),
+                    error_code: *code,
                 }]),
             },
             ApiError::InternalServerError => ErrorResponse {
@@ -70,15 +177,52 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
                 instance: None,
                 details: None,
             },
+            ApiError::Forbidden => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::FORBIDDEN.as_u16(),
+                instance: None,
+                details: None,
+            },
+            ApiError::InvalidCsrfToken => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::FORBIDDEN.as_u16(),
+                instance: None,
+                details: None,
+            },
+            ApiError::SessionNotFound => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::NOT_FOUND.as_u16(),
+                instance: None,
+                details: None,
+            },
+            ApiError::InvalidDpopProof => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::UNAUTHORIZED.as_u16(),
+                instance: None,
+                details: None,
+            },
+            ApiError::InvalidSignature => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::UNAUTHORIZED.as_u16(),
+                instance: None,
+                details: None,
+            },
+            ApiError::InvalidScope => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::BAD_REQUEST.as_u16(),
+                instance: None,
+                details: None,
+            },
             ApiError::ErrorCode(code) => {
                 if let Some(errorcode) = dict.get(code) {
+                    let message = messages::render(errorcode, locale, None, &[]);
                     ErrorResponse {
-                        title: errorcode.message.clone(),
+                        title: message.clone(),
                         status: errorcode.status_code.as_u16(),
                         instance: None,
                         details: Some(vec![ValidationProblem {
                             field: None,
-                            message: errorcode.message.clone(),
+                            message,
                             error_code: errorcode.code,
                         }]),
                     } //(errorcode.status_code, errorcode.code, errorcode.message.to_string())
@@ -99,13 +243,13 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
                 let mut validation_problems: Option<Vec<ValidationProblem>> = Some(vec![]);
                 let mut status_code: u16 = 0;
                 if let Some(error_list) = errors {
-                    for code in error_list {
-                        if let Some(errorcode) = dict.get(code) {
+                    for failure in error_list {
+                        if let Some(errorcode) = dict.get(&failure.code) {
                             status_code = errorcode.status_code.as_u16();
                             if let Some(ref mut problems) = validation_problems {
                                 problems.push(ValidationProblem {
                                     field: field.clone(),
-                                    message: errorcode.message.clone(),
+                                    message: messages::render(errorcode, locale, field.as_deref(), &failure.args),
                                     error_code: errorcode.code,
                                 });
                             }
@@ -119,6 +263,35 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
                     details: validation_problems,
                 }
             }
+            ApiError::FieldErrors(fields, instance) => {
+                let mut problems = Vec::new();
+                let mut status_code: u16 = 0;
+                for (field, failures) in fields {
+                    for failure in failures {
+                        if let Some(errorcode) = dict.get(&failure.code) {
+                            status_code = errorcode.status_code.as_u16();
+                            problems.push(ValidationProblem {
+                                field: field.clone(),
+                                message: messages::render(errorcode, locale, field.as_deref(), &failure.args),
+                                error_code: errorcode.code,
+                            });
+                        }
+                    }
+                }
+                ErrorResponse {
+                    title: e.to_string(),
+                    status: status_code,
+                    instance: instance.clone(),
+                    details: Some(problems),
+                }
+            }
+            // Answered by `oauth_error_response` before getting here.
+            ApiError::DeviceGrant(..) => ErrorResponse {
+                title: e.to_string(),
+                status: StatusCode::BAD_REQUEST.as_u16(),
+                instance: None,
+                details: None,
+            },
         }
     } else {
         ErrorResponse {
@@ -130,11 +303,10 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
     };
 
     let json = warp::reply::json(&errors);
-    let res_status_code: StatusCode;
-    match StatusCode::from_u16(errors.status) {
-        Ok(status_code) => res_status_code = status_code,
-        Err(_) => res_status_code = StatusCode::INTERNAL_SERVER_ERROR,
-    }
+    let res_status_code = match StatusCode::from_u16(errors.status) {
+        Ok(status_code) => status_code,
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
 
-    Ok(warp::reply::with_status(json, res_status_code))
+    warp::reply::with_status(json, res_status_code).into_response()
 }
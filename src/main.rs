@@ -19,7 +19,15 @@ mod server;
 #[tokio::main]
 async fn main(){
     
-    let(tx,_) = run_server().await;
+    let(tx,_) = match run_server().await {
+        Ok(server) => server,
+        Err(errors) => {
+            for error in &errors {
+                eprintln!("{}", error);
+            }
+            std::process::exit(1);
+        }
+    };
 
     tokio::signal::ctrl_c().await.expect("failed to install CTRL+C signal handler");
     println!("Shutting down server...");
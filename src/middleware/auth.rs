@@ -0,0 +1,374 @@
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use warp::hyper::body::Bytes;
+use warp::http::{HeaderMap, Method};
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+use crate::config::{BodyLimits, Config};
+use crate::errors::ApiError;
+use crate::middleware::body::{bytes_body, json_from_bytes};
+use crate::middleware::validator::instance;
+use crate::models::session_model::{ClientContext, Principal};
+use crate::services::auth_service::AuthService;
+use crate::services::dpop_service::DPOP_HEADER;
+use crate::services::signature_service::{SignedRequest, NONCE_HEADER, TIMESTAMP_HEADER};
+
+pub const SESSION_COOKIE: &str = "session";
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Where the caller's token came from. Cookie-borne tokens are sent by the
+/// browser automatically, so they only count on state-changing requests when
+/// a matching CSRF token is also supplied. DPoP tokens carry a proof that has
+/// to be checked against the request it arrived with.
+enum Credentials {
+    Bearer(String),
+    Dpop {
+        token: String,
+        proof: Option<String>,
+        method: Method,
+        url: String,
+    },
+    Cookie {
+        token: String,
+        csrf_token: Option<String>,
+        safe_method: bool,
+    },
+    Missing,
+}
+
+fn credentials(config: Arc<Config>) -> impl Filter<Extract = (Credentials,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(warp::cookie::optional::<String>(SESSION_COOKIE))
+        .and(warp::header::optional::<String>(CSRF_HEADER))
+        .and(dpop_request(Arc::clone(&config)))
+        .map(
+            move |header: Option<String>,
+                  cookie: Option<String>,
+                  csrf_token: Option<String>,
+                  (proof, method, url): (Option<String>, Method, String)| {
+                if let Some(token) = header.as_deref().and_then(|h| h.strip_prefix("Bearer ")) {
+                    return Credentials::Bearer(token.to_string());
+                }
+                if let Some(token) = header.as_deref().and_then(|h| h.strip_prefix("DPoP ")) {
+                    return Credentials::Dpop {
+                        token: token.to_string(),
+                        proof,
+                        method,
+                        url,
+                    };
+                }
+                match cookie {
+                    Some(token) if config.session_cookies => Credentials::Cookie {
+                        token,
+                        csrf_token,
+                        safe_method: matches!(method, Method::GET | Method::HEAD | Method::OPTIONS),
+                    },
+                    _ => Credentials::Missing,
+                }
+            },
+        )
+}
+
+/// The `DPoP` header together with the method and public URL the proof has
+/// to match; the URL is rebuilt from the configured issuer.
+fn dpop_request(
+    config: Arc<Config>,
+) -> impl Filter<Extract = ((Option<String>, Method, String),), Error = Rejection> + Clone {
+    warp::header::optional::<String>(DPOP_HEADER)
+        .and(warp::method())
+        .and(warp::path::full())
+        .map(move |proof: Option<String>, method: Method, path: FullPath| {
+            (proof, method, format!("{}{}", config.issuer, path.as_str()))
+        })
+}
+
+async fn bearer_principal<S: AuthService + Send + Sync>(service: &S, token: &str) -> Result<Principal, Rejection> {
+    match service.authenticate(token).await {
+        // A DPoP-bound token presented as a plain bearer token is treated as stolen.
+        Some(principal) if principal.dpop_jkt.is_none() => Ok(principal),
+        _ => Err(warp::reject::custom(ApiError::Unauthorized)),
+    }
+}
+
+async fn dpop_principal<S: AuthService + Send + Sync>(
+    service: &S,
+    token: &str,
+    proof: Option<String>,
+    method: &Method,
+    url: &str,
+) -> Result<Principal, Rejection> {
+    let principal = service
+        .authenticate(token)
+        .await
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+    let expected = principal
+        .dpop_jkt
+        .as_deref()
+        .ok_or_else(|| warp::reject::custom(ApiError::Unauthorized))?;
+    let proof = proof.ok_or_else(|| warp::reject::custom(ApiError::InvalidDpopProof))?;
+    let jkt = service
+        .verify_dpop_proof(&proof, method.as_str(), url, Some(token))
+        .await
+        .map_err(warp::reject::custom)?;
+    if jkt != expected {
+        return Err(warp::reject::custom(ApiError::InvalidDpopProof));
+    }
+    Ok(principal)
+}
+
+/// A cookie carries no proof either, so DPoP-bound sessions are refused here
+/// as well; state-changing requests also need the CSRF token.
+async fn cookie_principal<S: AuthService + Send + Sync>(
+    service: &S,
+    token: &str,
+    csrf_token: Option<String>,
+    safe_method: bool,
+) -> Result<Principal, Rejection> {
+    let principal = bearer_principal(service, token).await?;
+    check_csrf(service, token, csrf_token, safe_method).await?;
+    Ok(principal)
+}
+
+async fn check_csrf<S: AuthService + Send + Sync>(
+    service: &S,
+    token: &str,
+    csrf_token: Option<String>,
+    safe_method: bool,
+) -> Result<(), Rejection> {
+    if safe_method {
+        return Ok(());
+    }
+    match csrf_token {
+        Some(csrf) if service.validate_csrf(token, &csrf).await => Ok(()),
+        _ => Err(warp::reject::custom(ApiError::InvalidCsrfToken)),
+    }
+}
+
+pub fn authenticate<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = (Principal,), Error = Rejection> + Clone {
+    credentials(config).and_then(move |credentials: Credentials| {
+        let svc = Arc::clone(&service);
+        async move {
+            match credentials {
+                Credentials::Bearer(token) => bearer_principal(svc.as_ref(), &token).await,
+                Credentials::Dpop {
+                    token,
+                    proof,
+                    method,
+                    url,
+                } => dpop_principal(svc.as_ref(), &token, proof, &method, &url).await,
+                Credentials::Cookie {
+                    token,
+                    csrf_token,
+                    safe_method,
+                } => cookie_principal(svc.as_ref(), &token, csrf_token, safe_method).await,
+                Credentials::Missing => Err(warp::reject::custom(ApiError::Unauthorized)),
+            }
+        }
+    })
+}
+
+pub fn authorize<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    credentials(config)
+        .and_then(move |credentials: Credentials| {
+            let svc = Arc::clone(&service);
+            async move {
+                match credentials {
+                    Credentials::Bearer(token) => bearer_principal(svc.as_ref(), &token).await.map(|_| ()),
+                    Credentials::Dpop {
+                        token,
+                        proof,
+                        method,
+                        url,
+                    } => dpop_principal(svc.as_ref(), &token, proof, &method, &url)
+                        .await
+                        .map(|_| ()),
+                    Credentials::Cookie {
+                        token,
+                        csrf_token,
+                        safe_method,
+                    } => cookie_principal(svc.as_ref(), &token, csrf_token, safe_method)
+                        .await
+                        .map(|_| ()),
+                    Credentials::Missing => Err(warp::reject::custom(ApiError::Unauthorized)),
+                }
+            }
+        })
+        .untuple_one()
+}
+
+pub fn require_admin<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = (Principal,), Error = Rejection> + Clone {
+    authenticate(service, config).and_then(|principal: Principal| async move {
+        if principal.is_admin {
+            Ok(principal)
+        } else {
+            Err(warp::reject::custom(ApiError::Forbidden))
+        }
+    })
+}
+
+/// Only lets client-credential tokens granted `scope` through.
+pub fn require_scope<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    config: Arc<Config>,
+    scope: &'static str,
+) -> impl Filter<Extract = (Principal,), Error = Rejection> + Clone {
+    authenticate(service, config).and_then(move |principal: Principal| async move {
+        if principal.grant_type == "client" && principal.has_scope(scope) {
+            Ok(principal)
+        } else {
+            Err(warp::reject::custom(ApiError::Forbidden))
+        }
+    })
+}
+
+/// Authenticates a service client from an HMAC-SHA256 request signature
+/// instead of a token. The body is read here, within `limits.max_bytes`, to
+/// hash it, so it is passed on as the verified bytes; `signed_json`
+/// deserializes them for routes that take a JSON body.
+pub fn require_signature<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    limits: BodyLimits,
+) -> impl Filter<Extract = (Principal, Bytes), Error = Rejection> + Clone {
+    let query = warp::query::raw().or(warp::any().map(String::new)).unify();
+    warp::header::<String>("authorization")
+        .and(warp::method())
+        .and(warp::path::full())
+        .and(query)
+        .and(warp::header::headers_cloned())
+        .and(bytes_body(limits))
+        .and_then(
+            move |authorization: String, method: Method, path: FullPath, query: String, headers: HeaderMap, body: Bytes| {
+                let svc = Arc::clone(&service);
+                async move {
+                    let request = signed_request(&authorization, &method, &path, query, &headers, &body)
+                        .ok_or_else(|| warp::reject::custom(ApiError::InvalidSignature))?;
+                    let principal = svc
+                        .verify_request_signature(request)
+                        .await
+                        .map_err(warp::reject::custom)?;
+                    Ok::<_, Rejection>((principal, body))
+                }
+            },
+        )
+        .untuple_one()
+}
+
+/// A signed request with a JSON body, deserialized within `limits` after the
+/// signature over it checks out.
+pub fn signed_json<S, T>(service: Arc<S>, limits: BodyLimits) -> impl Filter<Extract = (Principal, T), Error = Rejection> + Clone
+where
+    S: AuthService + Send + Sync + 'static,
+    T: DeserializeOwned + Send + 'static,
+{
+    require_signature(service, limits)
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::path::full())
+        .and_then(move |principal: Principal, body: Bytes, content_type: Option<String>, path: FullPath| async move {
+            let body = json_from_bytes(content_type.as_deref(), &body, limits, instance(&path))?;
+            Ok::<_, Rejection>((principal, body))
+        })
+        .untuple_one()
+}
+
+fn signed_request(
+    authorization: &str,
+    method: &Method,
+    path: &FullPath,
+    query: String,
+    headers: &HeaderMap,
+    body: &Bytes,
+) -> Option<SignedRequest> {
+    let (client_id, signed_headers, signature) = SignedRequest::parse_authorization(authorization)?;
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let headers = signed_headers
+        .into_iter()
+        .map(|name| header(&name).map(|value| (name, value)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(SignedRequest {
+        client_id,
+        signature,
+        timestamp: header(TIMESTAMP_HEADER)?.parse().ok()?,
+        nonce: header(NONCE_HEADER)?,
+        method: method.to_string(),
+        path: path.as_str().to_string(),
+        query,
+        headers,
+        body: body.to_vec(),
+    })
+}
+
+pub fn client_context() -> impl Filter<Extract = (ClientContext,), Error = Rejection> + Clone {
+    warp::header::optional::<String>("user-agent")
+        .and(warp::addr::remote())
+        .map(|user_agent: Option<String>, remote: Option<std::net::SocketAddr>| ClientContext {
+            user_agent,
+            client_ip: remote.map(|addr| addr.ip().to_string()),
+            dpop_jkt: None,
+        })
+}
+
+/// Client context for the token endpoint. A `DPoP` header, when present,
+/// must hold a valid proof for this request and binds the issued token to
+/// its key.
+pub fn token_request_context<S: AuthService + Send + Sync + 'static>(
+    service: Arc<S>,
+    config: Arc<Config>,
+) -> impl Filter<Extract = (ClientContext,), Error = Rejection> + Clone {
+    client_context()
+        .and(dpop_request(config))
+        .and_then(move |mut context: ClientContext, (proof, method, url): (Option<String>, Method, String)| {
+            let svc = Arc::clone(&service);
+            async move {
+                if let Some(proof) = proof {
+                    let jkt = svc
+                        .verify_dpop_proof(&proof, method.as_str(), &url, None)
+                        .await
+                        .map_err(warp::reject::custom)?;
+                    context.dpop_jkt = Some(jkt);
+                }
+                Ok::<_, Rejection>(context)
+            }
+        })
+}
+
+/// Builds a `Set-Cookie` value honouring the configured `Secure`/`SameSite`
+/// attributes; a `max_age` of zero tells the browser to drop the cookie.
+pub fn build_cookie(config: &Config, name: &str, value: &str, http_only: bool, max_age: i64) -> String {
+    let mut cookie = format!(
+        "{}={}; Path=/; Max-Age={}; SameSite={}",
+        name, value, max_age, config.session_cookie_same_site
+    );
+    if http_only {
+        cookie.push_str("; HttpOnly");
+    }
+    if config.session_cookie_secure {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
+
+/// Only lets the request through when cookie sessions are enabled, so the
+/// browser endpoints answer 404 in bearer-only deployments.
+pub fn session_cookies_enabled(config: Arc<Config>) -> impl Filter<Extract = (Arc<Config>,), Error = Rejection> + Clone {
+    warp::any().and_then(move || {
+        let config = Arc::clone(&config);
+        async move {
+            if config.session_cookies {
+                Ok(config)
+            } else {
+                Err(warp::reject::custom(ApiError::RouteNotFound))
+            }
+        }
+    })
+}
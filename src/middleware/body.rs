@@ -0,0 +1,175 @@
+//! Guardrails for JSON request bodies. A body must be sent as JSON, stay
+//! within `BodyLimits`, and parse into its DTO; each failure is answered
+//! with its own problem: 415, 413, or 400 pointing at the line and column
+//! where parsing stopped.
+//!
+//! Unknown fields are ignored unless the DTO opts out with
+//! `#[serde(deny_unknown_fields)]`, in which case they are reported as
+//! `UnknownField`.
+
+use futures_util::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::error::Category;
+use warp::hyper::body::{Buf, Bytes};
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+use crate::config::BodyLimits;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::{ApiError, Failure};
+use crate::middleware::validator::instance;
+
+/// Reads and deserializes a JSON body within `limits`. Use it instead of
+/// `warp::body::json`, which reads bodies of any size and answers parse
+/// errors with a 500.
+pub fn json_body<T>(limits: BodyLimits) -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    warp::header::optional::<String>("content-type")
+        .and(warp::path::full())
+        .and_then(|content_type: Option<String>, path: FullPath| async move {
+            if is_json(content_type.as_deref()) {
+                Ok(())
+            } else {
+                Err(problem(ErrorCodes::UnsupportedMediaType, Vec::new(), None, instance(&path)))
+            }
+        })
+        .untuple_one()
+        .and(bytes_body(limits))
+        .and(warp::path::full())
+        .and_then(move |body: Bytes, path: FullPath| async move { decode(&body, limits, instance(&path)) })
+}
+
+/// Reads a body of any type, refusing it once it is larger than
+/// `limits.max_bytes`.
+pub(crate) fn bytes_body(limits: BodyLimits) -> impl Filter<Extract = (Bytes,), Error = Rejection> + Clone {
+    warp::header::optional::<u64>("content-length")
+        .and(warp::path::full())
+        .and_then(move |length: Option<u64>, path: FullPath| async move {
+            let instance = instance(&path);
+            // Refused before reading when the client announces the size.
+            if length.is_some_and(|length| length > limits.max_bytes as u64) {
+                return Err(too_large(limits, instance));
+            }
+            Ok(instance)
+        })
+        .and(warp::body::stream())
+        .and_then(move |instance: Option<String>, stream| async move {
+            read_body(stream, limits, &instance).await.map(Bytes::from)
+        })
+}
+
+/// Deserializes a body that `bytes_body` already read, such as the one
+/// checked by `require_signature`. Only the content type, depth and parse
+/// checks are left to run.
+pub(crate) fn json_from_bytes<T: DeserializeOwned>(
+    content_type: Option<&str>,
+    body: &[u8],
+    limits: BodyLimits,
+    instance: Option<String>,
+) -> Result<T, Rejection> {
+    if !is_json(content_type) {
+        return Err(problem(ErrorCodes::UnsupportedMediaType, Vec::new(), None, instance));
+    }
+    decode(body, limits, instance)
+}
+
+fn decode<T: DeserializeOwned>(body: &[u8], limits: BodyLimits, instance: Option<String>) -> Result<T, Rejection> {
+    if nesting_depth(body) > limits.max_depth {
+        let args = vec![("max", limits.max_depth.to_string())];
+        return Err(problem(ErrorCodes::JsonTooDeep, args, None, instance));
+    }
+    serde_json::from_slice::<T>(body).map_err(|error| parse_problem(&error, instance))
+}
+
+/// Collects the body, refusing it as soon as it grows past the limit.
+async fn read_body<S, B>(stream: S, limits: BodyLimits, instance: &Option<String>) -> Result<Vec<u8>, Rejection>
+where
+    S: Stream<Item = Result<B, warp::Error>>,
+    B: Buf,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut body = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let mut chunk = chunk.map_err(|e| warp::reject::custom(ApiError::BadRequest(e.to_string(), 0)))?;
+        if body.len() + chunk.remaining() > limits.max_bytes {
+            return Err(too_large(limits, instance.clone()));
+        }
+        while chunk.has_remaining() {
+            let bytes = chunk.chunk();
+            body.extend_from_slice(bytes);
+            let read = bytes.len();
+            chunk.advance(read);
+        }
+    }
+    Ok(body)
+}
+
+/// `application/json` or a JSON-based type such as `application/scim+json`,
+/// with any parameters.
+fn is_json(content_type: Option<&str>) -> bool {
+    let Some(content_type) = content_type else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    essence == "application/json" || (essence.starts_with("application/") && essence.ends_with("+json"))
+}
+
+/// How deeply objects and arrays nest in `body`, found without parsing it
+/// so a deep body is refused before serde recurses into it.
+fn nesting_depth(body: &[u8]) -> usize {
+    let (mut depth, mut deepest) = (0usize, 0usize);
+    let (mut in_string, mut escaped) = (false, false);
+    for &byte in body {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'{' | b'[' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+fn parse_problem(error: &serde_json::Error, instance: Option<String>) -> Rejection {
+    let message = error.to_string();
+    // serde_json appends the position, which is reported on its own.
+    let reason = message.rsplit_once(" at line ").map_or(message.as_str(), |(reason, _)| reason);
+    let mut args = vec![("line", error.line().to_string()), ("column", error.column().to_string())];
+
+    let unknown = reason
+        .strip_prefix("unknown field `")
+        .and_then(|rest| rest.split_once('`'))
+        .map(|(field, _)| field.to_string());
+    if let Some(field) = unknown {
+        return problem(ErrorCodes::UnknownField, args, Some(field), instance);
+    }
+    match error.classify() {
+        Category::Data => {
+            args.push(("reason", reason.to_string()));
+            problem(ErrorCodes::InvalidJsonBody, args, None, instance)
+        }
+        _ => problem(ErrorCodes::MalformedJson, args, None, instance),
+    }
+}
+
+fn too_large(limits: BodyLimits, instance: Option<String>) -> Rejection {
+    problem(ErrorCodes::BodyTooLarge, vec![("max", limits.max_bytes.to_string())], None, instance)
+}
+
+fn problem(code: ErrorCodes, args: Vec<(&'static str, String)>, field: Option<String>, instance: Option<String>) -> Rejection {
+    warp::reject::custom(ApiError::MultipleErrors(Some(vec![Failure { code, args }]), field, instance))
+}
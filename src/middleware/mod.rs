@@ -1 +1,4 @@
-pub mod validator;
\ No newline at end of file
+pub mod auth;
+pub mod body;
+pub mod normalizer;
+pub mod validator;
@@ -0,0 +1,86 @@
+//! Normalization of DTO fields before their rules run. Normalizers are
+//! declared next to the rules, as `#[validate(normalize(trim, nfc), ...)]`,
+//! and applied in that order by `Validate::normalize`; `validated_json` and
+//! `validated_query` run them, so handlers get the normalized DTO.
+
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(dead_code)]
+pub enum Normalizer {
+    /// Removes leading and trailing whitespace.
+    Trim,
+    /// Replaces every run of whitespace with one space.
+    CollapseWhitespace,
+    /// Unicode Normalization Form C, so composed and decomposed accents
+    /// compare equal.
+    Nfc,
+    /// Removes control characters but line breaks and tabs, and zero-width
+    /// characters such as U+200B and the byte order mark.
+    StripControl,
+    Lowercase,
+}
+
+/// Zero-width characters removed by `StripControl`.
+const ZERO_WIDTH: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+impl Normalizer {
+    pub fn apply_to(self, value: &str) -> String {
+        match self {
+            Normalizer::Trim => value.trim().to_string(),
+            Normalizer::CollapseWhitespace => {
+                let mut collapsed = String::with_capacity(value.len());
+                let mut in_whitespace = false;
+                for c in value.chars() {
+                    if c.is_whitespace() {
+                        if !in_whitespace {
+                            collapsed.push(' ');
+                        }
+                        in_whitespace = true;
+                    } else {
+                        collapsed.push(c);
+                        in_whitespace = false;
+                    }
+                }
+                collapsed
+            }
+            Normalizer::Nfc => value.nfc().collect(),
+            Normalizer::StripControl => value.chars().filter(|c| !is_stripped(*c)).collect(),
+            Normalizer::Lowercase => value.to_lowercase(),
+        }
+    }
+}
+
+fn is_stripped(c: char) -> bool {
+    let line_break_or_tab = matches!(c, '\n' | '\r' | '\t');
+    (c.is_control() && !line_break_or_tab) || ZERO_WIDTH.contains(&c)
+}
+
+/// Field types that normalizers apply to: text, and options and lists of it.
+pub trait Normalize {
+    fn normalize_with(&mut self, normalizers: &[Normalizer]);
+}
+
+impl Normalize for String {
+    fn normalize_with(&mut self, normalizers: &[Normalizer]) {
+        for normalizer in normalizers {
+            *self = normalizer.apply_to(self);
+        }
+    }
+}
+
+impl<T: Normalize> Normalize for Option<T> {
+    fn normalize_with(&mut self, normalizers: &[Normalizer]) {
+        if let Some(value) = self {
+            value.normalize_with(normalizers);
+        }
+    }
+}
+
+impl<T: Normalize> Normalize for Vec<T> {
+    fn normalize_with(&mut self, normalizers: &[Normalizer]) {
+        for item in self {
+            item.normalize_with(normalizers);
+        }
+    }
+}
@@ -1,25 +1,94 @@
-#![allow(dead_code, unused_variables)]
-
-use crate::errors::ApiError;
-use crate::errors::error_codes::ErrorCodes;
-use warp::Rejection;
+#![allow(dead_code, unused_variables)]
+
+use crate::config::BodyLimits;
+use crate::errors::{ApiError, Failure};
+use crate::errors::error_codes::ErrorCodes;
+use crate::middleware::body::json_body;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+pub use validate_derive::Validate;
+
+/// Common passwords rejected by `not_common_password`, one per line, lowercase.
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterClass {
+    Uppercase,
+    Lowercase,
+    Digit,
+    /// Anything that is not a letter or a digit.
+    Symbol,
+}
+
+type CustomCheck<T> = Box<dyn Fn(&T) -> Option<ErrorCodes> + Send + Sync>;
+type AsyncCheck<T> = Box<dyn Fn(&T) -> Pin<Box<dyn Future<Output = Option<ErrorCodes>> + Send>> + Send + Sync>;
 
 enum ValidationRule<T> {
     NotNull,
     NotEmpty,
     MaxLength(usize),
+    MinLength(usize),
     WithinRange(T, T),
+    Before(T),
+    After(T),
     IsInteger,
     IsDecimal,
     IsNumber,
     HasDecimals,
+    HasCharacterClass(CharacterClass),
+    NotContaining(String),
+    NotCommonPassword,
+    Pattern(Regex),
+    Email,
+    Url,
+    Uuid,
+    OneOf(Vec<String>),
+    IsoDate,
+    IsoDateTime,
+    StartsWith(String),
+    EndsWith(String),
+    Contains(String),
+    UniqueItems(CustomCheck<T>),
+    /// A comparison with the value of another field, named by the string.
+    CompareTo(CustomCheck<T>, String),
+    Custom(CustomCheck<T>),
+    CustomAsync(AsyncCheck<T>),
+}
+
+/// A JSON Schema keyword implied by a rule, for the OpenAPI document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Keyword {
+    Required,
+    /// `minItems` on lists.
+    MinLength(usize),
+    /// `maxItems` on lists.
+    MaxLength(usize),
+    Minimum(f64),
+    Maximum(f64),
+    Pattern(String),
+    Format(&'static str),
+    Enum(Vec<String>),
+    UniqueItems,
 }
 
 pub struct Rule<'a, T> {
     value: Option<&'a T>,
     rules: Vec<RuleItem<T>>,
     instance: Option<String>,
-    field: Option<String>
+    field: Option<String>,
+    /// Whether the rules apply, once `when` or `unless` made them
+    /// conditional.
+    condition: Option<bool>,
 }
 
 pub struct RuleItem<T> {
@@ -27,30 +96,420 @@ pub struct RuleItem<T> {
     error_code: Option<ErrorCodes>
 }
 
+/// How a type takes part in each rule. Every check passes unless the type
+/// overrides it, so a type only implements the rules that apply to it.
 pub trait Validation {
-    fn validate(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
-    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
-    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
-    fn check_max_length(&self, max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
-    fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>
+    fn validate(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    /// The value as shown in the `{value}`, `{min}` and `{max}` placeholders
+    /// of error messages.
+    fn display_value(&self) -> Option<String> {
+        None
+    }
+    /// The value as a JSON Schema `minimum` or `maximum`, for numbers.
+    fn as_number(&self) -> Option<f64> {
+        None
+    }
+    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(value.is_none(), error_code)
+    }
+    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_max_length(&self, max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_min_length(&self, min: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_before(&self, limit: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_after(&self, limit: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_character_class(&self, class: CharacterClass, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_not_containing(&self, needle: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_not_common_password(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_pattern(&self, pattern: &Regex, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_email(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_url(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_uuid(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_one_of(&self, allowed: &[String], error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_iso_date(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_iso_datetime(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_starts_with(&self, prefix: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_ends_with(&self, suffix: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+    fn check_contains(&self, needle: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+}
+
+/// Types with the text rules: `String` and `Option<String>`.
+pub trait Text: Validation {}
+
+/// Types with `within_range`: numbers, dates and their options. Bounds are
+/// given as the plain type, also for options.
+pub trait Bounded: Validation {
+    type Bound;
+
+    fn from_bound(bound: Self::Bound) -> Self;
+}
+
+/// Types with the decimal rules, such as `is_integer`.
+pub trait Float: Bounded {}
+
+/// Types with `before`, `after` and `between`.
+pub trait Dated: Bounded {}
+
+fn fail_if(failed: bool, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+    if failed {
+        Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+    } else {
+        None
+    }
+}
+
+/// A DTO whose fields carry `#[validate(...)]` rules. Derive it rather than
+/// implementing it by hand.
+pub trait Validate {
+    /// Adds the rules of every field to `validator`, under its current path.
+    fn rules(&self, validator: Validator) -> Validator;
+
+    /// Applies the `normalize(...)` entries of every field, including those
+    /// of nested DTOs. Runs before `validate`.
+    fn normalize(&mut self) {}
+
+    /// Runs every field's rules and reports all failing fields together;
+    /// `instance` is the request path reported in the problem response.
+    fn validate(&self, instance: Option<String>) -> Result<(), ApiError> {
+        self.rules(Validator::new(instance)).check()
+    }
+
+    /// The rules of every field as JSON Schema keywords, for the OpenAPI
+    /// document.
+    fn schema() -> Schema
     where
-        Self: PartialOrd;
-    fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
-    fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
-    fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
-    fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>;
+        Self: Sized,
+    {
+        Schema::default()
+    }
+}
+
+impl<V: Validate> Validate for Option<V> {
+    fn rules(&self, validator: Validator) -> Validator {
+        match self {
+            Some(value) => value.rules(validator),
+            None => validator,
+        }
+    }
+
+    fn normalize(&mut self) {
+        if let Some(value) = self {
+            value.normalize();
+        }
+    }
+
+    fn schema() -> Schema {
+        V::schema()
+    }
+}
+
+impl<V: Validate> Validate for Vec<V> {
+    fn rules(&self, validator: Validator) -> Validator {
+        self.entries()
+            .into_iter()
+            .fold(validator, |validator, (segment, item)| validator.nested(&segment, item))
+    }
+
+    fn normalize(&mut self) {
+        self.iter_mut().for_each(Validate::normalize);
+    }
+
+    fn schema() -> Schema {
+        V::schema()
+    }
+}
+
+impl<K: Display, V: Validate> Validate for HashMap<K, V> {
+    fn rules(&self, validator: Validator) -> Validator {
+        self.entries()
+            .into_iter()
+            .fold(validator, |validator, (segment, item)| validator.nested(&segment, item))
+    }
+
+    fn normalize(&mut self) {
+        self.values_mut().for_each(Validate::normalize);
+    }
+
+    fn schema() -> Schema {
+        V::schema()
+    }
+}
+
+impl<K: Display, V: Validate> Validate for BTreeMap<K, V> {
+    fn rules(&self, validator: Validator) -> Validator {
+        self.entries()
+            .into_iter()
+            .fold(validator, |validator, (segment, item)| validator.nested(&segment, item))
+    }
+
+    fn normalize(&mut self) {
+        self.values_mut().for_each(Validate::normalize);
+    }
+
+    fn schema() -> Schema {
+        V::schema()
+    }
+}
+
+/// The rules of a DTO as JSON Schema keywords by field, and every error
+/// code they can report, including those of nested DTOs.
+#[derive(Debug, Default)]
+pub struct Schema {
+    pub fields: Vec<FieldSchema>,
+    pub codes: Vec<ErrorCodes>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct FieldSchema {
+    /// The name used in JSON.
+    pub field: String,
+    pub keywords: Vec<Keyword>,
+    /// The keywords of every element of a list or map, from `each`.
+    pub items: Vec<Keyword>,
+}
+
+impl Schema {
+    pub fn field<T: Validation>(mut self, field: &str, rule: Rule<'_, T>) -> Self {
+        let (keywords, codes) = rule.describe();
+        self.entry(field).keywords.extend(keywords);
+        self.add_codes(codes);
+        self
+    }
+
+    pub fn each<T: Validation>(mut self, field: &str, rule: Rule<'_, T>) -> Self {
+        let (keywords, codes) = rule.describe();
+        self.entry(field).items.extend(keywords);
+        self.add_codes(codes);
+        self
+    }
+
+    /// Adds the codes of a nested DTO; its keywords belong to its own schema.
+    pub fn nested<V: Validate>(mut self) -> Self {
+        self.add_codes(V::schema().codes);
+        self
+    }
+
+    fn entry(&mut self, field: &str) -> &mut FieldSchema {
+        let index = match self.fields.iter().position(|f| f.field == field) {
+            Some(index) => index,
+            None => {
+                self.fields.push(FieldSchema {
+                    field: field.to_string(),
+                    ..FieldSchema::default()
+                });
+                self.fields.len() - 1
+            }
+        };
+        &mut self.fields[index]
+    }
+
+    fn add_codes(&mut self, codes: Vec<ErrorCodes>) {
+        for code in codes {
+            if !self.codes.contains(&code) {
+                self.codes.push(code);
+            }
+        }
+    }
+}
+
+/// Lists and maps whose elements `Validator::each` checks one by one.
+pub trait Collection {
+    type Item;
+
+    /// Every element with its path segment: the index of a list item or
+    /// the key of a map entry. Maps are listed by key so reports are stable.
+    fn entries(&self) -> Vec<(String, &Self::Item)>;
+}
+
+impl<T> Collection for Vec<T> {
+    type Item = T;
+
+    fn entries(&self) -> Vec<(String, &T)> {
+        self.iter().enumerate().map(|(index, item)| (index.to_string(), item)).collect()
+    }
+}
+
+impl<K: Display, T> Collection for HashMap<K, T> {
+    type Item = T;
+
+    fn entries(&self) -> Vec<(String, &T)> {
+        let mut entries: Vec<(String, &T)> = self.iter().map(|(key, item)| (key.to_string(), item)).collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+}
+
+impl<K: Display, T> Collection for BTreeMap<K, T> {
+    type Item = T;
+
+    fn entries(&self) -> Vec<(String, &T)> {
+        self.iter().map(|(key, item)| (key.to_string(), item)).collect()
+    }
+}
+
+impl<C: Collection> Collection for Option<C> {
+    type Item = C::Item;
+
+    fn entries(&self) -> Vec<(String, &C::Item)> {
+        self.as_ref().map(Collection::entries).unwrap_or_default()
+    }
+}
+
+/// Escapes one JSON Pointer reference token (RFC 6901).
+fn pointer_token(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// The request path as reported in problem responses.
+pub(crate) fn instance(path: &FullPath) -> Option<String> {
+    Some(path.as_str().trim_start_matches('/').to_string())
+}
+
+/// Deserializes a JSON body within `limits`, like `body::json_body`,
+/// normalizes it and runs its `#[validate(...)]` rules.
+pub fn validated_json<T>(limits: BodyLimits) -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    T: Validate + DeserializeOwned + Send + 'static,
+{
+    json_body(limits)
+        .and(warp::path::full())
+        .and_then(|mut body: T, path: FullPath| async move {
+            body.normalize();
+            body.validate(instance(&path)).map_err(warp::reject::custom)?;
+            Ok::<_, Rejection>(body)
+        })
+}
+
+/// Deserializes the query string, normalizes it and runs its
+/// `#[validate(...)]` rules. A query string that does not fit `T` is
+/// rejected with `InvalidQueryString`.
+pub fn validated_query<T>() -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    T: Validate + DeserializeOwned + Send + 'static,
+{
+    warp::query::<T>()
+        .map(Some)
+        .or(warp::any().map(|| None))
+        .unify()
+        .and(warp::path::full())
+        .and_then(|query: Option<T>, path: FullPath| async move {
+            let Some(mut query) = query else {
+                let errors = Some(vec![ErrorCodes::InvalidQueryString.into()]);
+                return Err(warp::reject::custom(ApiError::MultipleErrors(errors, None, instance(&path))));
+            };
+            query.normalize();
+            query.validate(instance(&path)).map_err(warp::reject::custom)?;
+            Ok::<_, Rejection>(query)
+        })
+}
+
+/// Extracts the next path segment, like `warp::path::param` but
+/// percent-decoded, and checks it with the rules built by `rule`; failures
+/// name the parameter `name`.
+pub fn validated_param<T, F>(name: &'static str, rule: F) -> impl Filter<Extract = (T,), Error = Rejection> + Clone
+where
+    T: FromStr + Validation + Send + 'static,
+    F: for<'b> Fn(Rule<'b, T>) -> Rule<'b, T> + Clone + Send + Sync + 'static,
+{
+    warp::path::param::<String>()
+        .and(warp::path::full())
+        .and_then(move |segment: String, path: FullPath| {
+            let rule = rule.clone();
+            async move {
+                let value = percent_encoding::percent_decode_str(&segment)
+                    .decode_utf8()
+                    .ok()
+                    .and_then(|decoded| decoded.parse::<T>().ok())
+                    .ok_or_else(warp::reject::not_found)?;
+                rule(Rule::new(Some(&value), Some(name.to_string()), instance(&path))).validate()?;
+                Ok::<_, Rejection>(value)
+            }
+        })
+}
+
+/// Extracts a required header and checks it with the rules built by `rule`.
+/// A missing header is reported as `MissingHeader` under its name.
+pub fn validated_header<F>(name: &'static str, rule: F) -> impl Filter<Extract = (String,), Error = Rejection> + Clone
+where
+    F: for<'b> Fn(Rule<'b, String>) -> Rule<'b, String> + Clone + Send + Sync + 'static,
+{
+    warp::header::optional::<String>(name)
+        .and(warp::path::full())
+        .and_then(move |value: Option<String>, path: FullPath| {
+            let rule = rule.clone();
+            async move {
+                let required = Rule::new(value.as_ref(), Some(name.to_string()), instance(&path))
+                    .not_null()
+                    .with_error_code(ErrorCodes::MissingHeader);
+                rule(required).validate()?;
+                Ok::<_, Rejection>(value.unwrap_or_default())
+            }
+        })
 }
 
 impl<'a, T> Rule<'a, T>
 where
-    T: Validation + PartialOrd,
+    T: Validation,
 {
     pub fn new(value: Option<&'a T>, field:Option<String>, instance: Option<String>) -> Self {//,field: Option<String>, instance: Option<String> ) -> Self {
         Rule {
             value,
             rules: Vec::new(),
             instance,
-            field
+            field,
+            condition: None,
         }
     }
 
@@ -66,305 +525,700 @@ where
         self
     }
 
-    pub fn field_name(mut self, field: Option<String>) -> Self {
-        self.field = field;
-        self
-    }
+    pub fn field_name(mut self, field: Option<String>) -> Self {
+        self.field = field;
+        self
+    }
+
+    /// Applies the rules only if `condition` holds, such as a check on
+    /// another field of the DTO. Several conditions must all hold.
+    pub fn when(mut self, condition: bool) -> Self {
+        self.condition = Some(self.condition.unwrap_or(true) && condition);
+        self
+    }
+
+    /// Applies the rules only if `condition` does not hold.
+    pub fn unless(self, condition: bool) -> Self {
+        self.when(!condition)
+    }
+
+    /// The only rule that fails for a missing value.
+    pub fn not_null(self) -> Self {
+        self.push(ValidationRule::NotNull, None)
+    }
+
+    /// Adds a check written as code. It returns the error code to report,
+    /// or `None` when the value is valid; `with_error_code` replaces it.
+    pub fn custom<F>(self, check: F) -> Self
+    where
+        F: Fn(&T) -> Option<ErrorCodes> + Send + Sync + 'static,
+    {
+        self.push(ValidationRule::Custom(Box::new(check)), None)
+    }
+
+    /// Like `custom`, for checks that await I/O such as a repository lookup.
+    /// The check gets the value by reference and must clone what its future
+    /// keeps. Only `check_async` and `Validator::rule_async` run these.
+    pub fn custom_async<F, Fut>(self, check: F) -> Self
+    where
+        F: Fn(&T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Option<ErrorCodes>> + Send + 'static,
+    {
+        let check: AsyncCheck<T> = Box::new(move |value| Box::pin(check(value)));
+        self.push(ValidationRule::CustomAsync(check), None)
+    }
+
+    pub fn validate(&self) -> Result<Option<&'a T>, Rejection> {
+        self.check().map_err(warp::reject::custom)
+    }
+
+    /// Runs the rules outside a filter, for services that validate values
+    /// they did not receive directly in a request body.
+    pub fn check(&self) -> Result<Option<&'a T>, ApiError> {
+        self.result(self.failures())
+    }
+
+    /// Runs the rules, then awaits the async ones.
+    pub async fn check_async(&self) -> Result<Option<&'a T>, ApiError> {
+        self.result(self.failures_async().await)
+    }
+
+    fn result(&self, errors: Vec<Failure>) -> Result<Option<&'a T>, ApiError> {
+        if errors.is_empty() {
+            Ok(self.value)
+        } else {
+            Err(ApiError::MultipleErrors(Some(errors), self.field.clone(), self.instance.clone()))
+        }
+    }
+
+    async fn failures_async(&self) -> Vec<Failure> {
+        let mut errors = self.failures();
+        let Some(value) = self.value.filter(|_| self.applies()) else {
+            return errors;
+        };
+        for rule in &self.rules {
+            if let ValidationRule::CustomAsync(check) = &rule.validation_rule {
+                if let Some(error) = check(value).await {
+                    errors.push(self.failure(rule, rule.error_code.clone().unwrap_or(error)));
+                }
+            }
+        }
+        errors
+    }
+
+    /// Every rule that fails, in declaration order. Only `NotNull` looks at
+    /// a missing value; the other rules pass for it.
+    fn failures(&self) -> Vec<Failure> {
+        let mut errors = Vec::new();
+        if !self.applies() {
+            return errors;
+        }
+
+        for rule in &self.rules {
+            let code = rule.error_code.clone();
+            let error = match (&rule.validation_rule, self.value) {
+                (ValidationRule::NotNull, value) => T::check_not_null(&value, code),
+                (_, None) => None,
+                (ValidationRule::NotEmpty, Some(value)) => value.check_not_empty(code),
+                (ValidationRule::MaxLength(max), Some(value)) => value.check_max_length(*max, code),
+                (ValidationRule::MinLength(min), Some(value)) => value.check_min_length(*min, code),
+                (ValidationRule::WithinRange(min, max), Some(value)) => value.check_within_range(min, max, code),
+                (ValidationRule::Before(limit), Some(value)) => value.check_before(limit, code),
+                (ValidationRule::After(limit), Some(value)) => value.check_after(limit, code),
+                (ValidationRule::IsInteger, Some(value)) => value.check_is_integer(code),
+                (ValidationRule::IsDecimal, Some(value)) => value.check_is_decimal(code),
+                (ValidationRule::IsNumber, Some(value)) => value.check_is_number(code),
+                (ValidationRule::HasDecimals, Some(value)) => value.check_has_decimals(code),
+                (ValidationRule::HasCharacterClass(class), Some(value)) => value.check_character_class(*class, code),
+                (ValidationRule::NotContaining(needle), Some(value)) => value.check_not_containing(needle, code),
+                (ValidationRule::NotCommonPassword, Some(value)) => value.check_not_common_password(code),
+                (ValidationRule::Pattern(pattern), Some(value)) => value.check_pattern(pattern, code),
+                (ValidationRule::Email, Some(value)) => value.check_email(code),
+                (ValidationRule::Url, Some(value)) => value.check_url(code),
+                (ValidationRule::Uuid, Some(value)) => value.check_uuid(code),
+                (ValidationRule::OneOf(allowed), Some(value)) => value.check_one_of(allowed, code),
+                (ValidationRule::IsoDate, Some(value)) => value.check_iso_date(code),
+                (ValidationRule::IsoDateTime, Some(value)) => value.check_iso_datetime(code),
+                (ValidationRule::StartsWith(prefix), Some(value)) => value.check_starts_with(prefix, code),
+                (ValidationRule::EndsWith(suffix), Some(value)) => value.check_ends_with(suffix, code),
+                (ValidationRule::Contains(needle), Some(value)) => value.check_contains(needle, code),
+                (
+                    ValidationRule::Custom(check) | ValidationRule::UniqueItems(check) | ValidationRule::CompareTo(check, _),
+                    Some(value),
+                ) => {
+                    check(value).map(|error| code.unwrap_or(error))
+                }
+                (ValidationRule::CustomAsync(_), Some(_)) => None,
+            };
+
+            if let Some(error) = error {
+                errors.push(self.failure(rule, error));
+            }
+        }
+        errors
+    }
+
+    /// The failure of `rule`, with the values its message can show.
+    fn failure(&self, rule: &RuleItem<T>, code: ErrorCodes) -> Failure {
+        let mut args = Vec::new();
+        if let Some(value) = self.value.and_then(Validation::display_value) {
+            args.push(("value", value));
+        }
+        let mut bound = |name, value: &T| {
+            if let Some(value) = value.display_value() {
+                args.push((name, value));
+            }
+        };
+        match &rule.validation_rule {
+            ValidationRule::MaxLength(max) => args.push(("max", max.to_string())),
+            ValidationRule::MinLength(min) => args.push(("min", min.to_string())),
+            ValidationRule::WithinRange(min, max) => {
+                bound("min", min);
+                bound("max", max);
+            }
+            ValidationRule::Before(limit) => bound("max", limit),
+            ValidationRule::After(limit) => bound("min", limit),
+            ValidationRule::CompareTo(_, other) => args.push(("other", other.clone())),
+            _ => {}
+        }
+        Failure { code, args }
+    }
+
+    fn applies(&self) -> bool {
+        self.condition.unwrap_or(true)
+    }
+
+    /// The JSON Schema keywords these rules imply and the error codes they
+    /// can report, for the OpenAPI document. Custom checks only add a code
+    /// when `with_error_code` names it, and conditional rules only add
+    /// codes, since their keywords do not always hold.
+    pub fn describe(&self) -> (Vec<Keyword>, Vec<ErrorCodes>) {
+        let mut keywords = Vec::new();
+        let mut codes = Vec::new();
+        for rule in &self.rules {
+            let (keyword, default) = match &rule.validation_rule {
+                ValidationRule::NotNull => (Some(Keyword::Required), ErrorCodes::Nodeclared),
+                ValidationRule::NotEmpty => (Some(Keyword::MinLength(1)), ErrorCodes::Nodeclared),
+                ValidationRule::MaxLength(max) => (Some(Keyword::MaxLength(*max)), ErrorCodes::Nodeclared),
+                ValidationRule::MinLength(min) => (Some(Keyword::MinLength(*min)), ErrorCodes::Nodeclared),
+                ValidationRule::Pattern(pattern) => (Some(Keyword::Pattern(pattern.to_string())), ErrorCodes::Nodeclared),
+                ValidationRule::Email => (Some(Keyword::Format("email")), ErrorCodes::Nodeclared),
+                ValidationRule::Url => (Some(Keyword::Format("uri")), ErrorCodes::Nodeclared),
+                ValidationRule::Uuid => (Some(Keyword::Format("uuid")), ErrorCodes::Nodeclared),
+                ValidationRule::IsoDate => (Some(Keyword::Format("date")), ErrorCodes::Nodeclared),
+                ValidationRule::IsoDateTime => (Some(Keyword::Format("date-time")), ErrorCodes::Nodeclared),
+                ValidationRule::OneOf(allowed) => (Some(Keyword::Enum(allowed.clone())), ErrorCodes::Nodeclared),
+                ValidationRule::UniqueItems(_) => (Some(Keyword::UniqueItems), ErrorCodes::DuplicateItems),
+                ValidationRule::WithinRange(min, max) => {
+                    if let (Some(min), Some(max)) = (min.as_number(), max.as_number()) {
+                        keywords.push(Keyword::Minimum(min));
+                        keywords.push(Keyword::Maximum(max));
+                    }
+                    (None, ErrorCodes::Nodeclared)
+                }
+                ValidationRule::Custom(_) | ValidationRule::CustomAsync(_) => {
+                    if let Some(code) = &rule.error_code {
+                        codes.push(code.clone());
+                    }
+                    continue;
+                }
+                _ => (None, ErrorCodes::Nodeclared),
+            };
+            keywords.extend(keyword);
+            codes.push(rule.error_code.clone().unwrap_or(default));
+        }
+        if self.condition.is_some() {
+            keywords.clear();
+        }
+        (keywords, codes)
+    }
+
+    fn push(mut self, validation_rule: ValidationRule<T>, error_code: Option<ErrorCodes>) -> Self {
+        self.rules.push(RuleItem {
+            validation_rule,
+            error_code,
+        });
+        self
+    }
+}
+
+/// Runs the rules of every field of a DTO and reports all failing fields in
+/// one problem response, each with its own codes. Fields are named by their
+/// JSON Pointer, such as `/attachments/2/name`.
+pub struct Validator {
+    instance: Option<String>,
+    path: String,
+    errors: Vec<(Option<String>, Vec<Failure>)>,
+}
+
+impl Validator {
+    pub fn new(instance: Option<String>) -> Self {
+        Validator {
+            instance,
+            path: String::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Adds one field's rule. The field name is taken relative to the
+    /// current path.
+    pub fn rule<T>(self, rule: Rule<'_, T>) -> Self
+    where
+        T: Validation,
+    {
+        let failures = rule.failures();
+        self.add(rule.field, failures)
+    }
+
+    /// Adds one field's rule, including its async checks.
+    pub async fn rule_async<T>(self, rule: Rule<'_, T>) -> Self
+    where
+        T: Validation + Sync,
+    {
+        let failures = rule.failures_async().await;
+        self.add(rule.field, failures)
+    }
+
+    /// Validates a nested object, list of objects or map of objects; its
+    /// fields are reported under `field`.
+    pub fn nested<V: Validate + ?Sized>(self, field: &str, value: &V) -> Self {
+        value.rules(self.enter(field)).leave()
+    }
+
+    /// Applies the rules built by `rule` to every element of a list or map,
+    /// reporting each failing element under `field` and its index or key.
+    pub fn each<C, F>(self, field: &str, items: &C, rule: F) -> Self
+    where
+        C: Collection,
+        C::Item: Validation,
+        F: for<'b> Fn(Rule<'b, C::Item>) -> Rule<'b, C::Item>,
+    {
+        items
+            .entries()
+            .into_iter()
+            .fold(self.enter(field), |validator, (segment, item)| {
+                validator.rule(rule(Rule::new(Some(item), Some(segment), None)))
+            })
+            .leave()
+    }
+
+    /// Moves the current path one level down, into `segment`. Pair it with
+    /// `leave` when adding rules by hand.
+    pub fn enter(mut self, segment: &str) -> Self {
+        self.path = format!("{}/{}", self.path, pointer_token(segment));
+        self
+    }
+
+    /// Moves the current path back up one level.
+    pub fn leave(mut self) -> Self {
+        let parent = self.path.rfind('/').unwrap_or(0);
+        self.path.truncate(parent);
+        self
+    }
+
+    fn add(mut self, field: Option<String>, failures: Vec<Failure>) -> Self {
+        if !failures.is_empty() {
+            let pointer = match field {
+                Some(field) => Some(format!("{}/{}", self.path, pointer_token(&field))),
+                None if self.path.is_empty() => None,
+                None => Some(self.path.clone()),
+            };
+            self.errors.push((pointer, failures));
+        }
+        self
+    }
+
+    pub fn validate(self) -> Result<(), Rejection> {
+        self.check().map_err(warp::reject::custom)
+    }
+
+    pub fn check(self) -> Result<(), ApiError> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ApiError::FieldErrors(self.errors, self.instance))
+        }
+    }
+}
+
+
+
+impl Validation for String {
+    fn validate(&self, _error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+
+    fn display_value(&self) -> Option<String> {
+        Some(self.clone())
+    }
+
+    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        if value.is_none() {
+            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+        } else {
+            None
+        }
+    }
+
+    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        if self.is_empty() {
+            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+        } else {
+            None
+        }
+    }
+
+    /// Counts characters, not bytes, like `check_min_length`.
+    fn check_max_length(&self, max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        if self.chars().count() > max {
+            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+        } else {
+            None
+        }
+    }
+
+    /// Counts characters, not bytes, so accented letters count once.
+    fn check_min_length(&self, min: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        if self.chars().count() < min {
+            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+        } else {
+            None
+        }
+    }
+
+    fn check_within_range(&self, _min: &Self, _max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+
+    fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+
+    fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+
+    fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+
+    fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        None
+    }
+
+    fn check_character_class(&self, class: CharacterClass, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        let matches = |c: char| match class {
+            CharacterClass::Uppercase => c.is_uppercase(),
+            CharacterClass::Lowercase => c.is_lowercase(),
+            CharacterClass::Digit => c.is_numeric(),
+            CharacterClass::Symbol => !c.is_alphanumeric(),
+        };
+        if self.chars().any(matches) {
+            None
+        } else {
+            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+        }
+    }
+
+    fn check_not_containing(&self, needle: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        if !needle.is_empty() && self.to_lowercase().contains(&needle.to_lowercase()) {
+            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+        } else {
+            None
+        }
+    }
+
+    fn check_not_common_password(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        let candidate = self.to_lowercase();
+        if COMMON_PASSWORDS.lines().any(|common| common == candidate) {
+            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
+        } else {
+            None
+        }
+    }
+
+    fn check_pattern(&self, pattern: &Regex, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(!pattern.is_match(self), error_code)
+    }
+
+    /// An `addr-spec` as accepted for sending mail, without a display name.
+    fn check_email(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(self.parse::<lettre::Address>().is_err(), error_code)
+    }
+
+    /// An absolute URL with a host.
+    fn check_url(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        let valid = url::Url::parse(self).is_ok_and(|url| url.has_host());
+        fail_if(!valid, error_code)
+    }
+
+    /// The hyphenated form, in either case.
+    fn check_uuid(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(self.len() != 36 || uuid::Uuid::parse_str(self).is_err(), error_code)
+    }
+
+    fn check_one_of(&self, allowed: &[String], error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(!allowed.contains(self), error_code)
+    }
+
+    fn check_iso_date(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(NaiveDate::parse_from_str(self, "%Y-%m-%d").is_err(), error_code)
+    }
+
+    /// RFC 3339, the ISO 8601 profile with a mandatory offset.
+    fn check_iso_datetime(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(DateTime::parse_from_rfc3339(self).is_err(), error_code)
+    }
+
+    fn check_starts_with(&self, prefix: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(!self.starts_with(prefix), error_code)
+    }
+
+    fn check_ends_with(&self, suffix: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(!self.ends_with(suffix), error_code)
+    }
+
+    fn check_contains(&self, needle: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(!self.contains(needle), error_code)
+    }
+}
+
+impl Validation for bool {}
+
+macro_rules! integer_validation {
+    ($($t:ty),*) => {$(
+        impl Validation for $t {
+            fn display_value(&self) -> Option<String> {
+                Some(self.to_string())
+            }
+
+            fn as_number(&self) -> Option<f64> {
+                Some(*self as f64)
+            }
+
+            fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self < min || self > max, error_code)
+            }
+        }
+
+        impl Bounded for $t {
+            type Bound = $t;
+
+            fn from_bound(bound: $t) -> Self {
+                bound
+            }
+        }
+    )*};
+}
+
+integer_validation!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! float_validation {
+    ($($t:ty),*) => {$(
+        impl Validation for $t {
+            fn display_value(&self) -> Option<String> {
+                Some(self.to_string())
+            }
+
+            fn as_number(&self) -> Option<f64> {
+                Some(*self as f64)
+            }
+
+            fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self < min || self > max, error_code)
+            }
+
+            fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self.fract() != 0.0, error_code)
+            }
 
-    pub fn validate(&self) -> Result<Option<&'a T>, Rejection> {
-        let mut errors: Option<Vec<ErrorCodes>> = Some(Vec::new());
+            fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self.fract() == 0.0, error_code)
+            }
 
-        for rule in &self.rules {
-            let error = match &rule.validation_rule {
-                ValidationRule::NotNull => T::check_not_null(&self.value, rule.error_code.clone()),
-                ValidationRule::NotEmpty => {
-                    if let Some(value) = self.value {
-                        value.check_not_empty(rule.error_code.clone())
-                    } else {
-                        None
-                    }
-                },
-                ValidationRule::MaxLength(max) => {
-                    if let Some(value) = self.value {
-                        value.check_max_length(*max, rule.error_code.clone())
-                    } else {
-                        None
-                    }
-                },
-                ValidationRule::WithinRange(min, max) => {
-                    if let Some(value) = self.value {
-                        value.check_within_range(min, max, rule.error_code.clone())
-                    } else {
-                        None
-                    }
-                },
-                ValidationRule::IsInteger => {
-                    if let Some(value) = self.value {
-                        value.check_is_integer(rule.error_code.clone())
-                    } else {
-                        None
-                    }
-                },
-                ValidationRule::IsDecimal => {
-                    if let Some(value) = self.value {
-                        value.check_is_decimal(rule.error_code.clone())
-                    } else {
-                        None
-                    }
-                },
-                ValidationRule::IsNumber => {
-                    if let Some(value) = self.value {
-                        value.check_is_number(rule.error_code.clone())
-                    } else {
-                        None
-                    }
-                },
-                ValidationRule::HasDecimals => {
-                    if let Some(value) = self.value {
-                        value.check_has_decimals(rule.error_code.clone())
-                    } else {
-                        None
-                    }
-                },
-            };
+            /// NaN and the infinities are not numbers.
+            fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(!self.is_finite(), error_code)
+            }
 
-            if let Some(error) = error {
-                if let Some(ref mut error_list) = errors {
-                    error_list.push(error);
-                }
+            fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self.fract() == 0.0, error_code)
             }
         }
 
-        if let Some(error_list) = errors.clone() {
-            if error_list.is_empty() {
-                Ok(self.value)
-            } else {
-                Err(warp::reject::custom(ApiError::MultipleErrors(errors, self.field.clone(), self.instance.clone())))
+        impl Bounded for $t {
+            type Bound = $t;
+
+            fn from_bound(bound: $t) -> Self {
+                bound
             }
-        } else {
-            Err(warp::reject::custom(ApiError::ErrorCode(ErrorCodes::Nodeclared)))
         }
-    }
+
+        impl Float for $t {}
+    )*};
 }
 
-impl Validation for String {
-    fn validate(&self, _error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
-    }
+float_validation!(f32, f64);
 
-    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if value.is_none() {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
-    }
+macro_rules! date_validation {
+    ($($t:ty),*) => {$(
+        impl Validation for $t {
+            fn display_value(&self) -> Option<String> {
+                Some(self.to_string())
+            }
 
-    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if self.is_empty() {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
+            fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self < min || self > max, error_code)
+            }
+
+            fn check_before(&self, limit: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self >= limit, error_code)
+            }
+
+            fn check_after(&self, limit: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+                fail_if(self <= limit, error_code)
+            }
         }
-    }
 
-    fn check_max_length(&self, max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if self.len() > max {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
+        impl Bounded for $t {
+            type Bound = $t;
+
+            fn from_bound(bound: $t) -> Self {
+                bound
+            }
         }
-    }
 
-    fn check_within_range(&self, _min: &Self, _max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>
-    where
-        Self: PartialOrd,
-    {
-        None
-    }
+        impl Dated for $t {}
+    )*};
+}
 
-    fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+date_validation!(NaiveDate, DateTime<Utc>);
+
+/// A missing inner value passes every rule but `not_null`, as a missing
+/// field does.
+impl<T: Validation> Validation for Option<T> {
+    fn display_value(&self) -> Option<String> {
+        self.as_ref()?.display_value()
     }
 
-    fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn as_number(&self) -> Option<f64> {
+        self.as_ref()?.as_number()
     }
 
-    fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(!matches!(value, Some(Some(_))), error_code)
     }
 
-    fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_not_empty(error_code)
     }
-}
 
-impl Validation for u32 {
-    fn validate(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_max_length(&self, max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_max_length(max, error_code)
     }
 
-    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if value.is_none() {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
+    fn check_min_length(&self, min: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_min_length(min, error_code)
     }
 
-    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_within_range(min.as_ref()?, max.as_ref()?, error_code)
     }
 
-    fn check_max_length(&self, _max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_before(&self, limit: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_before(limit.as_ref()?, error_code)
     }
 
-    fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>
-    where
-        Self: PartialOrd,
-    {
-        if self < min || self > max {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
+    fn check_after(&self, limit: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_after(limit.as_ref()?, error_code)
     }
 
     fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+        self.as_ref()?.check_is_integer(error_code)
     }
 
     fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+        self.as_ref()?.check_is_decimal(error_code)
     }
 
     fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+        self.as_ref()?.check_is_number(error_code)
     }
 
     fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+        self.as_ref()?.check_has_decimals(error_code)
     }
-}
 
-impl Validation for bool {
-    fn validate(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_character_class(&self, class: CharacterClass, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_character_class(class, error_code)
     }
 
-    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if value.is_none() {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
+    fn check_not_containing(&self, needle: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_not_containing(needle, error_code)
     }
 
-    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_not_common_password(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_not_common_password(error_code)
     }
 
-    fn check_max_length(&self, _max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_pattern(&self, pattern: &Regex, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_pattern(pattern, error_code)
     }
 
-    fn check_within_range(&self, _min: &Self, _max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>
-    where
-        Self: PartialOrd,
-    {
-        None
+    fn check_email(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_email(error_code)
     }
 
-    fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_url(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_url(error_code)
     }
 
-    fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_uuid(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_uuid(error_code)
     }
 
-    fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_one_of(&self, allowed: &[String], error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_one_of(allowed, error_code)
     }
 
-    fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_iso_date(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_iso_date(error_code)
     }
-}
 
-impl Validation for f64 {
-    fn validate(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_iso_datetime(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_iso_datetime(error_code)
     }
 
-    fn check_not_null(value: &Option<&Self>, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if value.is_none() {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
+    fn check_starts_with(&self, prefix: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_starts_with(prefix, error_code)
     }
 
-    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_ends_with(&self, suffix: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_ends_with(suffix, error_code)
     }
 
-    fn check_max_length(&self, _max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
+    fn check_contains(&self, needle: &str, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        self.as_ref()?.check_contains(needle, error_code)
     }
+}
 
-    fn check_within_range(&self, min: &Self, max: &Self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes>
-    where
-        Self: PartialOrd,
-    {
-        if self < min || self > max {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
-    }
+impl<T: Text> Text for Option<T> {}
 
-    fn check_is_integer(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if self.fract() != 0.0 {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
-    }
+impl<T: Bounded> Bounded for Option<T> {
+    type Bound = T::Bound;
 
-    fn check_is_decimal(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if self.fract() == 0.0 {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
+    fn from_bound(bound: T::Bound) -> Self {
+        Some(T::from_bound(bound))
     }
+}
 
-    fn check_is_number(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        None
-    }
+impl<T: Float> Float for Option<T> {}
 
-    fn check_has_decimals(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
-        if self.fract() == 0.0 {
-            Some(error_code.unwrap_or(ErrorCodes::Nodeclared))
-        } else {
-            None
-        }
-    }
-}
+impl<T: Dated> Dated for Option<T> {}
 
-impl<'a> Rule<'a, String> {
-    pub fn not_null(mut self) -> Self {
-        let rule_item = RuleItem {
-            validation_rule: ValidationRule::NotNull,
-            error_code: None
-        };
-        self.rules.push(rule_item);
-        self
-    }
+impl Text for String {}
 
+impl<'a, T: Text> Rule<'a, T> {
     pub fn not_empty(mut self) -> Self {
         let rule_item = RuleItem {
             validation_rule: ValidationRule::NotEmpty,
@@ -382,63 +1236,234 @@ impl<'a> Rule<'a, String> {
         self.rules.push(rule_item);
         self
     }
-}
 
-impl<'a> Rule<'a, u32> {
-    pub fn within_range(mut self, min: u32, max: u32) -> Self {
+    pub fn min_length(mut self, min: usize) -> Self {
         let rule_item = RuleItem {
-            validation_rule: ValidationRule::WithinRange(min, max),
-            error_code: None
+            validation_rule: ValidationRule::MinLength(min),
+            error_code: Some(ErrorCodes::MinSize)
         };
         self.rules.push(rule_item);
         self
     }
-}
 
-impl<'a> Rule<'a, f64> {
-    pub fn is_integer(mut self) -> Self {
+    pub fn has_character_class(mut self, class: CharacterClass) -> Self {
         let rule_item = RuleItem {
-            validation_rule: ValidationRule::IsInteger,
+            validation_rule: ValidationRule::HasCharacterClass(class),
             error_code: None
         };
         self.rules.push(rule_item);
         self
     }
 
-    pub fn is_decimal(mut self) -> Self {
+    /// Case-insensitive; an empty `needle` always passes.
+    pub fn not_containing(mut self, needle: &str) -> Self {
         let rule_item = RuleItem {
-            validation_rule: ValidationRule::IsDecimal,
+            validation_rule: ValidationRule::NotContaining(needle.to_string()),
             error_code: None
         };
         self.rules.push(rule_item);
         self
     }
 
-    pub fn is_number(mut self) -> Self {
+    pub fn not_common_password(mut self) -> Self {
         let rule_item = RuleItem {
-            validation_rule: ValidationRule::IsNumber,
+            validation_rule: ValidationRule::NotCommonPassword,
             error_code: None
         };
         self.rules.push(rule_item);
         self
     }
 
-    pub fn has_decimals(mut self) -> Self {
-        let rule_item = RuleItem {
-            validation_rule: ValidationRule::HasDecimals,
-            error_code: None
-        };
-        self.rules.push(rule_item);
-        self
+    /// Matches anywhere in the value unless the pattern is anchored.
+    ///
+    /// # Panics
+    ///
+    /// If `pattern` is not a valid regular expression.
+    pub fn pattern(self, pattern: &str) -> Self {
+        let pattern = Regex::new(pattern).unwrap_or_else(|e| panic!("invalid validation pattern {}: {}", pattern, e));
+        self.push(ValidationRule::Pattern(pattern), Some(ErrorCodes::PatternMismatch))
     }
 
-    pub fn within_range(mut self, min: f64, max: f64) -> Self {
-        let rule_item = RuleItem {
-            validation_rule: ValidationRule::WithinRange(min, max),
-            error_code: None
+    pub fn email(self) -> Self {
+        self.push(ValidationRule::Email, Some(ErrorCodes::InvalidEmail))
+    }
+
+    pub fn url(self) -> Self {
+        self.push(ValidationRule::Url, Some(ErrorCodes::InvalidUrl))
+    }
+
+    pub fn uuid(self) -> Self {
+        self.push(ValidationRule::Uuid, Some(ErrorCodes::InvalidUuid))
+    }
+
+    /// Case-sensitive.
+    pub fn one_of(self, allowed: &[&str]) -> Self {
+        let allowed = allowed.iter().map(|a| a.to_string()).collect();
+        self.push(ValidationRule::OneOf(allowed), Some(ErrorCodes::NotAllowedValue))
+    }
+
+    /// `YYYY-MM-DD`.
+    pub fn iso_date(self) -> Self {
+        self.push(ValidationRule::IsoDate, Some(ErrorCodes::InvalidDate))
+    }
+
+    /// Such as `2024-05-01T13:45:00Z` or `2024-05-01T13:45:00+02:00`.
+    pub fn iso_datetime(self) -> Self {
+        self.push(ValidationRule::IsoDateTime, Some(ErrorCodes::InvalidDateTime))
+    }
+
+    pub fn starts_with(self, prefix: &str) -> Self {
+        self.push(ValidationRule::StartsWith(prefix.to_string()), Some(ErrorCodes::MissingPrefix))
+    }
+
+    pub fn ends_with(self, suffix: &str) -> Self {
+        self.push(ValidationRule::EndsWith(suffix.to_string()), Some(ErrorCodes::MissingSuffix))
+    }
+
+    /// Case-sensitive, unlike `not_containing`.
+    pub fn contains(self, needle: &str) -> Self {
+        self.push(ValidationRule::Contains(needle.to_string()), Some(ErrorCodes::MissingSubstring))
+    }
+}
+
+impl<'a, T: Bounded> Rule<'a, T> {
+    /// Inclusive at both ends.
+    pub fn within_range(self, min: T::Bound, max: T::Bound) -> Self {
+        self.push(ValidationRule::WithinRange(T::from_bound(min), T::from_bound(max)), None)
+    }
+}
+
+#[allow(clippy::wrong_self_convention)]
+impl<'a, T: Float> Rule<'a, T> {
+    pub fn is_integer(self) -> Self {
+        self.push(ValidationRule::IsInteger, None)
+    }
+
+    pub fn is_decimal(self) -> Self {
+        self.push(ValidationRule::IsDecimal, None)
+    }
+
+    pub fn is_number(self) -> Self {
+        self.push(ValidationRule::IsNumber, None)
+    }
+
+    pub fn has_decimals(self) -> Self {
+        self.push(ValidationRule::HasDecimals, None)
+    }
+}
+
+impl<'a, T: Dated> Rule<'a, T> {
+    /// Strictly earlier than `limit`.
+    pub fn before(self, limit: T::Bound) -> Self {
+        self.push(ValidationRule::Before(T::from_bound(limit)), Some(ErrorCodes::DateTooLate))
+    }
+
+    /// Strictly later than `limit`.
+    pub fn after(self, limit: T::Bound) -> Self {
+        self.push(ValidationRule::After(T::from_bound(limit)), Some(ErrorCodes::DateTooEarly))
+    }
+
+    /// Inclusive at both ends.
+    pub fn between(self, first: T::Bound, last: T::Bound) -> Self {
+        let range = ValidationRule::WithinRange(T::from_bound(first), T::from_bound(last));
+        self.push(range, Some(ErrorCodes::DateOutOfRange))
+    }
+}
+
+/// Comparisons with another field of the same DTO, given as its value and
+/// its name. They pass while either value is missing; pair them with
+/// `not_null` where both are required.
+impl<'a, T> Rule<'a, T>
+where
+    T: Validation + PartialOrd + Clone + Send + Sync + 'static,
+{
+    /// Such as a password confirmation.
+    pub fn equal_to_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o == Ordering::Equal, ErrorCodes::FieldMismatch)
+    }
+
+    pub fn different_from_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o != Ordering::Equal, ErrorCodes::FieldNotDifferent)
+    }
+
+    pub fn less_than_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o == Ordering::Less, ErrorCodes::NotLessThanField)
+    }
+
+    pub fn greater_than_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o == Ordering::Greater, ErrorCodes::NotGreaterThanField)
+    }
+
+    pub fn at_most_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o != Ordering::Greater, ErrorCodes::AboveField)
+    }
+
+    pub fn at_least_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o != Ordering::Less, ErrorCodes::BelowField)
+    }
+
+    /// Fails unless `passes` holds for how the value orders against the
+    /// other one; values that do not order, such as `NaN`, fail.
+    fn compare_to(self, other: Option<&T>, field: &str, passes: fn(Ordering) -> bool, code: ErrorCodes) -> Self {
+        let other = other.cloned();
+        let default = code.clone();
+        let check = move |value: &T| {
+            let other = other.as_ref()?;
+            let passed = value.partial_cmp(other).is_some_and(passes);
+            (!passed).then(|| default.clone())
         };
-        self.rules.push(rule_item);
-        self
+        self.push(ValidationRule::CompareTo(Box::new(check), field.to_string()), Some(code))
+    }
+}
+
+impl<'a, T> Rule<'a, T>
+where
+    T: Dated + PartialOrd + Clone + Send + Sync + 'static,
+{
+    /// Strictly earlier than the other field, such as a start before its end.
+    pub fn before_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o == Ordering::Less, ErrorCodes::NotBeforeField)
+    }
+
+    /// Strictly later than the other field.
+    pub fn after_field(self, other: Option<&T>, field: &str) -> Self {
+        self.compare_to(other, field, |o| o == Ordering::Greater, ErrorCodes::NotAfterField)
+    }
+}
+
+/// Lists count items for `min_length` and `max_length`.
+impl<T> Validation for Vec<T> {
+    fn check_not_empty(&self, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(self.is_empty(), error_code)
+    }
+
+    fn check_max_length(&self, max: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(self.len() > max, error_code)
+    }
+
+    fn check_min_length(&self, min: usize, error_code: Option<ErrorCodes>) -> Option<ErrorCodes> {
+        fail_if(self.len() < min, error_code)
     }
 }
 
+impl<'a, T: 'static> Rule<'a, Vec<T>> {
+    pub fn min_items(self, min: usize) -> Self {
+        self.push(ValidationRule::MinLength(min), Some(ErrorCodes::TooFewItems))
+    }
+
+    pub fn max_items(self, max: usize) -> Self {
+        self.push(ValidationRule::MaxLength(max), Some(ErrorCodes::TooManyItems))
+    }
+
+    /// No two items are equal.
+    pub fn unique_items(self) -> Self
+    where
+        T: PartialEq,
+    {
+        let check = |items: &Vec<T>| {
+            let duplicated = items.iter().enumerate().any(|(i, item)| items[..i].contains(item));
+            duplicated.then_some(ErrorCodes::DuplicateItems)
+        };
+        self.push(ValidationRule::UniqueItems(Box::new(check)), None)
+    }
+}
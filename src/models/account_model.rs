@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::middleware::validator::Validate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    PasswordReset,
+    EmailVerification,
+}
+
+/// A single-use token mailed to a user. Only its hash is stored.
+#[derive(Debug, Clone)]
+pub struct ActionTokenModel {
+    pub hashed: String,
+    pub user_id: String,
+    pub purpose: TokenPurpose,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::ToSchema, Validate)]
+pub struct ForgotPasswordDto {
+    #[validate(normalize(trim), not_null(code = NotNull), not_empty(code = NotEmpty))]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::ToSchema, Validate)]
+pub struct ResetPasswordDto {
+    #[validate(normalize(trim), not_null(code = NotNull), not_empty(code = NotEmpty))]
+    pub token: Option<String>,
+    #[validate(not_null(code = NotNull), not_empty(code = NotEmpty))]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::ToSchema, Validate)]
+pub struct VerifyEmailDto {
+    #[validate(normalize(trim), not_null(code = NotNull), not_empty(code = NotEmpty))]
+    pub token: Option<String>,
+}
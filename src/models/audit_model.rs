@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    TokenIssued,
+    LoginFailed,
+    SessionRevoked,
+    SessionsRevokedAll,
+    MessageCreated,
+    KeysRotated,
+    UserCreated,
+    UserUpdated,
+    UserDeleted,
+    GroupCreated,
+    GroupUpdated,
+    GroupDeleted,
+    PasswordReset,
+    EmailVerified,
+    PasskeyRegistered,
+    DeviceApproved,
+    DeviceDenied,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub action: AuditAction,
+    pub outcome: AuditOutcome,
+    pub actor: Option<String>,
+    pub client_ip: Option<String>,
+    pub target: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema, utoipa::ToResponse)]
+pub struct AuditEntry {
+    pub sequence: u64,
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    pub outcome: AuditOutcome,
+    pub actor: Option<String>,
+    pub client_ip: Option<String>,
+    pub target: Option<String>,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub action: Option<AuditAction>,
+    pub actor: Option<String>,
+    pub outcome: Option<AuditOutcome>,
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub from: Option<DateTime<Utc>>,
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.action.is_none_or(|a| a == entry.action)
+            && self.outcome.is_none_or(|o| o == entry.outcome)
+            && self
+                .actor
+                .as_ref()
+                .is_none_or(|a| entry.actor.as_ref() == Some(a))
+            && self.from.is_none_or(|f| entry.timestamp >= f)
+            && self.to.is_none_or(|t| entry.timestamp <= t)
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+pub struct AuditVerificationDto {
+    pub valid: bool,
+    pub entries: usize,
+}
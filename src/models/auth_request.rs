@@ -4,6 +4,51 @@ use utoipa::ToSchema;
 #[derive(Serialize, Deserialize, Debug, ToSchema)]
 #[serde(tag = "grant_type", rename_all = "snake_case")]
 pub enum AuthRequestDto {
-    User { username: String, password: String },
-    Client { client_id: String, client_secret: String },
+    User {
+        username: String,
+        password: String,
+        #[serde(default)]
+        scope: Option<String>,
+    },
+    Client {
+        client_id: String,
+        client_secret: String,
+        #[serde(default)]
+        scope: Option<String>,
+    },
+    /// A passkey assertion answering a challenge from `/auth/webauthn/login/options`.
+    /// Binary members are base64url encoded.
+    Webauthn {
+        credential_id: String,
+        client_data_json: String,
+        authenticator_data: String,
+        signature: String,
+        #[serde(default)]
+        user_handle: Option<String>,
+        #[serde(default)]
+        scope: Option<String>,
+    },
+    /// Polls a code from `/auth/device`; the scope is the one requested there.
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:device_code")]
+    DeviceCode { device_code: String, client_id: String },
+}
+
+impl AuthRequestDto {
+    pub fn subject(&self) -> &str {
+        match self {
+            AuthRequestDto::User { username, .. } => username,
+            AuthRequestDto::Client { client_id, .. } => client_id,
+            AuthRequestDto::Webauthn { credential_id, .. } => credential_id,
+            AuthRequestDto::DeviceCode { client_id, .. } => client_id,
+        }
+    }
+
+    pub fn scope(&self) -> Option<&str> {
+        match self {
+            AuthRequestDto::User { scope, .. }
+            | AuthRequestDto::Client { scope, .. }
+            | AuthRequestDto::Webauthn { scope, .. } => scope.as_deref(),
+            AuthRequestDto::DeviceCode { .. } => None,
+        }
+    }
 }
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeviceAuthorizationRequestDto {
+    pub client_id: String,
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+/// RFC 8628 device authorization response. Show `user_code` and
+/// `verification_uri` to the user, then poll `/auth/token` with
+/// `device_code` every `interval` seconds.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeviceAuthorizationResponseDto {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct DeviceVerificationDto {
+    pub user_code: String,
+    /// `false` denies the request; the polling device then gets `access_denied`.
+    #[serde(default = "default_approve")]
+    pub approve: bool,
+}
+
+fn default_approve() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeviceVerificationQuery {
+    /// Prefills the code, as sent by `verification_uri_complete`.
+    pub user_code: Option<String>,
+}
+
+/// The user and scope an approved device code is redeemed for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceGrant {
+    pub username: String,
+    pub scope: Option<String>,
+}
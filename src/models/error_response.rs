@@ -13,4 +13,16 @@ pub struct ValidationProblem {
    pub field: Option<String>,
    pub message: String,
    pub error_code: u16,
+}
+
+/// RFC 6749 error body, answered to device code polls because device flow
+/// clients read `error` instead of a problem.
+#[derive(Debug, Serialize, utoipa::ToResponse, utoipa::ToSchema)]
+pub struct OAuthErrorResponse {
+   /// `authorization_pending`, `slow_down`, `expired_token`, `access_denied` or `invalid_grant`.
+   pub error: String,
+   pub error_description: String,
+   /// Seconds to wait between polls from now on; sent with `slow_down`.
+   #[serde(skip_serializing_if = "Option::is_none")]
+   pub interval: Option<i64>,
 }
\ No newline at end of file
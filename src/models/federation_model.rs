@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// What the provider sends back to the callback; `error` replaces `code`
+/// when the user cancelled or the provider refused the login.
+#[derive(Debug, Clone, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Where `login` sends the browser, and the value of the cookie that binds
+/// the login to that browser.
+#[derive(Debug, Clone)]
+pub struct LoginRedirect {
+    pub url: String,
+    pub binding: String,
+}
+
+/// Provider URL a signed-in user opens to link that provider to their
+/// account.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct LinkRedirectDto {
+    pub url: String,
+}
+
+/// The parts of a provider's discovery document the login flow uses.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
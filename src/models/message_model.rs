@@ -1,18 +1,21 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct MessageModel {
-  pub id: usize,
-  pub content: String
-}
-
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, utoipa::ToSchema)]
-pub struct CreateMessageModelDto{
-  pub content:Option<String>
-}
-
-#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
-pub struct MessageResponseDto {
-  pub id: usize,
-  pub content: String
+use serde::{Deserialize, Serialize};
+
+use crate::middleware::validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageModel {
+  pub id: usize,
+  pub content: String
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, utoipa::ToSchema, Validate)]
+pub struct CreateMessageModelDto{
+  #[validate(normalize(strip_control, trim, nfc), not_null(code = NotNull), not_empty(code = NotEmpty), max_length(32, code = MaxSize))]
+  pub content:Option<String>
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+pub struct MessageResponseDto {
+  pub id: usize,
+  pub content: String
 }
\ No newline at end of file
@@ -2,3 +2,13 @@ pub mod error_response;
 pub mod message_model;
 pub mod token_model;
 pub mod auth_request;
+pub mod session_model;
+pub mod audit_model;
+pub mod oidc_model;
+pub mod user_model;
+pub mod scim_model;
+pub mod mail_model;
+pub mod account_model;
+pub mod webauthn_model;
+pub mod federation_model;
+pub mod device_model;
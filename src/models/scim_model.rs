@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const GROUP_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+pub const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+pub const PATCH_OP_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+pub const SERVICE_PROVIDER_CONFIG_SCHEMA: &str =
+    "urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig";
+pub const SCHEMA_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Schema";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMetaDto {
+    pub resource_type: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created: DateTime<Utc>,
+    #[schema(value_type = String, format = DateTime)]
+    pub last_modified: DateTime<Utc>,
+    pub location: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimNameDto {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ScimEmailDto {
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary: Option<bool>,
+}
+
+/// A member of a group, or a group on a user (`display` is read-only).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ScimReferenceDto {
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserDto {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub user_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<ScimNameDto>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub emails: Vec<ScimEmailDto>,
+    #[serde(default)]
+    pub active: Option<bool>,
+    /// Write-only; never returned.
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub groups: Vec<ScimReferenceDto>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ScimMetaDto>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupDto {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    pub display_name: String,
+    #[serde(default)]
+    pub members: Vec<ScimReferenceDto>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<ScimMetaDto>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserListDto {
+    pub schemas: Vec<String>,
+    pub total_results: usize,
+    pub start_index: usize,
+    pub items_per_page: usize,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<ScimUserDto>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupListDto {
+    pub schemas: Vec<String>,
+    pub total_results: usize,
+    pub start_index: usize,
+    pub items_per_page: usize,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<ScimGroupDto>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListQuery {
+    /// SCIM filter, e.g. `userName eq "bjensen"`.
+    pub filter: Option<String>,
+    /// 1-based index of the first result.
+    pub start_index: Option<usize>,
+    pub count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimPatchOperationDto {
+    /// `add`, `replace` or `remove`.
+    pub op: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, utoipa::ToSchema)]
+pub struct ScimPatchRequestDto {
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperationDto>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ScimSupportedDto {
+    pub supported: bool,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimFilterSupportDto {
+    pub supported: bool,
+    pub max_results: usize,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimAuthenticationSchemeDto {
+    #[serde(rename = "type")]
+    pub scheme_type: String,
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceProviderConfigDto {
+    pub schemas: Vec<String>,
+    pub patch: ScimSupportedDto,
+    pub bulk: ScimSupportedDto,
+    pub filter: ScimFilterSupportDto,
+    pub change_password: ScimSupportedDto,
+    pub sort: ScimSupportedDto,
+    pub etag: ScimSupportedDto,
+    pub authentication_schemes: Vec<ScimAuthenticationSchemeDto>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimAttributeDto {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub attribute_type: String,
+    pub multi_valued: bool,
+    pub required: bool,
+    pub case_exact: bool,
+    pub mutability: String,
+    pub returned: String,
+    pub uniqueness: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ScimSchemaDto {
+    pub schemas: Vec<String>,
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub attributes: Vec<ScimAttributeDto>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimSchemaListDto {
+    pub schemas: Vec<String>,
+    pub total_results: usize,
+    pub start_index: usize,
+    pub items_per_page: usize,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<ScimSchemaDto>,
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Clone)]
+pub struct SessionModel {
+    pub id: String,
+    pub subject: String,
+    pub grant_type: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub client_ip: Option<String>,
+    pub csrf_hash: Option<String>,
+    pub scope: Option<String>,
+    pub jkt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClientContext {
+    pub user_agent: Option<String>,
+    pub client_ip: Option<String>,
+    /// Thumbprint of the key a DPoP proof on the token request was signed
+    /// with; the issued token is bound to it.
+    pub dpop_jkt: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+    pub subject: String,
+    pub grant_type: String,
+    pub session_id: String,
+    pub is_admin: bool,
+    pub dpop_jkt: Option<String>,
+    pub scope: Option<String>,
+}
+
+impl Principal {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope
+            .as_deref()
+            .is_some_and(|s| s.split_whitespace().any(|s| s == scope))
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+pub struct SessionResponseDto {
+    pub id: String,
+    pub grant_type: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub issued_at: DateTime<Utc>,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub client_ip: Option<String>,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct BrowserSession {
+    pub token: String,
+    pub csrf_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+pub struct LoginResponseDto {
+    pub csrf_token: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
+pub struct RevokedSessionsDto {
+    pub revoked: usize,
+}
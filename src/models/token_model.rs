@@ -3,4 +3,8 @@ use serde::Serialize;
 #[derive(Debug, Serialize, utoipa::ToSchema, utoipa::ToResponse)]
 pub struct TokenResponseDto {
     pub token: String,
+    /// `Bearer`, or `DPoP` when the token is bound to the caller's key.
+    pub token_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
 }
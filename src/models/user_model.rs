@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+
+/// A user as kept by the credential store. The password is stored next to
+/// it by the repository and never leaves it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserAccount {
+    pub id: String,
+    pub username: String,
+    pub external_id: Option<String>,
+    pub given_name: Option<String>,
+    pub family_name: Option<String>,
+    pub display_name: Option<String>,
+    pub emails: Vec<String>,
+    /// Set once the user confirms the code mailed to their primary address.
+    pub email_verified: bool,
+    pub active: bool,
+    pub created: DateTime<Utc>,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl UserAccount {
+    pub fn new(username: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            username: username.to_string(),
+            external_id: None,
+            given_name: None,
+            family_name: None,
+            display_name: None,
+            emails: Vec::new(),
+            email_verified: false,
+            active: true,
+            created: now,
+            last_modified: now,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupModel {
+    pub id: String,
+    pub display_name: String,
+    pub external_id: Option<String>,
+    /// Ids of the member users.
+    pub members: Vec<String>,
+    pub created: DateTime<Utc>,
+    pub last_modified: DateTime<Utc>,
+}
+
+impl GroupModel {
+    pub fn new(display_name: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            display_name: display_name.to_string(),
+            external_id: None,
+            members: Vec::new(),
+            created: now,
+            last_modified: now,
+        }
+    }
+}
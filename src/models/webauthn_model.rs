@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A passkey registered by a user. The public key is the uncompressed SEC1
+/// encoding of its P-256 point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebauthnCredential {
+    /// Base64url credential id chosen by the authenticator.
+    pub id: String,
+    pub user_id: String,
+    pub public_key: Vec<u8>,
+    pub sign_count: u32,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RelyingPartyDto {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebauthnUserDto {
+    /// Base64url user handle.
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PubKeyCredParamDto {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub alg: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CredentialDescriptorDto {
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelectionDto {
+    pub resident_key: String,
+    pub user_verification: String,
+}
+
+/// `PublicKeyCredentialCreationOptions` with binary members base64url encoded.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationOptionsDto {
+    pub challenge: String,
+    pub rp: RelyingPartyDto,
+    pub user: WebauthnUserDto,
+    pub pub_key_cred_params: Vec<PubKeyCredParamDto>,
+    pub timeout: u64,
+    pub attestation: String,
+    pub exclude_credentials: Vec<CredentialDescriptorDto>,
+    pub authenticator_selection: AuthenticatorSelectionDto,
+}
+
+/// `PublicKeyCredentialRequestOptions` with binary members base64url encoded.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptionsDto {
+    pub challenge: String,
+    pub rp_id: String,
+    pub timeout: u64,
+    pub allow_credentials: Vec<CredentialDescriptorDto>,
+    pub user_verification: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LoginOptionsRequestDto {
+    /// Limits `allowCredentials` to this user's passkeys; omit for discoverable credentials.
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationResponseDto {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    pub attestation_object: String,
+}
+
+/// The `PublicKeyCredential` returned by `navigator.credentials.create()`.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RegistrationCredentialDto {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub credential_type: String,
+    pub response: AttestationResponseDto,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RegisteredCredentialDto {
+    pub id: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created: DateTime<Utc>,
+}
+
+/// The members of an assertion from `navigator.credentials.get()`, base64url encoded.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebauthnAssertion {
+    pub credential_id: String,
+    pub client_data_json: String,
+    pub authenticator_data: String,
+    pub signature: String,
+    pub user_handle: Option<String>,
+}
@@ -0,0 +1,54 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::{Arc, Mutex};
+
+use crate::models::account_model::{ActionTokenModel, TokenPurpose};
+
+#[async_trait]
+pub trait ActionTokenRepository: Send + Sync {
+    /// Stores a token, replacing any earlier token of the same purpose for
+    /// that user so only the most recent email works.
+    async fn store(&self, token: ActionTokenModel);
+    /// Removes and returns the token if it exists, matches `purpose` and has
+    /// not expired.
+    async fn consume(&self, hashed: &str, purpose: TokenPurpose) -> Option<ActionTokenModel>;
+}
+
+pub struct InMemoryActionTokenRepository {
+    tokens: Arc<Mutex<Vec<ActionTokenModel>>>,
+}
+
+impl InMemoryActionTokenRepository {
+    pub fn new() -> Self {
+        Self {
+            tokens: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl Default for InMemoryActionTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ActionTokenRepository for InMemoryActionTokenRepository {
+    async fn store(&self, token: ActionTokenModel) {
+        let mut tokens = self.tokens.lock().unwrap();
+        let now = Utc::now();
+        tokens.retain(|t| {
+            t.expires_at > now && !(t.user_id == token.user_id && t.purpose == token.purpose)
+        });
+        tokens.push(token);
+    }
+
+    async fn consume(&self, hashed: &str, purpose: TokenPurpose) -> Option<ActionTokenModel> {
+        let mut tokens = self.tokens.lock().unwrap();
+        let index = tokens
+            .iter()
+            .position(|t| t.hashed == hashed && t.purpose == purpose)?;
+        let token = tokens.remove(index);
+        (token.expires_at > Utc::now()).then_some(token)
+    }
+}
@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::models::audit_model::AuditEntry;
+
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    async fn append(&self, entry: AuditEntry);
+    async fn last_entry(&self) -> Option<AuditEntry>;
+    async fn entries(&self) -> Vec<AuditEntry>;
+}
+
+pub struct InMemoryAuditRepository {
+    entries: Arc<Mutex<Vec<AuditEntry>>>,
+}
+
+impl InMemoryAuditRepository {
+    pub fn new() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl Default for InMemoryAuditRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl AuditRepository for InMemoryAuditRepository {
+    async fn append(&self, entry: AuditEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    async fn last_entry(&self) -> Option<AuditEntry> {
+        self.entries.lock().unwrap().last().cloned()
+    }
+
+    async fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+/// Appends every entry as one JSON document per line, so the log can be
+/// shipped or inspected with standard tooling.
+pub struct JsonlAuditRepository {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl JsonlAuditRepository {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read_entries(&self) -> Vec<AuditEntry> {
+        let _guard = self.lock.lock().unwrap();
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(_) => return Vec::new(),
+        };
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AuditRepository for JsonlAuditRepository {
+    async fn append(&self, entry: AuditEntry) {
+        let _guard = self.lock.lock().unwrap();
+        let result = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| {
+                let line = serde_json::to_string(&entry).map_err(std::io::Error::other)?;
+                writeln!(file, "{}", line)
+            });
+        if let Err(e) = result {
+            eprintln!("failed to write audit entry to {}: {}", self.path.display(), e);
+        }
+    }
+
+    async fn last_entry(&self) -> Option<AuditEntry> {
+        self.read_entries().pop()
+    }
+
+    async fn entries(&self) -> Vec<AuditEntry> {
+        self.read_entries()
+    }
+}
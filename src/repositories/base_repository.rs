@@ -21,6 +21,12 @@ impl InMemoryBaseRepository {
   }
 }
 
+impl Default for InMemoryBaseRepository {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 #[async_trait]
 impl BaseRepository for InMemoryBaseRepository {
   async fn get_messages(&self) -> Vec<MessageModel> {
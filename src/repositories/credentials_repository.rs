@@ -1,29 +1,119 @@
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
+use crate::models::user_model::UserAccount;
+use crate::models::webauthn_model::WebauthnCredential;
+
 #[async_trait]
 pub trait CredentialRepository: Send + Sync {
     async fn validate_user(&self, username: &str, password: &str) -> bool;
     async fn validate_client(&self, client_id: &str, client_secret: &str) -> bool;
+    async fn is_admin(&self, username: &str) -> bool;
+    async fn client_scopes(&self, client_id: &str) -> Vec<String>;
+    /// The shared secret service clients key their request signatures with.
+    async fn client_secret(&self, client_id: &str) -> Option<String>;
+    async fn client_exists(&self, client_id: &str) -> bool;
+    async fn list_users(&self) -> Vec<UserAccount>;
+    async fn find_user(&self, id: &str) -> Option<UserAccount>;
+    async fn find_user_by_username(&self, username: &str) -> Option<UserAccount>;
+    /// Looks up a user by any of their addresses, ignoring case.
+    async fn find_user_by_email(&self, email: &str) -> Option<UserAccount>;
+    /// Inserts or replaces the user with `account.id`. A `None` password keeps
+    /// the current one. Returns false if another user already has the username.
+    async fn save_user(&self, account: UserAccount, password: Option<String>) -> bool;
+    /// Deleting a user also drops their passkeys.
+    async fn delete_user(&self, id: &str) -> bool;
+    /// Inserts or replaces the passkey with `credential.id`.
+    async fn save_webauthn_credential(&self, credential: WebauthnCredential);
+    async fn find_webauthn_credential(&self, id: &str) -> Option<WebauthnCredential>;
+    async fn list_webauthn_credentials(&self, user_id: &str) -> Vec<WebauthnCredential>;
+    /// Links the `subject` of an external identity provider to a local user.
+    async fn link_identity(&self, provider: &str, subject: &str, user_id: &str);
+    async fn find_linked_user(&self, provider: &str, subject: &str) -> Option<UserAccount>;
+}
+
+struct StoredUser {
+    account: UserAccount,
+    password: Option<String>,
+}
+
+struct StoredClient {
+    secret: String,
+    scopes: Vec<String>,
 }
 
+/// Clones share the same underlying store.
+#[derive(Clone)]
 pub struct InMemoryCredentialRepository {
-    users: Arc<Mutex<HashMap<String, String>>>,
-    clients: Arc<Mutex<HashMap<String, String>>>,
+    users: Arc<Mutex<HashMap<String, StoredUser>>>,
+    clients: Arc<Mutex<HashMap<String, StoredClient>>>,
+    admins: Arc<Mutex<HashSet<String>>>,
+    webauthn_credentials: Arc<Mutex<HashMap<String, WebauthnCredential>>>,
+    /// `(provider, subject)` to user id.
+    identities: Arc<Mutex<HashMap<(String, String), String>>>,
 }
 
 impl InMemoryCredentialRepository {
     pub fn new() -> Self {
+        let admin = UserAccount {
+            email_verified: true,
+            ..UserAccount::new("admin")
+        };
         let mut users = HashMap::new();
-        users.insert("admin".to_string(), "password".to_string());
+        users.insert(
+            admin.id.clone(),
+            StoredUser {
+                account: admin,
+                password: Some("password".to_string()),
+            },
+        );
         let mut clients = HashMap::new();
-        clients.insert("client".to_string(), "secret".to_string());
+        clients.insert(
+            "client".to_string(),
+            StoredClient {
+                secret: "secret".to_string(),
+                scopes: vec!["openid".to_string(), "introspect".to_string()],
+            },
+        );
+        let mut admins = HashSet::new();
+        admins.insert("admin".to_string());
         Self {
             users: Arc::new(Mutex::new(users)),
             clients: Arc::new(Mutex::new(clients)),
+            admins: Arc::new(Mutex::new(admins)),
+            webauthn_credentials: Arc::new(Mutex::new(HashMap::new())),
+            identities: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+
+    /// Registers another client next to the seeded one.
+    pub fn add_client(&self, client_id: &str, secret: &str, scopes: &[&str]) {
+        self.clients.lock().unwrap().insert(
+            client_id.to_string(),
+            StoredClient {
+                secret: secret.to_string(),
+                scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            },
+        );
+    }
+
+    /// A store without the seeded admin user and client.
+    pub fn empty() -> Self {
+        Self {
+            users: Arc::new(Mutex::new(HashMap::new())),
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            admins: Arc::new(Mutex::new(HashSet::new())),
+            webauthn_credentials: Arc::new(Mutex::new(HashMap::new())),
+            identities: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for InMemoryCredentialRepository {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[async_trait]
@@ -32,8 +122,9 @@ impl CredentialRepository for InMemoryCredentialRepository {
         self.users
             .lock()
             .unwrap()
-            .get(username)
-            .map(|p| p == password)
+            .values()
+            .find(|u| u.account.username == username)
+            .map(|u| u.account.active && u.password.as_deref() == Some(password))
             .unwrap_or(false)
     }
 
@@ -42,7 +133,128 @@ impl CredentialRepository for InMemoryCredentialRepository {
             .lock()
             .unwrap()
             .get(client_id)
-            .map(|s| s == client_secret)
+            .map(|c| c.secret == client_secret)
             .unwrap_or(false)
     }
+
+    async fn is_admin(&self, username: &str) -> bool {
+        self.admins.lock().unwrap().contains(username)
+    }
+
+    async fn client_scopes(&self, client_id: &str) -> Vec<String> {
+        self.clients
+            .lock()
+            .unwrap()
+            .get(client_id)
+            .map(|c| c.scopes.clone())
+            .unwrap_or_default()
+    }
+
+    async fn client_secret(&self, client_id: &str) -> Option<String> {
+        self.clients.lock().unwrap().get(client_id).map(|c| c.secret.clone())
+    }
+
+    async fn client_exists(&self, client_id: &str) -> bool {
+        self.clients.lock().unwrap().contains_key(client_id)
+    }
+
+    async fn list_users(&self) -> Vec<UserAccount> {
+        let mut users: Vec<UserAccount> = self
+            .users
+            .lock()
+            .unwrap()
+            .values()
+            .map(|u| u.account.clone())
+            .collect();
+        users.sort_by(|a, b| a.created.cmp(&b.created).then_with(|| a.username.cmp(&b.username)));
+        users
+    }
+
+    async fn find_user(&self, id: &str) -> Option<UserAccount> {
+        self.users.lock().unwrap().get(id).map(|u| u.account.clone())
+    }
+
+    async fn find_user_by_username(&self, username: &str) -> Option<UserAccount> {
+        self.users
+            .lock()
+            .unwrap()
+            .values()
+            .find(|u| u.account.username == username)
+            .map(|u| u.account.clone())
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Option<UserAccount> {
+        self.users
+            .lock()
+            .unwrap()
+            .values()
+            .find(|u| u.account.emails.iter().any(|e| e.eq_ignore_ascii_case(email)))
+            .map(|u| u.account.clone())
+    }
+
+    async fn save_user(&self, account: UserAccount, password: Option<String>) -> bool {
+        let mut users = self.users.lock().unwrap();
+        let taken = users
+            .values()
+            .any(|u| u.account.id != account.id && u.account.username == account.username);
+        if taken {
+            return false;
+        }
+        let password = password.or_else(|| users.get(&account.id).and_then(|u| u.password.clone()));
+        users.insert(account.id.clone(), StoredUser { account, password });
+        true
+    }
+
+    async fn delete_user(&self, id: &str) -> bool {
+        let removed = self.users.lock().unwrap().remove(id).is_some();
+        if removed {
+            self.webauthn_credentials
+                .lock()
+                .unwrap()
+                .retain(|_, c| c.user_id != id);
+            self.identities.lock().unwrap().retain(|_, user_id| user_id != id);
+        }
+        removed
+    }
+
+    async fn save_webauthn_credential(&self, credential: WebauthnCredential) {
+        self.webauthn_credentials
+            .lock()
+            .unwrap()
+            .insert(credential.id.clone(), credential);
+    }
+
+    async fn find_webauthn_credential(&self, id: &str) -> Option<WebauthnCredential> {
+        self.webauthn_credentials.lock().unwrap().get(id).cloned()
+    }
+
+    async fn list_webauthn_credentials(&self, user_id: &str) -> Vec<WebauthnCredential> {
+        let mut credentials: Vec<WebauthnCredential> = self
+            .webauthn_credentials
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|c| c.user_id == user_id)
+            .cloned()
+            .collect();
+        credentials.sort_by_key(|c| c.created);
+        credentials
+    }
+
+    async fn link_identity(&self, provider: &str, subject: &str, user_id: &str) {
+        self.identities
+            .lock()
+            .unwrap()
+            .insert((provider.to_string(), subject.to_string()), user_id.to_string());
+    }
+
+    async fn find_linked_user(&self, provider: &str, subject: &str) -> Option<UserAccount> {
+        let user_id = self
+            .identities
+            .lock()
+            .unwrap()
+            .get(&(provider.to_string(), subject.to_string()))
+            .cloned()?;
+        self.find_user(&user_id).await
+    }
 }
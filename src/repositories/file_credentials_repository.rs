@@ -0,0 +1,460 @@
+use argon2::password_hash::{PasswordHash, PasswordVerifier};
+use argon2::Argon2;
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+use crate::models::user_model::UserAccount;
+use crate::models::webauthn_model::WebauthnCredential;
+use crate::repositories::credentials_repository::{CredentialRepository, InMemoryCredentialRepository};
+use crate::services::signature_service::constant_time_eq;
+
+const ADMIN_ROLE: &str = "admin";
+
+/// A password hash as written in a credentials file.
+#[derive(Debug, Clone)]
+enum StoredHash {
+    /// `$2a$`, `$2b$` or `$2y$`.
+    Bcrypt(String),
+    /// PHC string starting with `$argon2`.
+    Argon2(String),
+    /// `{SHA}` followed by the base64 SHA-1 digest, as written by `htpasswd -s`.
+    Sha1(Vec<u8>),
+    /// `{PLAIN}`; clients only, since request signing needs the raw secret.
+    Plain(String),
+}
+
+impl StoredHash {
+    fn parse(value: &str, allow_plain: bool) -> Result<Self, String> {
+        if value.starts_with("$2a$") || value.starts_with("$2b$") || value.starts_with("$2y$") {
+            value
+                .parse::<bcrypt::HashParts>()
+                .map(|_| Self::Bcrypt(value.to_string()))
+                .map_err(|e| format!("invalid bcrypt hash: {}", e))
+        } else if value.starts_with("$argon2") {
+            PasswordHash::new(value)
+                .map(|_| Self::Argon2(value.to_string()))
+                .map_err(|e| format!("invalid argon2 hash: {}", e))
+        } else if let Some(digest) = value.strip_prefix("{SHA}") {
+            match general_purpose::STANDARD.decode(digest) {
+                Ok(bytes) if bytes.len() == 20 => Ok(Self::Sha1(bytes)),
+                _ => Err("invalid {SHA} hash".to_string()),
+            }
+        } else if let Some(secret) = value.strip_prefix("{PLAIN}") {
+            if !allow_plain {
+                Err("plain text passwords are only allowed for clients".to_string())
+            } else if secret.is_empty() {
+                Err("empty secret".to_string())
+            } else {
+                Ok(Self::Plain(secret.to_string()))
+            }
+        } else {
+            Err("unsupported hash; use bcrypt, argon2 or {SHA}".to_string())
+        }
+    }
+
+    fn verify(&self, password: &str) -> bool {
+        match self {
+            Self::Bcrypt(hash) => bcrypt::verify(password, hash).unwrap_or(false),
+            Self::Argon2(hash) => PasswordHash::new(hash)
+                .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+                .unwrap_or(false),
+            Self::Sha1(digest) => constant_time_eq(&Sha1::digest(password.as_bytes()), digest),
+            Self::Plain(secret) => constant_time_eq(secret.as_bytes(), password.as_bytes()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct FileUser {
+    account: UserAccount,
+    hash: StoredHash,
+    admin: bool,
+}
+
+#[derive(Clone)]
+struct FileClient {
+    hash: StoredHash,
+    scopes: Vec<String>,
+}
+
+/// Everything read from the files in one pass. Reloads swap it whole, so a
+/// request never sees half of an edit.
+#[derive(Default)]
+struct Snapshot {
+    users: HashMap<String, FileUser>,
+    clients: HashMap<String, FileClient>,
+    users_modified: Option<SystemTime>,
+    clients_modified: Option<SystemTime>,
+}
+
+struct CredentialFiles {
+    users_path: Option<PathBuf>,
+    clients_path: Option<PathBuf>,
+    snapshot: RwLock<Snapshot>,
+}
+
+fn modified(path: &Option<PathBuf>) -> Option<SystemTime> {
+    path.as_ref()
+        .and_then(|p| std::fs::metadata(p).ok())
+        .and_then(|m| m.modified().ok())
+}
+
+/// Yields the line number and content of every line that is not blank or a
+/// `#` comment.
+fn entries(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn read(path: &Path, errors: &mut Vec<String>) -> Option<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            errors.push(format!("{}: {}", path.display(), e));
+            None
+        }
+    }
+}
+
+/// Parses `username:hash[:role,role]` lines.
+fn parse_users(path: &Path, previous: &HashMap<String, FileUser>, errors: &mut Vec<String>) -> HashMap<String, FileUser> {
+    let mut users = HashMap::new();
+    let Some(content) = read(path, errors) else {
+        return users;
+    };
+    for (number, line) in entries(&content) {
+        let mut error = |reason: String| errors.push(format!("{}:{}: {}", path.display(), number, reason));
+        let mut fields = line.splitn(3, ':');
+        let username = fields.next().unwrap_or_default().trim();
+        let hash = fields.next().unwrap_or_default().trim();
+        let roles: Vec<&str> = fields
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .collect();
+        if username.is_empty() || hash.is_empty() {
+            error("expected username:hash[:roles]".to_string());
+            continue;
+        }
+        if users.contains_key(username) {
+            error(format!("duplicate user {}", username));
+            continue;
+        }
+        if let Some(role) = roles.iter().find(|r| **r != ADMIN_ROLE) {
+            error(format!("unknown role {}", role));
+            continue;
+        }
+        let hash = match StoredHash::parse(hash, false) {
+            Ok(hash) => hash,
+            Err(reason) => {
+                error(reason);
+                continue;
+            }
+        };
+        // Keep the account of a user that survives a reload so its
+        // creation time stays put.
+        let account = previous.get(username).map(|u| u.account.clone()).unwrap_or_else(|| UserAccount {
+            id: username.to_string(),
+            // Nobody can mail a file-managed user, so there is nothing to verify.
+            email_verified: true,
+            ..UserAccount::new(username)
+        });
+        users.insert(
+            username.to_string(),
+            FileUser {
+                account,
+                hash,
+                admin: roles.contains(&ADMIN_ROLE),
+            },
+        );
+    }
+    users
+}
+
+/// Parses `client_id:hash[:scope scope]` lines.
+fn parse_clients(path: &Path, errors: &mut Vec<String>) -> HashMap<String, FileClient> {
+    let mut clients = HashMap::new();
+    let Some(content) = read(path, errors) else {
+        return clients;
+    };
+    for (number, line) in entries(&content) {
+        let mut error = |reason: String| errors.push(format!("{}:{}: {}", path.display(), number, reason));
+        let mut fields = line.splitn(3, ':');
+        let client_id = fields.next().unwrap_or_default().trim();
+        let hash = fields.next().unwrap_or_default().trim();
+        let scopes = fields
+            .next()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        if client_id.is_empty() || hash.is_empty() {
+            error("expected client_id:hash[:scopes]".to_string());
+            continue;
+        }
+        if clients.contains_key(client_id) {
+            error(format!("duplicate client {}", client_id));
+            continue;
+        }
+        match StoredHash::parse(hash, true) {
+            Ok(hash) => {
+                clients.insert(client_id.to_string(), FileClient { hash, scopes });
+            }
+            Err(reason) => error(reason),
+        }
+    }
+    clients
+}
+
+impl CredentialFiles {
+    fn load(&self, previous: &Snapshot) -> Result<Snapshot, Vec<String>> {
+        let mut errors = Vec::new();
+        let users_modified = modified(&self.users_path);
+        let clients_modified = modified(&self.clients_path);
+        let users = self
+            .users_path
+            .as_deref()
+            .map(|path| parse_users(path, &previous.users, &mut errors))
+            .unwrap_or_default();
+        let clients = self
+            .clients_path
+            .as_deref()
+            .map(|path| parse_clients(path, &mut errors))
+            .unwrap_or_default();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Snapshot {
+            users,
+            clients,
+            users_modified,
+            clients_modified,
+        })
+    }
+
+    /// Re-reads the files. On errors the current credentials stay in place.
+    fn reload(&self) -> Result<(), Vec<String>> {
+        let snapshot = self.load(&self.snapshot.read().unwrap())?;
+        *self.snapshot.write().unwrap() = snapshot;
+        Ok(())
+    }
+
+    /// Records the current modification times without reloading, so a
+    /// broken edit is reported once rather than on every check.
+    fn mark_seen(&self) {
+        let mut snapshot = self.snapshot.write().unwrap();
+        snapshot.users_modified = modified(&self.users_path);
+        snapshot.clients_modified = modified(&self.clients_path);
+    }
+
+    fn changed(&self) -> bool {
+        let snapshot = self.snapshot.read().unwrap();
+        modified(&self.users_path) != snapshot.users_modified
+            || modified(&self.clients_path) != snapshot.clients_modified
+    }
+}
+
+/// Users and clients managed as htpasswd-style files. Either file may be
+/// left out, in which case there are no users or no clients from files.
+///
+/// Users created through the API, passkeys and federated identity links are
+/// kept in memory next to the files; a file-managed user cannot be changed
+/// or deleted through the API. Clones share the same store.
+#[derive(Clone)]
+pub struct FileCredentialRepository {
+    files: Arc<CredentialFiles>,
+    overlay: InMemoryCredentialRepository,
+    /// `(provider, subject)` to user id, so links can point at file users.
+    identities: Arc<Mutex<HashMap<(String, String), String>>>,
+}
+
+impl FileCredentialRepository {
+    /// Reads both files. Fails with one `path:line: reason` entry per
+    /// malformed line.
+    pub fn open(users_path: Option<&str>, clients_path: Option<&str>) -> Result<Self, Vec<String>> {
+        let files = CredentialFiles {
+            users_path: users_path.map(PathBuf::from),
+            clients_path: clients_path.map(PathBuf::from),
+            snapshot: RwLock::new(Snapshot::default()),
+        };
+        let snapshot = files.load(&Snapshot::default())?;
+        *files.snapshot.write().unwrap() = snapshot;
+        Ok(Self {
+            files: Arc::new(files),
+            overlay: InMemoryCredentialRepository::empty(),
+            identities: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Checks the files every `interval` and reloads them when either one
+    /// was modified. Stops once the last clone of the repository is dropped.
+    pub fn watch(&self, interval: Duration) {
+        let files = Arc::downgrade(&self.files);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(files) = files.upgrade() else {
+                    break;
+                };
+                if !files.changed() {
+                    continue;
+                }
+                match files.reload() {
+                    Ok(()) => println!("reloaded credential files"),
+                    Err(errors) => {
+                        for error in errors {
+                            eprintln!("{}", error);
+                        }
+                        eprintln!("keeping the previous credentials");
+                        files.mark_seen();
+                    }
+                }
+            }
+        });
+    }
+
+    fn user(&self, username: &str) -> Option<FileUser> {
+        self.files.snapshot.read().unwrap().users.get(username).cloned()
+    }
+
+    fn client(&self, client_id: &str) -> Option<FileClient> {
+        self.files.snapshot.read().unwrap().clients.get(client_id).cloned()
+    }
+}
+
+/// Hash checks are slow on purpose, so they run off the async workers.
+async fn verify(hash: StoredHash, password: &str) -> bool {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash.verify(&password))
+        .await
+        .unwrap_or(false)
+}
+
+#[async_trait]
+impl CredentialRepository for FileCredentialRepository {
+    async fn validate_user(&self, username: &str, password: &str) -> bool {
+        match self.user(username) {
+            Some(user) => verify(user.hash, password).await,
+            None => self.overlay.validate_user(username, password).await,
+        }
+    }
+
+    async fn validate_client(&self, client_id: &str, client_secret: &str) -> bool {
+        match self.client(client_id) {
+            Some(client) => verify(client.hash, client_secret).await,
+            None => false,
+        }
+    }
+
+    async fn is_admin(&self, username: &str) -> bool {
+        self.user(username).is_some_and(|u| u.admin)
+    }
+
+    async fn client_scopes(&self, client_id: &str) -> Vec<String> {
+        self.client(client_id).map(|c| c.scopes).unwrap_or_default()
+    }
+
+    /// Only `{PLAIN}` clients can sign requests; a hash cannot key an HMAC.
+    async fn client_secret(&self, client_id: &str) -> Option<String> {
+        match self.client(client_id)?.hash {
+            StoredHash::Plain(secret) => Some(secret),
+            _ => None,
+        }
+    }
+
+    async fn client_exists(&self, client_id: &str) -> bool {
+        self.client(client_id).is_some()
+    }
+
+    async fn list_users(&self) -> Vec<UserAccount> {
+        let mut users: Vec<UserAccount> = self
+            .files
+            .snapshot
+            .read()
+            .unwrap()
+            .users
+            .values()
+            .map(|u| u.account.clone())
+            .collect();
+        users.extend(self.overlay.list_users().await);
+        users.sort_by(|a, b| a.created.cmp(&b.created).then_with(|| a.username.cmp(&b.username)));
+        users
+    }
+
+    async fn find_user(&self, id: &str) -> Option<UserAccount> {
+        match self.user(id) {
+            Some(user) => Some(user.account),
+            None => self.overlay.find_user(id).await,
+        }
+    }
+
+    async fn find_user_by_username(&self, username: &str) -> Option<UserAccount> {
+        match self.user(username) {
+            Some(user) => Some(user.account),
+            None => self.overlay.find_user_by_username(username).await,
+        }
+    }
+
+    async fn find_user_by_email(&self, email: &str) -> Option<UserAccount> {
+        self.overlay.find_user_by_email(email).await
+    }
+
+    async fn save_user(&self, account: UserAccount, password: Option<String>) -> bool {
+        if self.user(&account.id).is_some() || self.user(&account.username).is_some() {
+            return false;
+        }
+        self.overlay.save_user(account, password).await
+    }
+
+    async fn delete_user(&self, id: &str) -> bool {
+        if self.user(id).is_some() {
+            return false;
+        }
+        let removed = self.overlay.delete_user(id).await;
+        if removed {
+            self.identities.lock().unwrap().retain(|_, user_id| user_id != id);
+        }
+        removed
+    }
+
+    async fn save_webauthn_credential(&self, credential: WebauthnCredential) {
+        self.overlay.save_webauthn_credential(credential).await
+    }
+
+    async fn find_webauthn_credential(&self, id: &str) -> Option<WebauthnCredential> {
+        self.overlay.find_webauthn_credential(id).await
+    }
+
+    async fn list_webauthn_credentials(&self, user_id: &str) -> Vec<WebauthnCredential> {
+        self.overlay.list_webauthn_credentials(user_id).await
+    }
+
+    async fn link_identity(&self, provider: &str, subject: &str, user_id: &str) {
+        self.identities
+            .lock()
+            .unwrap()
+            .insert((provider.to_string(), subject.to_string()), user_id.to_string());
+    }
+
+    async fn find_linked_user(&self, provider: &str, subject: &str) -> Option<UserAccount> {
+        let user_id = self
+            .identities
+            .lock()
+            .unwrap()
+            .get(&(provider.to_string(), subject.to_string()))
+            .cloned()?;
+        self.find_user(&user_id).await
+    }
+}
@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::models::user_model::GroupModel;
+
+#[async_trait]
+pub trait GroupRepository: Send + Sync {
+    async fn list_groups(&self) -> Vec<GroupModel>;
+    async fn find_group(&self, id: &str) -> Option<GroupModel>;
+    /// Inserts or replaces the group with `group.id`. Returns false if another
+    /// group already has the display name.
+    async fn save_group(&self, group: GroupModel) -> bool;
+    async fn delete_group(&self, id: &str) -> bool;
+    /// Drops a deleted user from every group it belonged to.
+    async fn remove_member(&self, user_id: &str);
+}
+
+pub struct InMemoryGroupRepository {
+    groups: Arc<Mutex<HashMap<String, GroupModel>>>,
+}
+
+impl InMemoryGroupRepository {
+    pub fn new() -> Self {
+        Self {
+            groups: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for InMemoryGroupRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl GroupRepository for InMemoryGroupRepository {
+    async fn list_groups(&self) -> Vec<GroupModel> {
+        let mut groups: Vec<GroupModel> = self.groups.lock().unwrap().values().cloned().collect();
+        groups.sort_by(|a, b| a.created.cmp(&b.created).then_with(|| a.display_name.cmp(&b.display_name)));
+        groups
+    }
+
+    async fn find_group(&self, id: &str) -> Option<GroupModel> {
+        self.groups.lock().unwrap().get(id).cloned()
+    }
+
+    async fn save_group(&self, group: GroupModel) -> bool {
+        let mut groups = self.groups.lock().unwrap();
+        let taken = groups
+            .values()
+            .any(|g| g.id != group.id && g.display_name == group.display_name);
+        if taken {
+            return false;
+        }
+        groups.insert(group.id.clone(), group);
+        true
+    }
+
+    async fn delete_group(&self, id: &str) -> bool {
+        self.groups.lock().unwrap().remove(id).is_some()
+    }
+
+    async fn remove_member(&self, user_id: &str) {
+        for group in self.groups.lock().unwrap().values_mut() {
+            group.members.retain(|m| m != user_id);
+        }
+    }
+}
@@ -1,3 +1,7 @@
 pub mod base_repository;
 pub mod token_repository;
 pub mod credentials_repository;
+pub mod audit_repository;
+pub mod group_repository;
+pub mod action_token_repository;
+pub mod file_credentials_repository;
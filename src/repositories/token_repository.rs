@@ -1,16 +1,22 @@
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use std::sync::{Arc, Mutex};
 
+use crate::models::session_model::SessionModel;
+
 struct TokenEntry {
     hashed: String,
-    expires_at: DateTime<Utc>,
+    session: SessionModel,
 }
 
 #[async_trait]
 pub trait TokenRepository: Send + Sync {
-    async fn store_token(&self, hashed_token: String, expires_at: DateTime<Utc>);
-    async fn is_valid(&self, hashed_token: &str) -> bool;
+    async fn store_token(&self, hashed_token: String, session: SessionModel);
+    async fn find_session(&self, hashed_token: &str) -> Option<SessionModel>;
+    async fn list_sessions(&self, subject: &str, grant_type: &str) -> Vec<SessionModel>;
+    async fn revoke_session(&self, subject: &str, grant_type: &str, session_id: &str) -> bool;
+    async fn revoke_all(&self, subject: &str, grant_type: &str) -> usize;
+    async fn revoke_token(&self, hashed_token: &str) -> bool;
 }
 
 pub struct InMemoryTokenRepository {
@@ -25,20 +31,65 @@ impl InMemoryTokenRepository {
     }
 }
 
+impl Default for InMemoryTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_owned_by(session: &SessionModel, subject: &str, grant_type: &str) -> bool {
+    session.subject == subject && session.grant_type == grant_type
+}
+
 #[async_trait]
 impl TokenRepository for InMemoryTokenRepository {
-    async fn store_token(&self, hashed_token: String, expires_at: DateTime<Utc>) {
+    async fn store_token(&self, hashed_token: String, session: SessionModel) {
         let mut tokens = self.tokens.lock().unwrap();
         tokens.push(TokenEntry {
             hashed: hashed_token,
-            expires_at,
+            session,
         });
     }
 
-    async fn is_valid(&self, hashed_token: &str) -> bool {
+    async fn find_session(&self, hashed_token: &str) -> Option<SessionModel> {
+        let mut tokens = self.tokens.lock().unwrap();
+        let now = Utc::now();
+        tokens.retain(|t| t.session.expires_at > now);
+        tokens
+            .iter()
+            .find(|t| t.hashed == hashed_token)
+            .map(|t| t.session.clone())
+    }
+
+    async fn list_sessions(&self, subject: &str, grant_type: &str) -> Vec<SessionModel> {
         let mut tokens = self.tokens.lock().unwrap();
         let now = Utc::now();
-        tokens.retain(|t| t.expires_at > now);
-        tokens.iter().any(|t| t.hashed == hashed_token)
+        tokens.retain(|t| t.session.expires_at > now);
+        tokens
+            .iter()
+            .filter(|t| is_owned_by(&t.session, subject, grant_type))
+            .map(|t| t.session.clone())
+            .collect()
+    }
+
+    async fn revoke_session(&self, subject: &str, grant_type: &str, session_id: &str) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        let before = tokens.len();
+        tokens.retain(|t| !(is_owned_by(&t.session, subject, grant_type) && t.session.id == session_id));
+        tokens.len() != before
+    }
+
+    async fn revoke_all(&self, subject: &str, grant_type: &str) -> usize {
+        let mut tokens = self.tokens.lock().unwrap();
+        let before = tokens.len();
+        tokens.retain(|t| !is_owned_by(&t.session, subject, grant_type));
+        before - tokens.len()
+    }
+
+    async fn revoke_token(&self, hashed_token: &str) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        let before = tokens.len();
+        tokens.retain(|t| t.hashed != hashed_token);
+        tokens.len() != before
     }
 }
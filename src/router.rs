@@ -2,29 +2,36 @@ use warp::Filter;
 use crate::services::base_service::BaseService;
 use crate::config::Config;
 use std::sync::Arc;
-use std::convert::Infallible;
-use warp::Rejection;
+use std::convert::Infallible;
+use warp::Rejection;
 use crate::controllers::base_controller::{handle_get_messages, handle_create_message, handle_search_messages};
+use crate::controllers::with_audit;
+use crate::middleware::auth::client_context;
+use crate::errors::error_codes::ErrorCodes;
+use crate::middleware::validator::{validated_json, validated_param};
+use crate::models::message_model::CreateMessageModelDto;
+use crate::services::audit_service::AuditService;
 
 pub struct Router<S: BaseService> {
   service: Arc<S>,
+  audit: Arc<dyn AuditService>,
   config: Arc<Config>
 }
 
 impl<S: BaseService + Send + Sync + 'static> Router<S> {
-  pub fn new(service: S, config: Arc<Config>) -> Self {
+  pub fn new(service: S, audit: Arc<dyn AuditService>, config: Arc<Config>) -> Self {
     Self {
       service: Arc::new(service),
+      audit,
       config
     }
   }
 
 
-pub fn routes(&self) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+pub fn routes(&self) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
     let service = self.service.clone();
     let api_base = self.config.api_base.trim_matches('/').to_string();
     let api_segments: Vec<String> = api_base.split('/').map(|s| s.to_string()).collect();
-    let api_path_complete: String = api_base.clone() + ("/messages");
 
     let mut api_path = warp::path(api_segments[0].clone()).boxed();
     for segment in &api_segments[1..] {
@@ -42,20 +49,27 @@ pub fn routes(&self) -> impl Filter<Extract = impl warp::Reply, Error = Rejectio
         .and(api_path.clone())
         .and(warp::path("messages"))
         .and(warp::path::end())
-        .and(crate::validators::base_validator::validate_create_message(Some(api_path_complete.clone())))
+        .and(validated_json::<CreateMessageModelDto>(self.config.body_limits))
         .and(with_service(Arc::clone(&service)))
+        .and(with_audit(Arc::clone(&self.audit)))
+        .and(client_context())
         .and_then(handle_create_message);
 
     let search_messages = warp::get()
             .and(api_path.clone())
             .and(warp::path("messages"))
-            .and(warp::path::param::<String>())
+            .and(validated_param::<String, _>("message", |rule| {
+                rule.not_empty()
+                    .with_error_code(ErrorCodes::NotEmpty)
+                    .max_length(32)
+                    .with_error_code(ErrorCodes::MaxSize)
+            }))
             .and(with_service(Arc::clone(&service)))
             .and_then(handle_search_messages);
 
-        get_messages
-            .or(add_message)
-            .or(search_messages)
+        get_messages
+            .or(add_message)
+            .or(search_messages)
   }
 }
 
@@ -63,4 +77,4 @@ fn with_service<S: BaseService + Send + Sync + 'static>(
     service: Arc<S>,
 ) -> impl Filter<Extract = (Arc<S>,), Error = Infallible> + Clone {
     warp::any().map(move || Arc::clone(&service))
-}
\ No newline at end of file
+}
\ No newline at end of file
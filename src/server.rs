@@ -11,12 +11,14 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::Config;
 use warp::{Filter, Rejection};
 
-pub async fn run_server() -> (oneshot::Sender<()>, String) {
+/// Starts the server, or returns every configuration error found before
+/// binding the port.
+pub async fn run_server() -> Result<(oneshot::Sender<()>, String), Vec<String>> {
     dotenv().ok();
 
     let config = Arc::new(config::Config::from_env());
     let config_swagger = Arc::new(Config::from(format!("/{}/api-doc.json", config.api_base)));
-    let routes = controllers::routes(Arc::clone(&config));
+    let routes = controllers::routes(Arc::clone(&config))?;
     let static_files = warp::fs::dir(config.static_dir.clone());
 
     let port: u16 = config.port;
@@ -43,12 +45,15 @@ pub async fn run_server() -> (oneshot::Sender<()>, String) {
         .and(warp::any().map(move || config_swagger.clone()))
         .and_then(serve_swagger);
 
+    if let Some(dir) = &config.locales_dir {
+        errors::messages::load_dir(dir)?;
+    }
+
     let (tx, rx) = oneshot::channel();
-    let routes = api_doc
-        .or(swagger_ui)
-        .or(routes)
-        .or(static_files)
-        .recover(errors::handle_rejection);
+    let routes = errors::recover_localized(
+        api_doc.or(swagger_ui).or(routes).or(static_files),
+        config.default_locale.clone(),
+    );
 
     let socket_addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
     let listener = tokio::net::TcpListener::bind(socket_addr)
@@ -67,5 +72,5 @@ pub async fn run_server() -> (oneshot::Sender<()>, String) {
     println!("Server starting on port {}", addr.port());
     println!("API base path: /{}", config.api_base);
 
-    (tx, format!("http://127.0.0.1:{}", addr.port()))
+    Ok((tx, format!("http://127.0.0.1:{}", addr.port())))
 }
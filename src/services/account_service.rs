@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{Duration, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::config::PasswordPolicy;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::models::account_model::{ActionTokenModel, TokenPurpose};
+use crate::models::mail_model::MailMessage;
+use crate::models::user_model::UserAccount;
+use crate::repositories::{
+    action_token_repository::ActionTokenRepository, credentials_repository::CredentialRepository,
+};
+use crate::services::mail_service::Mailer;
+use crate::validators::password_validator::validate_password;
+
+pub const RESET_TOKEN_TTL_MINUTES: i64 = 30;
+pub const VERIFICATION_TOKEN_TTL_HOURS: i64 = 24;
+
+#[async_trait]
+pub trait AccountService: Send + Sync {
+    /// Mails a reset token if an active user owns `email`. Unknown addresses
+    /// are silently ignored so callers cannot probe for accounts.
+    async fn forgot_password(&self, email: &str);
+    /// Sets a new password and returns the user's username.
+    async fn reset_password(&self, token: &str, password: &str) -> Result<String, ApiError>;
+    async fn send_verification(&self, user_id: &str) -> Result<(), ApiError>;
+    /// Marks the email as verified and returns the user's username.
+    async fn verify_email(&self, token: &str) -> Result<String, ApiError>;
+}
+
+pub struct AccountServiceImpl<C: CredentialRepository, T: ActionTokenRepository> {
+    credential_repository: C,
+    token_repository: T,
+    mailer: Arc<dyn Mailer>,
+    api_url: String,
+    password_policy: PasswordPolicy,
+}
+
+impl<C: CredentialRepository, T: ActionTokenRepository> AccountServiceImpl<C, T> {
+    /// `api_url` is the public API root used in the instructions of mailed tokens.
+    pub fn new(credential_repository: C, token_repository: T, mailer: Arc<dyn Mailer>, api_url: String) -> Self {
+        Self {
+            credential_repository,
+            token_repository,
+            mailer,
+            api_url,
+            password_policy: PasswordPolicy::default(),
+        }
+    }
+
+    /// Replaces the default rules reset passwords are checked against.
+    pub fn with_password_policy(mut self, policy: PasswordPolicy) -> Self {
+        self.password_policy = policy;
+        self
+    }
+
+    async fn issue_token(&self, user: &UserAccount, purpose: TokenPurpose) -> String {
+        let mut bytes = [0u8; 32];
+        rand::rng().fill_bytes(&mut bytes);
+        let token = general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+        let ttl = match purpose {
+            TokenPurpose::PasswordReset => Duration::minutes(RESET_TOKEN_TTL_MINUTES),
+            TokenPurpose::EmailVerification => Duration::hours(VERIFICATION_TOKEN_TTL_HOURS),
+        };
+        self.token_repository
+            .store(ActionTokenModel {
+                hashed: hash_token(&token),
+                user_id: user.id.clone(),
+                purpose,
+                expires_at: Utc::now() + ttl,
+            })
+            .await;
+        token
+    }
+
+    async fn consume(&self, token: &str, purpose: TokenPurpose, error: ErrorCodes) -> Result<UserAccount, ApiError> {
+        let stored = self
+            .token_repository
+            .consume(&hash_token(token), purpose)
+            .await
+            .ok_or(ApiError::ErrorCode(error.clone()))?;
+        self.credential_repository
+            .find_user(&stored.user_id)
+            .await
+            .ok_or(ApiError::ErrorCode(error))
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[async_trait]
+impl<C: CredentialRepository + Send + Sync, T: ActionTokenRepository + Send + Sync> AccountService
+    for AccountServiceImpl<C, T>
+{
+    async fn forgot_password(&self, email: &str) {
+        let Some(user) = self.credential_repository.find_user_by_email(email).await else {
+            return;
+        };
+        if !user.active {
+            return;
+        }
+        let token = self.issue_token(&user, TokenPurpose::PasswordReset).await;
+        let message = MailMessage {
+            to: email.to_string(),
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Someone asked to reset the password for {}.\n\n\
+                 Reset code: {}\n\n\
+                 Send it with your new password to POST {}/auth/password/reset within {} minutes. \
+                 If this wasn't you, ignore this email.",
+                user.username, token, self.api_url, RESET_TOKEN_TTL_MINUTES
+            ),
+        };
+        // Sent in the background so known addresses answer as fast as
+        // unknown ones.
+        let mailer = Arc::clone(&self.mailer);
+        tokio::spawn(async move {
+            if mailer.send(message).await.is_err() {
+                eprintln!("password reset mail for {} was not sent", user.username);
+            }
+        });
+    }
+
+    async fn reset_password(&self, token: &str, password: &str) -> Result<String, ApiError> {
+        let invalid = || ApiError::ErrorCode(ErrorCodes::InvalidResetToken);
+        let stored = self
+            .token_repository
+            .consume(&hash_token(token), TokenPurpose::PasswordReset)
+            .await
+            .ok_or_else(invalid)?;
+        let user = self
+            .credential_repository
+            .find_user(&stored.user_id)
+            .await
+            .ok_or_else(invalid)?;
+        let password = match validate_password(&self.password_policy, password, &user.username) {
+            Ok(password) => password,
+            Err(e) => {
+                // Keep the code valid so the user can retry with a better password.
+                self.token_repository.store(stored).await;
+                return Err(e);
+            }
+        };
+        let username = user.username.clone();
+        if !self
+            .credential_repository
+            .save_user(user, Some(password))
+            .await
+        {
+            return Err(ApiError::InternalServerError);
+        }
+        Ok(username)
+    }
+
+    async fn send_verification(&self, user_id: &str) -> Result<(), ApiError> {
+        let user = self
+            .credential_repository
+            .find_user(user_id)
+            .await
+            .ok_or(ApiError::NotFound)?;
+        let Some(email) = user.emails.first().cloned() else {
+            return Ok(());
+        };
+        let token = self.issue_token(&user, TokenPurpose::EmailVerification).await;
+        self.mailer
+            .send(MailMessage {
+                to: email,
+                subject: "Verify your email address".to_string(),
+                body: format!(
+                    "Welcome, {}.\n\n\
+                     Verification code: {}\n\n\
+                     Send it to POST {}/auth/email/verify within {} hours to activate your account.",
+                    user.username, token, self.api_url, VERIFICATION_TOKEN_TTL_HOURS
+                ),
+            })
+            .await
+    }
+
+    async fn verify_email(&self, token: &str) -> Result<String, ApiError> {
+        let mut user = self
+            .consume(token, TokenPurpose::EmailVerification, ErrorCodes::InvalidVerificationToken)
+            .await?;
+        let username = user.username.clone();
+        user.email_verified = true;
+        user.last_modified = Utc::now();
+        if !self.credential_repository.save_user(user, None).await {
+            return Err(ApiError::InternalServerError);
+        }
+        Ok(username)
+    }
+}
@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use hex;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::models::audit_model::{AuditEntry, AuditEvent, AuditQuery};
+use crate::repositories::audit_repository::AuditRepository;
+
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[async_trait]
+pub trait AuditService: Send + Sync {
+    async fn record(&self, event: AuditEvent) -> AuditEntry;
+    async fn query(&self, query: &AuditQuery) -> Vec<AuditEntry>;
+    async fn verify(&self) -> (bool, usize);
+}
+
+pub struct AuditServiceImpl<R: AuditRepository> {
+    repository: R,
+    // Serializes appends so every entry is chained to the one written before it.
+    chain: Mutex<()>,
+}
+
+impl<R: AuditRepository> AuditServiceImpl<R> {
+    pub fn new(repository: R) -> Self {
+        Self {
+            repository,
+            chain: Mutex::new(()),
+        }
+    }
+}
+
+/// Hash of an entry over all of its fields except `hash` itself; since
+/// `previous_hash` is included, altering any entry breaks every later link.
+pub fn compute_hash(entry: &AuditEntry) -> String {
+    let unsealed = AuditEntry {
+        hash: String::new(),
+        ..entry.clone()
+    };
+    let payload = serde_json::to_string(&unsealed).unwrap_or_default();
+    hex::encode(Sha256::digest(payload.as_bytes()))
+}
+
+pub fn verify_chain(entries: &[AuditEntry]) -> bool {
+    let mut previous = GENESIS_HASH.to_string();
+    for entry in entries {
+        if entry.previous_hash != previous || entry.hash != compute_hash(entry) {
+            return false;
+        }
+        previous = entry.hash.clone();
+    }
+    true
+}
+
+#[async_trait]
+impl<R: AuditRepository + Send + Sync> AuditService for AuditServiceImpl<R> {
+    async fn record(&self, event: AuditEvent) -> AuditEntry {
+        let _guard = self.chain.lock().await;
+        let (sequence, previous_hash) = match self.repository.last_entry().await {
+            Some(last) => (last.sequence + 1, last.hash),
+            None => (1, GENESIS_HASH.to_string()),
+        };
+        let mut entry = AuditEntry {
+            sequence,
+            timestamp: Utc::now(),
+            action: event.action,
+            outcome: event.outcome,
+            actor: event.actor,
+            client_ip: event.client_ip,
+            target: event.target,
+            previous_hash,
+            hash: String::new(),
+        };
+        entry.hash = compute_hash(&entry);
+        self.repository.append(entry.clone()).await;
+        entry
+    }
+
+    async fn query(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        let matching = self
+            .repository
+            .entries()
+            .await
+            .into_iter()
+            .filter(|e| query.matches(e));
+        match query.limit {
+            Some(limit) => matching.take(limit).collect(),
+            None => matching.collect(),
+        }
+    }
+
+    async fn verify(&self) -> (bool, usize) {
+        let entries = self.repository.entries().await;
+        (verify_chain(&entries), entries.len())
+    }
+}
@@ -3,22 +3,86 @@ use base64::{engine::general_purpose, Engine as _};
 use chrono::{Duration, Utc};
 use hex;
 use rand::RngCore;
+use serde_json::json;
 use sha2::{Digest, Sha256};
+use std::sync::Arc;
 
+use crate::errors::error_codes::ErrorCodes;
 use crate::errors::ApiError;
-use crate::models::{auth_request::AuthRequestDto, token_model::TokenResponseDto};
+use crate::models::{
+    auth_request::AuthRequestDto,
+    oidc_model::{ConfirmationDto, IntrospectionResponseDto},
+    session_model::{BrowserSession, ClientContext, Principal, SessionModel},
+    token_model::TokenResponseDto,
+};
 use crate::repositories::{credentials_repository::CredentialRepository, token_repository::TokenRepository};
+use crate::services::device_service::DeviceService;
+use crate::services::dpop_service::DpopVerifier;
+use crate::services::jwt_service::JwtService;
+use crate::models::webauthn_model::WebauthnAssertion;
+use crate::services::signature_service::{SignatureVerifier, SignedRequest};
+use crate::services::webauthn_service::WebauthnService;
+use crate::validators::password_validator::normalize_password;
+
+pub const OPENID_SCOPE: &str = "openid";
+/// Lets a client introspect tokens issued to others.
+pub const INTROSPECT_SCOPE: &str = "introspect";
 
 #[async_trait]
 pub trait AuthService: Send + Sync {
-    async fn generate_token(&self, request: AuthRequestDto) -> Result<TokenResponseDto, ApiError>;
-    async fn validate_token(&self, token: &str) -> bool;
+    async fn generate_token(
+        &self,
+        request: AuthRequestDto,
+        context: ClientContext,
+    ) -> Result<TokenResponseDto, ApiError>;
+    async fn create_browser_session(
+        &self,
+        request: AuthRequestDto,
+        context: ClientContext,
+    ) -> Result<BrowserSession, ApiError>;
+    /// Issues tokens for a user who was authenticated elsewhere, such as by
+    /// an external identity provider.
+    async fn issue_for_user(&self, username: &str, context: ClientContext) -> Result<TokenResponseDto, ApiError>;
+    async fn validate_csrf(&self, token: &str, csrf_token: &str) -> bool;
+    async fn authenticate(&self, token: &str) -> Option<Principal>;
+    async fn list_sessions(&self, subject: &str, grant_type: &str) -> Vec<SessionModel>;
+    async fn revoke_session(
+        &self,
+        subject: &str,
+        grant_type: &str,
+        session_id: &str,
+    ) -> Result<(), ApiError>;
+    async fn revoke_all_sessions(&self, subject: &str, grant_type: &str) -> usize;
+    async fn revoke_token(&self, token: &str) -> bool;
+    async fn introspect(&self, token: &str) -> IntrospectionResponseDto;
+    /// Verifies a DPoP proof and returns the JWK thumbprint it was signed with.
+    async fn verify_dpop_proof(
+        &self,
+        proof: &str,
+        method: &str,
+        url: &str,
+        access_token: Option<&str>,
+    ) -> Result<String, ApiError>;
+    /// Verifies an HMAC-signed request and returns the signing client.
+    async fn verify_request_signature(&self, request: SignedRequest) -> Result<Principal, ApiError>;
+}
+
+struct IdTokenIssuer {
+    jwt: Arc<dyn JwtService>,
+    issuer: String,
+    audience: String,
 }
 
 pub struct AuthServiceImpl<R: TokenRepository, C: CredentialRepository> {
     token_repository: R,
     credential_repository: C,
     ttl_minutes: i64,
+    id_tokens: Option<IdTokenIssuer>,
+    dpop: DpopVerifier,
+    signatures: SignatureVerifier,
+    webauthn: Option<Arc<dyn WebauthnService>>,
+    device: Option<Arc<dyn DeviceService>>,
+    require_verified_email: bool,
 }
 
 impl<R: TokenRepository, C: CredentialRepository> AuthServiceImpl<R, C> {
@@ -27,53 +91,330 @@ impl<R: TokenRepository, C: CredentialRepository> AuthServiceImpl<R, C> {
             token_repository,
             credential_repository,
             ttl_minutes: 60,
+            id_tokens: None,
+            dpop: DpopVerifier::default(),
+            signatures: SignatureVerifier::default(),
+            webauthn: None,
+            device: None,
+            require_verified_email: false,
         }
     }
-}
 
-#[async_trait]
-impl<R: TokenRepository + Send + Sync, C: CredentialRepository + Send + Sync> AuthService
-    for AuthServiceImpl<R, C>
-{
-    async fn generate_token(
+    /// Enables the `webauthn` grant, which logs users in with a passkey.
+    pub fn with_webauthn(mut self, webauthn: Arc<dyn WebauthnService>) -> Self {
+        self.webauthn = Some(webauthn);
+        self
+    }
+
+    /// Enables the device code grant for codes approved through `device`.
+    pub fn with_device_flow(mut self, device: Arc<dyn DeviceService>) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Sets how far a signed request's timestamp may drift from the server clock.
+    pub fn with_signature_max_skew(mut self, seconds: i64) -> Self {
+        self.signatures = SignatureVerifier::new(seconds);
+        self
+    }
+
+    /// Refuses user logins until the account's email address is verified.
+    pub fn with_email_verification(mut self) -> Self {
+        self.require_verified_email = true;
+        self
+    }
+
+    /// Enables ID tokens for user grants that request the `openid` scope.
+    pub fn with_id_tokens(mut self, jwt: Arc<dyn JwtService>, issuer: String, audience: String) -> Self {
+        self.id_tokens = Some(IdTokenIssuer {
+            jwt,
+            issuer,
+            audience,
+        });
+        self
+    }
+
+    fn id_token_for(&self, session: &SessionModel) -> Option<String> {
+        let issuer = self.id_tokens.as_ref()?;
+        let wants_openid = session
+            .scope
+            .as_deref()
+            .is_some_and(|s| s.split_whitespace().any(|s| s == OPENID_SCOPE));
+        if session.grant_type != "user" || !wants_openid {
+            return None;
+        }
+        Some(issuer.jwt.sign(&json!({
+            "iss": issuer.issuer,
+            "sub": session.subject,
+            "aud": issuer.audience,
+            "iat": session.issued_at.timestamp(),
+            "exp": session.expires_at.timestamp(),
+            "auth_time": session.issued_at.timestamp(),
+            "sid": session.id,
+        })))
+    }
+
+    async fn issue(
         &self,
         request: AuthRequestDto,
-    ) -> Result<TokenResponseDto, ApiError> {
-        let valid = match request {
-            AuthRequestDto::User { username, password } => {
+        context: ClientContext,
+        csrf_token: Option<&str>,
+    ) -> Result<(String, SessionModel), ApiError> {
+        let mut scope = request.scope().map(str::to_string);
+        let (valid, subject, grant_type) = match request {
+            AuthRequestDto::User {
+                username, password, ..
+            } => (
                 self.credential_repository
-                    .validate_user(&username, &password)
-                    .await
-            }
+                    .validate_user(&username, &normalize_password(&password))
+                    .await,
+                username,
+                "user",
+            ),
             AuthRequestDto::Client {
                 client_id,
                 client_secret,
-            } => {
+                ..
+            } => (
                 self.credential_repository
                     .validate_client(&client_id, &client_secret)
-                    .await
+                    .await,
+                client_id,
+                "client",
+            ),
+            AuthRequestDto::Webauthn {
+                credential_id,
+                client_data_json,
+                authenticator_data,
+                signature,
+                user_handle,
+                ..
+            } => {
+                let webauthn = self.webauthn.as_ref().ok_or(ApiError::Unauthorized)?;
+                let assertion = WebauthnAssertion {
+                    credential_id,
+                    client_data_json,
+                    authenticator_data,
+                    signature,
+                    user_handle,
+                };
+                (true, webauthn.authenticate(&assertion).await?, "user")
+            }
+            AuthRequestDto::DeviceCode { device_code, client_id } => {
+                let device = self.device.as_ref().ok_or(ApiError::Unauthorized)?;
+                let grant = device.redeem(&client_id, &device_code).await?;
+                scope = grant.scope;
+                (true, grant.username, "user")
             }
         };
 
         if !valid {
             return Err(ApiError::Unauthorized);
         }
+        if grant_type == "user" && self.require_verified_email {
+            let verified = self
+                .credential_repository
+                .find_user_by_username(&subject)
+                .await
+                .is_some_and(|u| u.email_verified);
+            if !verified {
+                return Err(ApiError::ErrorCode(ErrorCodes::EmailNotVerified));
+            }
+        }
+        if let Some(requested) = scope.as_deref() {
+            let allowed = match grant_type {
+                "client" => self.credential_repository.client_scopes(&subject).await,
+                _ => vec![OPENID_SCOPE.to_string()],
+            };
+            if !requested.split_whitespace().all(|s| allowed.iter().any(|a| a == s)) {
+                return Err(ApiError::InvalidScope);
+            }
+        }
+        Ok(self.start_session(subject, grant_type, scope, context, csrf_token).await)
+    }
 
-        let mut bytes = [0u8; 32];
-        rand::rng().fill_bytes(&mut bytes);
-        let token = general_purpose::URL_SAFE_NO_PAD.encode(&bytes);
-        let hashed = Sha256::digest(token.as_bytes());
-        let hashed_hex = hex::encode(hashed);
-        let expires_at = Utc::now() + Duration::minutes(self.ttl_minutes);
+    async fn start_session(
+        &self,
+        subject: String,
+        grant_type: &str,
+        scope: Option<String>,
+        context: ClientContext,
+        csrf_token: Option<&str>,
+    ) -> (String, SessionModel) {
+        let token = random_string(32);
+        let issued_at = Utc::now();
+        let session = SessionModel {
+            id: random_string(12),
+            subject,
+            grant_type: grant_type.to_string(),
+            issued_at,
+            expires_at: issued_at + Duration::minutes(self.ttl_minutes),
+            user_agent: context.user_agent,
+            client_ip: context.client_ip,
+            csrf_hash: csrf_token.map(hash_token),
+            scope,
+            jkt: context.dpop_jkt,
+        };
         self.token_repository
-            .store_token(hashed_hex, expires_at)
+            .store_token(hash_token(&token), session.clone())
+            .await;
+        (token, session)
+    }
+
+    fn token_response(&self, token: String, session: &SessionModel) -> TokenResponseDto {
+        TokenResponseDto {
+            token,
+            token_type: if session.jkt.is_some() { "DPoP" } else { "Bearer" }.to_string(),
+            id_token: self.id_token_for(session),
+        }
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn random_string(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::rng().fill_bytes(&mut bytes);
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[async_trait]
+impl<R: TokenRepository + Send + Sync, C: CredentialRepository + Send + Sync> AuthService
+    for AuthServiceImpl<R, C>
+{
+    async fn generate_token(
+        &self,
+        request: AuthRequestDto,
+        context: ClientContext,
+    ) -> Result<TokenResponseDto, ApiError> {
+        let (token, session) = self.issue(request, context, None).await?;
+        Ok(self.token_response(token, &session))
+    }
+
+    async fn issue_for_user(&self, username: &str, context: ClientContext) -> Result<TokenResponseDto, ApiError> {
+        let active = self
+            .credential_repository
+            .find_user_by_username(username)
+            .await
+            .is_some_and(|u| u.active);
+        if !active {
+            return Err(ApiError::Unauthorized);
+        }
+        let (token, session) = self
+            .start_session(username.to_string(), "user", None, context, None)
             .await;
-        Ok(TokenResponseDto { token })
+        Ok(self.token_response(token, &session))
+    }
+
+    async fn create_browser_session(
+        &self,
+        request: AuthRequestDto,
+        context: ClientContext,
+    ) -> Result<BrowserSession, ApiError> {
+        let csrf_token = random_string(32);
+        let (token, session) = self.issue(request, context, Some(&csrf_token)).await?;
+        Ok(BrowserSession {
+            token,
+            csrf_token,
+            expires_at: session.expires_at,
+        })
+    }
+
+    async fn validate_csrf(&self, token: &str, csrf_token: &str) -> bool {
+        match self.token_repository.find_session(&hash_token(token)).await {
+            Some(session) => session.csrf_hash == Some(hash_token(csrf_token)),
+            None => false,
+        }
+    }
+
+    async fn authenticate(&self, token: &str) -> Option<Principal> {
+        let session = self.token_repository.find_session(&hash_token(token)).await?;
+        let is_admin = session.grant_type == "user"
+            && self.credential_repository.is_admin(&session.subject).await;
+        Some(Principal {
+            subject: session.subject,
+            grant_type: session.grant_type,
+            session_id: session.id,
+            is_admin,
+            dpop_jkt: session.jkt,
+            scope: session.scope,
+        })
+    }
+
+    async fn list_sessions(&self, subject: &str, grant_type: &str) -> Vec<SessionModel> {
+        self.token_repository.list_sessions(subject, grant_type).await
+    }
+
+    async fn revoke_session(
+        &self,
+        subject: &str,
+        grant_type: &str,
+        session_id: &str,
+    ) -> Result<(), ApiError> {
+        if self
+            .token_repository
+            .revoke_session(subject, grant_type, session_id)
+            .await
+        {
+            Ok(())
+        } else {
+            Err(ApiError::SessionNotFound)
+        }
+    }
+
+    async fn revoke_all_sessions(&self, subject: &str, grant_type: &str) -> usize {
+        self.token_repository.revoke_all(subject, grant_type).await
+    }
+
+    async fn revoke_token(&self, token: &str) -> bool {
+        self.token_repository.revoke_token(&hash_token(token)).await
+    }
+
+    async fn introspect(&self, token: &str) -> IntrospectionResponseDto {
+        match self.token_repository.find_session(&hash_token(token)).await {
+            Some(session) => IntrospectionResponseDto {
+                active: true,
+                sub: Some(session.subject),
+                grant_type: Some(session.grant_type),
+                scope: session.scope,
+                iat: Some(session.issued_at.timestamp()),
+                exp: Some(session.expires_at.timestamp()),
+                sid: Some(session.id),
+                cnf: session.jkt.map(|jkt| ConfirmationDto { jkt }),
+            },
+            // ID tokens and anything else not issued here as an access
+            // token are inactive, so they are never accepted as one.
+            None => IntrospectionResponseDto::default(),
+        }
+    }
+
+    async fn verify_dpop_proof(
+        &self,
+        proof: &str,
+        method: &str,
+        url: &str,
+        access_token: Option<&str>,
+    ) -> Result<String, ApiError> {
+        self.dpop.verify(proof, method, url, access_token)
     }
 
-    async fn validate_token(&self, token: &str) -> bool {
-        let hashed = Sha256::digest(token.as_bytes());
-        let hashed_hex = hex::encode(hashed);
-        self.token_repository.is_valid(&hashed_hex).await
+    async fn verify_request_signature(&self, request: SignedRequest) -> Result<Principal, ApiError> {
+        let secret = self
+            .credential_repository
+            .client_secret(&request.client_id)
+            .await
+            .ok_or(ApiError::InvalidSignature)?;
+        self.signatures.verify(&request, &secret)?;
+        let scopes = self.credential_repository.client_scopes(&request.client_id).await;
+        Ok(Principal {
+            subject: request.client_id,
+            grant_type: "client".to_string(),
+            session_id: String::new(),
+            is_admin: false,
+            dpop_jkt: None,
+            scope: Some(scopes.join(" ")).filter(|s| !s.is_empty()),
+        })
     }
 }
@@ -0,0 +1,205 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use rand::{Rng, RngCore};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::{ApiError, Failure};
+use crate::models::device_model::{DeviceAuthorizationResponseDto, DeviceGrant};
+use crate::repositories::credentials_repository::CredentialRepository;
+use crate::services::auth_service::OPENID_SCOPE;
+
+/// Consonants only, so codes cannot spell words or be misread (RFC 8628 §6.1).
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+/// Added to the polling interval each time a device polls too fast.
+const SLOW_DOWN_SECONDS: i64 = 5;
+/// Most device authorizations kept at once, so unused ones cannot fill the memory.
+const MAX_PENDING_DEVICES: usize = 10_000;
+
+#[async_trait]
+pub trait DeviceService: Send + Sync {
+    /// Starts a device authorization for a registered client.
+    async fn authorize(&self, client_id: &str, scope: Option<String>) -> Result<DeviceAuthorizationResponseDto, ApiError>;
+    /// Records the logged-in user's decision for a user code.
+    async fn verify(&self, user_code: &str, username: &str, approve: bool) -> Result<(), ApiError>;
+    /// Polls a device code. Fails with `authorization_pending` until the
+    /// user decides, and with `slow_down` when polled faster than the interval.
+    async fn redeem(&self, client_id: &str, device_code: &str) -> Result<DeviceGrant, ApiError>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Decision {
+    Pending,
+    Approved(String),
+    Denied,
+}
+
+struct PendingDevice {
+    client_id: String,
+    user_code: String,
+    scope: Option<String>,
+    decision: Decision,
+    expires_at: DateTime<Utc>,
+    interval: i64,
+    last_polled: Option<DateTime<Utc>>,
+}
+
+/// RFC 8628 device authorization grant. Pending codes live in memory.
+pub struct DeviceServiceImpl<C: CredentialRepository> {
+    credential_repository: C,
+    verification_uri: String,
+    ttl_seconds: i64,
+    interval: i64,
+    pending: Mutex<HashMap<String, PendingDevice>>,
+}
+
+fn error(code: ErrorCodes) -> ApiError {
+    ApiError::ErrorCode(code)
+}
+
+/// A failed poll, answered in the shape device flow clients expect.
+fn grant_error(code: ErrorCodes) -> ApiError {
+    ApiError::DeviceGrant(code.into(), None)
+}
+
+/// Uppercases and drops the dash and any spacing the user typed.
+fn normalize_user_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn random_user_code() -> String {
+    let mut rng = rand::rng();
+    (0..USER_CODE_LENGTH)
+        .map(|_| USER_CODE_ALPHABET[rng.random_range(0..USER_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+fn random_device_code() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+impl<C: CredentialRepository> DeviceServiceImpl<C> {
+    pub fn new(credential_repository: C, verification_uri: String, ttl_seconds: i64, interval: i64) -> Self {
+        Self {
+            credential_repository,
+            verification_uri,
+            ttl_seconds,
+            interval,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: CredentialRepository + Send + Sync> DeviceService for DeviceServiceImpl<C> {
+    async fn authorize(&self, client_id: &str, scope: Option<String>) -> Result<DeviceAuthorizationResponseDto, ApiError> {
+        if !self.credential_repository.client_exists(client_id).await {
+            return Err(ApiError::Unauthorized);
+        }
+        if let Some(requested) = scope.as_deref() {
+            if !requested.split_whitespace().all(|s| s == OPENID_SCOPE) {
+                return Err(ApiError::InvalidScope);
+            }
+        }
+
+        let now = Utc::now();
+        let device_code = random_device_code();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.expires_at > now);
+        if pending.len() >= MAX_PENDING_DEVICES {
+            // Make room by dropping the authorization closest to expiring.
+            let oldest = pending.iter().min_by_key(|(_, p)| p.expires_at).map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                pending.remove(&oldest);
+            }
+        }
+        let user_code = loop {
+            let code = random_user_code();
+            if !pending.values().any(|p| p.user_code == code) {
+                break code;
+            }
+        };
+        pending.insert(
+            device_code.clone(),
+            PendingDevice {
+                client_id: client_id.to_string(),
+                user_code: user_code.clone(),
+                scope,
+                decision: Decision::Pending,
+                expires_at: now + Duration::seconds(self.ttl_seconds),
+                interval: self.interval,
+                last_polled: None,
+            },
+        );
+
+        let display_code = format!("{}-{}", &user_code[..4], &user_code[4..]);
+        Ok(DeviceAuthorizationResponseDto {
+            device_code,
+            verification_uri_complete: format!("{}?user_code={}", self.verification_uri, display_code),
+            user_code: display_code,
+            verification_uri: self.verification_uri.clone(),
+            expires_in: self.ttl_seconds,
+            interval: self.interval,
+        })
+    }
+
+    async fn verify(&self, user_code: &str, username: &str, approve: bool) -> Result<(), ApiError> {
+        let user_code = normalize_user_code(user_code);
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        let device = pending
+            .values_mut()
+            .find(|p| p.user_code == user_code && p.expires_at > now && p.decision == Decision::Pending)
+            .ok_or_else(|| error(ErrorCodes::InvalidUserCode))?;
+        device.decision = if approve {
+            Decision::Approved(username.to_string())
+        } else {
+            Decision::Denied
+        };
+        Ok(())
+    }
+
+    async fn redeem(&self, client_id: &str, device_code: &str) -> Result<DeviceGrant, ApiError> {
+        let now = Utc::now();
+        let mut pending = self.pending.lock().unwrap();
+        let device = pending
+            .get_mut(device_code)
+            .filter(|p| p.client_id == client_id)
+            .ok_or_else(|| grant_error(ErrorCodes::InvalidDeviceCode))?;
+        if device.expires_at <= now {
+            pending.remove(device_code);
+            return Err(grant_error(ErrorCodes::ExpiredDeviceCode));
+        }
+        let too_fast = device
+            .last_polled
+            .is_some_and(|last| now - last < Duration::seconds(device.interval));
+        device.last_polled = Some(now);
+        if too_fast {
+            device.interval += SLOW_DOWN_SECONDS;
+            let failure = Failure {
+                code: ErrorCodes::SlowDown,
+                args: vec![("seconds", SLOW_DOWN_SECONDS.to_string())],
+            };
+            return Err(ApiError::DeviceGrant(failure, Some(device.interval)));
+        }
+        match device.decision.clone() {
+            Decision::Pending => Err(grant_error(ErrorCodes::AuthorizationPending)),
+            Decision::Denied => {
+                pending.remove(device_code);
+                Err(grant_error(ErrorCodes::DeviceAccessDenied))
+            }
+            Decision::Approved(username) => {
+                let scope = pending.remove(device_code).and_then(|p| p.scope);
+                Ok(DeviceGrant { username, scope })
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::errors::ApiError;
+use crate::models::oidc_model::JwkDto;
+use crate::services::jwt_service::{decode_unverified, from_jwk, verify_es256, ES256};
+
+pub const DPOP_HEADER: &str = "dpop";
+pub const DPOP_PROOF_TYPE: &str = "dpop+jwt";
+
+/// Checks DPoP proofs (RFC 9449). Every accepted `jti` is remembered until
+/// the proof would be too old anyway, so a captured proof cannot be replayed.
+pub struct DpopVerifier {
+    max_age_seconds: i64,
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+impl DpopVerifier {
+    pub fn new(max_age_seconds: i64) -> Self {
+        Self {
+            max_age_seconds,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verifies `proof` for a request and returns the thumbprint of the key
+    /// that signed it. `access_token` is set when the proof accompanies a
+    /// bound token, in which case the `ath` claim must hash to it.
+    pub fn verify(
+        &self,
+        proof: &str,
+        method: &str,
+        url: &str,
+        access_token: Option<&str>,
+    ) -> Result<String, ApiError> {
+        let invalid = || ApiError::InvalidDpopProof;
+        let (header, _) = decode_unverified(proof).ok_or_else(invalid)?;
+        if header.get("typ").and_then(Value::as_str) != Some(DPOP_PROOF_TYPE)
+            || header.get("alg").and_then(Value::as_str) != Some(ES256)
+        {
+            return Err(invalid());
+        }
+        let jwk = header.get("jwk").ok_or_else(invalid)?;
+        if jwk.get("d").is_some() {
+            return Err(invalid());
+        }
+        let jwk: JwkDto = serde_json::from_value(jwk.clone()).map_err(|_| invalid())?;
+        let key = from_jwk(&jwk).ok_or_else(invalid)?;
+        let claims = verify_es256(proof, &key).ok_or_else(invalid)?;
+
+        let claim = |name: &str| claims.get(name).and_then(Value::as_str);
+        if !claim("htm").is_some_and(|htm| htm.eq_ignore_ascii_case(method))
+            || claim("htu").map(without_query) != Some(without_query(url))
+        {
+            return Err(invalid());
+        }
+        if let Some(token) = access_token {
+            if claim("ath") != Some(access_token_hash(token).as_str()) {
+                return Err(invalid());
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        let iat = claims.get("iat").and_then(Value::as_i64).ok_or_else(invalid)?;
+        // `iat` is chosen by the client, so the difference may overflow.
+        let age = now.checked_sub(iat).map(i64::unsigned_abs);
+        if age.is_none_or(|age| age > self.max_age_seconds.unsigned_abs()) {
+            return Err(invalid());
+        }
+        let jti = claim("jti").filter(|jti| !jti.is_empty()).ok_or_else(invalid)?;
+        let mut seen = self.seen.lock().unwrap();
+        // Proofs are accepted up to and including their last second, so keep
+        // the `jti` through it too.
+        seen.retain(|_, expires| *expires >= now);
+        if seen.contains_key(jti) {
+            return Err(invalid());
+        }
+        seen.insert(jti.to_string(), iat + self.max_age_seconds);
+
+        Ok(jwk_thumbprint(&jwk))
+    }
+}
+
+impl Default for DpopVerifier {
+    fn default() -> Self {
+        Self::new(300)
+    }
+}
+
+/// RFC 9449 compares `htu` without its query and fragment.
+fn without_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// JWK SHA-256 thumbprint (RFC 7638) over the required EC members.
+pub fn jwk_thumbprint(jwk: &JwkDto) -> String {
+    let canonical = format!(
+        r#"{{"crv":"{}","kty":"{}","x":"{}","y":"{}"}}"#,
+        jwk.crv, jwk.kty, jwk.x, jwk.y
+    );
+    general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+pub fn access_token_hash(token: &str) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,460 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use rand::RngCore;
+use rsa::pkcs1v15;
+use rsa::signature::Verifier;
+use rsa::{BigUint, RsaPublicKey};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::OidcProviderConfig;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::models::federation_model::{LoginRedirect, ProviderMetadata};
+use crate::models::oidc_model::JwkDto;
+use crate::models::user_model::UserAccount;
+use crate::repositories::credentials_repository::CredentialRepository;
+use crate::services::jwt_service::{decode_unverified, from_jwk, verify_es256, ES256};
+use crate::services::signature_service::constant_time_eq;
+
+pub const LOGIN_STATE_TTL_SECONDS: i64 = 600;
+/// Most logins waiting for their callback, so abandoned ones cannot fill the memory.
+const MAX_PENDING_LOGINS: usize = 10_000;
+pub const RS256: &str = "RS256";
+/// Cookie holding the value that ties a pending login to the browser that
+/// started it, so a callback URL cannot be replayed in someone else's.
+pub const LOGIN_BINDING_COOKIE: &str = "oidc_login";
+/// Allowed difference between our clock and the provider's for `exp` and `iat`.
+const CLOCK_LEEWAY_SECONDS: i64 = 60;
+/// Default limit for a whole request to a provider, connecting included.
+const PROVIDER_TIMEOUT_SECONDS: u64 = 10;
+/// Upper bound for opening the connection to a provider.
+const PROVIDER_CONNECT_TIMEOUT_SECONDS: u64 = 5;
+
+#[async_trait]
+pub trait FederationService: Send + Sync {
+    /// Starts a login and returns the provider URL to send the browser to,
+    /// with the binding the browser must bring back to the callback. With
+    /// `link_to`, the login links the provider account to that signed-in
+    /// user instead.
+    async fn login_url(&self, provider: &str, link_to: Option<&str>) -> Result<LoginRedirect, ApiError>;
+    /// Redeems the authorization code, validates the ID token and returns
+    /// the local user linked to it, creating one on first login. `binding`
+    /// must be the one `login_url` returned for `state`.
+    async fn complete_login(
+        &self,
+        provider: &str,
+        code: &str,
+        state: &str,
+        binding: Option<&str>,
+    ) -> Result<UserAccount, ApiError>;
+}
+
+struct PendingLogin {
+    provider: String,
+    nonce: String,
+    code_verifier: String,
+    binding: String,
+    /// Username of the signed-in user confirming a link.
+    link_to: Option<String>,
+    expires_at: i64,
+}
+
+/// OpenID Connect relying party using the authorization code flow with PKCE.
+/// Provider metadata and keys are fetched on first use and cached; the keys
+/// are fetched again when a token names a `kid` that is not cached.
+pub struct FederationServiceImpl<C: CredentialRepository> {
+    credential_repository: C,
+    providers: HashMap<String, OidcProviderConfig>,
+    callback_base: String,
+    http: reqwest::Client,
+    metadata: Mutex<HashMap<String, ProviderMetadata>>,
+    keys: Mutex<HashMap<String, Vec<Value>>>,
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+fn error(code: ErrorCodes) -> ApiError {
+    ApiError::ErrorCode(code)
+}
+
+/// A provider that stops answering fails the login with
+/// `IdentityProviderUnavailable` once `timeout` has passed.
+fn http_client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(timeout.min(Duration::from_secs(PROVIDER_CONNECT_TIMEOUT_SECONDS)))
+        .build()
+        .expect("the HTTP client for identity providers could not be built")
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+impl<C: CredentialRepository> FederationServiceImpl<C> {
+    /// `callback_base` is the public URL the provider name and `/callback`
+    /// are appended to, e.g. `https://api.example.com/api/v1/auth/oidc`.
+    pub fn new(credential_repository: C, providers: Vec<OidcProviderConfig>, callback_base: String) -> Self {
+        Self {
+            credential_repository,
+            providers: providers.into_iter().map(|p| (p.name.clone(), p)).collect(),
+            callback_base,
+            http: http_client(Duration::from_secs(PROVIDER_TIMEOUT_SECONDS)),
+            metadata: Mutex::new(HashMap::new()),
+            keys: Mutex::new(HashMap::new()),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Replaces the default limit for requests to providers.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.http = http_client(timeout);
+        self
+    }
+
+    fn provider(&self, name: &str) -> Result<&OidcProviderConfig, ApiError> {
+        self.providers
+            .get(name)
+            .ok_or_else(|| error(ErrorCodes::UnknownIdentityProvider))
+    }
+
+    fn redirect_uri(&self, provider: &str) -> String {
+        format!("{}/{}/callback", self.callback_base, provider)
+    }
+
+    async fn get_json(&self, url: &str) -> Result<Value, ApiError> {
+        let unavailable = |e: reqwest::Error| {
+            eprintln!("identity provider request to {} failed: {}", url, e);
+            error(ErrorCodes::IdentityProviderUnavailable)
+        };
+        self.http
+            .get(url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(unavailable)?
+            .json()
+            .await
+            .map_err(unavailable)
+    }
+
+    async fn provider_metadata(&self, provider: &OidcProviderConfig) -> Result<ProviderMetadata, ApiError> {
+        if let Some(metadata) = self.metadata.lock().unwrap().get(&provider.name) {
+            return Ok(metadata.clone());
+        }
+        let document = self
+            .get_json(&format!("{}/.well-known/openid-configuration", provider.issuer))
+            .await?;
+        let metadata: ProviderMetadata =
+            serde_json::from_value(document).map_err(|_| error(ErrorCodes::IdentityProviderUnavailable))?;
+        if metadata.issuer.trim_end_matches('/') != provider.issuer {
+            eprintln!("identity provider {} reports issuer {}", provider.name, metadata.issuer);
+            return Err(error(ErrorCodes::IdentityProviderUnavailable));
+        }
+        self.metadata
+            .lock()
+            .unwrap()
+            .insert(provider.name.clone(), metadata.clone());
+        Ok(metadata)
+    }
+
+    async fn signing_key(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        kid: Option<&str>,
+    ) -> Result<Value, ApiError> {
+        let select = |keys: &[Value]| {
+            keys.iter()
+                .filter(|k| k.get("use").and_then(Value::as_str).unwrap_or("sig") == "sig")
+                .find(|k| kid.is_none() || k.get("kid").and_then(Value::as_str) == kid)
+                .cloned()
+        };
+        if let Some(key) = self.keys.lock().unwrap().get(&provider.name).and_then(|k| select(k)) {
+            return Ok(key);
+        }
+        let jwks = self.get_json(&metadata.jwks_uri).await?;
+        let keys = jwks
+            .get("keys")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        let key = select(&keys);
+        self.keys.lock().unwrap().insert(provider.name.clone(), keys);
+        key.ok_or_else(|| error(ErrorCodes::FederatedLoginFailed))
+    }
+
+    /// Checks the ID token's signature against the provider JWKS and its
+    /// `iss`, `aud`, `exp`, `iat` and `nonce` claims, returning the claims.
+    async fn validate_id_token(
+        &self,
+        provider: &OidcProviderConfig,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: &str,
+    ) -> Result<Value, ApiError> {
+        let failed = || error(ErrorCodes::FederatedLoginFailed);
+        let (header, claims) = decode_unverified(id_token).ok_or_else(failed)?;
+        let kid = header.get("kid").and_then(Value::as_str);
+        let key = self.signing_key(provider, metadata, kid).await?;
+        let verified = match header.get("alg").and_then(Value::as_str) {
+            Some(RS256) => verify_rs256(id_token, &key),
+            Some(ES256) => serde_json::from_value::<JwkDto>(key)
+                .ok()
+                .and_then(|jwk| from_jwk(&jwk))
+                .and_then(|key| verify_es256(id_token, &key))
+                .is_some(),
+            _ => false,
+        };
+        if !verified {
+            return Err(failed());
+        }
+
+        let now = Utc::now().timestamp();
+        let claim = |name: &str| claims.get(name);
+        let audience_ok = match claim("aud") {
+            Some(Value::String(aud)) => *aud == provider.client_id,
+            Some(Value::Array(auds)) => auds.iter().any(|a| a.as_str() == Some(provider.client_id.as_str())),
+            _ => false,
+        };
+        let valid = claim("iss").and_then(Value::as_str).map(|i| i.trim_end_matches('/')) == Some(provider.issuer.as_str())
+            && audience_ok
+            && claim("exp").and_then(Value::as_i64).is_some_and(|exp| exp + CLOCK_LEEWAY_SECONDS > now)
+            && claim("iat").and_then(Value::as_i64).is_some_and(|iat| iat - CLOCK_LEEWAY_SECONDS <= now)
+            && claim("nonce").and_then(Value::as_str) == Some(nonce)
+            && claim("sub").and_then(Value::as_str).is_some_and(|sub| !sub.is_empty());
+        if !valid {
+            return Err(failed());
+        }
+        Ok(claims)
+    }
+
+    /// Finds the user linked to the provider subject, or on first login
+    /// creates a passwordless user from the token's profile claims. Existing
+    /// users are never linked here: when the provider links by email and the
+    /// verified email is taken, the login stops so that user can sign in and
+    /// confirm the link, and admins are not offered even that.
+    async fn link_user(&self, provider: &OidcProviderConfig, claims: &Value) -> Result<UserAccount, ApiError> {
+        let failed = || error(ErrorCodes::FederatedLoginFailed);
+        let text = |name: &str| claims.get(name).and_then(Value::as_str).map(str::to_string);
+        let subject = text("sub").ok_or_else(failed)?;
+        let user = match self.credential_repository.find_linked_user(&provider.name, &subject).await {
+            Some(user) => user,
+            None => {
+                let email = text("email");
+                let email_verified = claims.get("email_verified").and_then(Value::as_bool) == Some(true);
+                let existing = match &email {
+                    Some(email) if email_verified && provider.link_by_email => {
+                        self.credential_repository.find_user_by_email(email).await
+                    }
+                    _ => None,
+                };
+                if let Some(existing) = existing {
+                    if self.credential_repository.is_admin(&existing.username).await {
+                        eprintln!(
+                            "{} login matches the email of admin {}; not offering a link",
+                            provider.name, existing.username
+                        );
+                        return Err(failed());
+                    }
+                    return Err(error(ErrorCodes::AccountLinkRequired));
+                }
+                let account = UserAccount {
+                    given_name: text("given_name"),
+                    family_name: text("family_name"),
+                    display_name: text("name"),
+                    emails: email.clone().into_iter().collect(),
+                    email_verified,
+                    ..UserAccount::new("")
+                };
+                let base = text("preferred_username")
+                    .or(email)
+                    .unwrap_or_else(|| format!("{}-{}", provider.name, subject));
+                let user = self.create_user(account, &base).await?;
+                self.credential_repository
+                    .link_identity(&provider.name, &subject, &user.id)
+                    .await;
+                user
+            }
+        };
+        if !user.active {
+            return Err(failed());
+        }
+        Ok(user)
+    }
+
+    /// Links the provider subject to `username`, who started the login from
+    /// their own session and so confirmed it.
+    async fn confirm_link(&self, provider: &str, claims: &Value, username: &str) -> Result<UserAccount, ApiError> {
+        let failed = || error(ErrorCodes::FederatedLoginFailed);
+        let subject = claims.get("sub").and_then(Value::as_str).ok_or_else(failed)?;
+        let user = self
+            .credential_repository
+            .find_user_by_username(username)
+            .await
+            .filter(|user| user.active)
+            .ok_or_else(failed)?;
+        match self.credential_repository.find_linked_user(provider, subject).await {
+            Some(linked) if linked.id != user.id => return Err(error(ErrorCodes::IdentityAlreadyLinked)),
+            Some(_) => {}
+            None => self.credential_repository.link_identity(provider, subject, &user.id).await,
+        }
+        Ok(user)
+    }
+
+    /// Saves `account` under `base`, or `base-2`, `base-3`... if taken.
+    async fn create_user(&self, mut account: UserAccount, base: &str) -> Result<UserAccount, ApiError> {
+        for n in 1..=100 {
+            account.username = if n == 1 { base.to_string() } else { format!("{}-{}", base, n) };
+            if self.credential_repository.save_user(account.clone(), None).await {
+                return Ok(account);
+            }
+        }
+        Err(ApiError::InternalServerError)
+    }
+}
+
+fn verify_rs256(token: &str, jwk: &Value) -> bool {
+    let part = |name: &str| {
+        jwk.get(name)
+            .and_then(Value::as_str)
+            .and_then(|v| general_purpose::URL_SAFE_NO_PAD.decode(v).ok())
+    };
+    let (Some(n), Some(e)) = (part("n"), part("e")) else {
+        return false;
+    };
+    let Some((signing_input, signature)) = token.rsplit_once('.') else {
+        return false;
+    };
+    let Ok(key) = RsaPublicKey::new(BigUint::from_bytes_be(&n), BigUint::from_bytes_be(&e)) else {
+        return false;
+    };
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(signature)
+        .ok()
+        .and_then(|s| pkcs1v15::Signature::try_from(s.as_slice()).ok())
+        .is_some_and(|s| {
+            pkcs1v15::VerifyingKey::<Sha256>::new(key)
+                .verify(signing_input.as_bytes(), &s)
+                .is_ok()
+        })
+}
+
+#[async_trait]
+impl<C: CredentialRepository + Send + Sync> FederationService for FederationServiceImpl<C> {
+    async fn login_url(&self, provider: &str, link_to: Option<&str>) -> Result<LoginRedirect, ApiError> {
+        let config = self.provider(provider)?;
+        let metadata = self.provider_metadata(config).await?;
+        let state = random_token();
+        let binding = random_token();
+        let nonce = random_token();
+        let code_verifier = random_token();
+        let code_challenge = general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+        let url = reqwest::Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", config.client_id.as_str()),
+                ("redirect_uri", self.redirect_uri(provider).as_str()),
+                ("scope", config.scopes.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|_| error(ErrorCodes::IdentityProviderUnavailable))?;
+
+        let now = Utc::now().timestamp();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.expires_at > now);
+        if pending.len() >= MAX_PENDING_LOGINS {
+            // Make room by dropping the login closest to expiring.
+            let oldest = pending.iter().min_by_key(|(_, p)| p.expires_at).map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                pending.remove(&oldest);
+            }
+        }
+        pending.insert(
+            state,
+            PendingLogin {
+                provider: provider.to_string(),
+                nonce,
+                code_verifier,
+                binding: binding.clone(),
+                link_to: link_to.map(str::to_string),
+                expires_at: now + LOGIN_STATE_TTL_SECONDS,
+            },
+        );
+        Ok(LoginRedirect {
+            url: url.to_string(),
+            binding,
+        })
+    }
+
+    async fn complete_login(
+        &self,
+        provider: &str,
+        code: &str,
+        state: &str,
+        binding: Option<&str>,
+    ) -> Result<UserAccount, ApiError> {
+        let config = self.provider(provider)?;
+        let pending = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|p| p.provider == provider && p.expires_at > Utc::now().timestamp())
+            .filter(|p| binding.is_some_and(|b| constant_time_eq(b.as_bytes(), p.binding.as_bytes())))
+            .ok_or_else(|| error(ErrorCodes::InvalidLoginState))?;
+        let metadata = self.provider_metadata(config).await?;
+
+        let redirect_uri = self.redirect_uri(provider);
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri.as_str()),
+                ("client_id", config.client_id.as_str()),
+                ("client_secret", config.client_secret.as_str()),
+                ("code_verifier", pending.code_verifier.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| {
+                eprintln!("token request to {} failed: {}", metadata.token_endpoint, e);
+                error(ErrorCodes::IdentityProviderUnavailable)
+            })?;
+        if !response.status().is_success() {
+            return Err(error(ErrorCodes::FederatedLoginFailed));
+        }
+        let tokens: Value = response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                error(ErrorCodes::IdentityProviderUnavailable)
+            } else {
+                error(ErrorCodes::FederatedLoginFailed)
+            }
+        })?;
+        let id_token = tokens
+            .get("id_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| error(ErrorCodes::FederatedLoginFailed))?;
+
+        let claims = self
+            .validate_id_token(config, &metadata, id_token, &pending.nonce)
+            .await?;
+        match &pending.link_to {
+            Some(username) => self.confirm_link(provider, &claims, username).await,
+            None => self.link_user(config, &claims).await,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use base64::{engine::general_purpose, Engine as _};
+use p256::ecdsa::{
+    signature::{Signer, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+use p256::EncodedPoint;
+use rand::RngCore;
+use rand_core::OsRng;
+use serde_json::{json, Value};
+use std::sync::RwLock;
+
+use crate::models::oidc_model::{JwkDto, JwksDto};
+
+pub const ES256: &str = "ES256";
+
+pub trait JwtService: Send + Sync {
+    fn sign(&self, claims: &Value) -> String;
+    fn jwks(&self) -> JwksDto;
+    fn rotate(&self);
+}
+
+struct KeyPair {
+    kid: String,
+    signing_key: SigningKey,
+}
+
+impl KeyPair {
+    fn generate() -> Self {
+        let mut kid = [0u8; 8];
+        rand::rng().fill_bytes(&mut kid);
+        Self {
+            kid: hex::encode(kid),
+            signing_key: SigningKey::random(&mut OsRng),
+        }
+    }
+}
+
+struct KeyRing {
+    current: KeyPair,
+    previous: Option<KeyPair>,
+}
+
+/// Signs tokens with an in-memory ES256 key. The key replaced by the last
+/// rotation is still published and accepted so tokens signed just before a
+/// rotation keep verifying until they expire.
+pub struct Es256JwtService {
+    keys: RwLock<KeyRing>,
+}
+
+impl Es256JwtService {
+    pub fn new() -> Self {
+        Self {
+            keys: RwLock::new(KeyRing {
+                current: KeyPair::generate(),
+                previous: None,
+            }),
+        }
+    }
+}
+
+impl Default for Es256JwtService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JwtService for Es256JwtService {
+    fn sign(&self, claims: &Value) -> String {
+        let keys = self.keys.read().unwrap();
+        encode_es256(&keys.current.signing_key, Some(&keys.current.kid), claims)
+    }
+
+    fn jwks(&self) -> JwksDto {
+        let keys = self.keys.read().unwrap();
+        JwksDto {
+            keys: std::iter::once(&keys.current)
+                .chain(keys.previous.as_ref())
+                .map(|k| to_jwk(k.signing_key.verifying_key(), Some(k.kid.clone())))
+                .collect(),
+        }
+    }
+
+    fn rotate(&self) {
+        let mut keys = self.keys.write().unwrap();
+        let retired = std::mem::replace(&mut keys.current, KeyPair::generate());
+        keys.previous = Some(retired);
+    }
+}
+
+fn b64(bytes: impl AsRef<[u8]>) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+pub fn encode_es256(key: &SigningKey, kid: Option<&str>, claims: &Value) -> String {
+    let mut header = json!({ "alg": ES256, "typ": "JWT" });
+    if let Some(kid) = kid {
+        header["kid"] = json!(kid);
+    }
+    encode_es256_with_header(key, &header, claims)
+}
+
+pub fn encode_es256_with_header(key: &SigningKey, header: &Value, claims: &Value) -> String {
+    let signing_input = format!("{}.{}", b64(header.to_string()), b64(claims.to_string()));
+    let signature: Signature = key.sign(signing_input.as_bytes());
+    format!("{}.{}", signing_input, b64(signature.to_bytes()))
+}
+
+/// Splits a compact JWS and parses its header and payload without checking
+/// the signature; callers must verify before trusting the claims.
+pub fn decode_unverified(token: &str) -> Option<(Value, Value)> {
+    let mut parts = token.split('.');
+    let header = parts.next()?;
+    let payload = parts.next()?;
+    parts.next()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let header = serde_json::from_slice(&general_purpose::URL_SAFE_NO_PAD.decode(header).ok()?).ok()?;
+    let payload = serde_json::from_slice(&general_purpose::URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    Some((header, payload))
+}
+
+pub fn verify_es256(token: &str, key: &VerifyingKey) -> Option<Value> {
+    let (header, payload) = decode_unverified(token)?;
+    if header.get("alg")?.as_str()? != ES256 {
+        return None;
+    }
+    let (signing_input, signature) = token.rsplit_once('.')?;
+    let signature = Signature::from_slice(&general_purpose::URL_SAFE_NO_PAD.decode(signature).ok()?).ok()?;
+    key.verify(signing_input.as_bytes(), &signature).ok()?;
+    Some(payload)
+}
+
+pub fn to_jwk(key: &VerifyingKey, kid: Option<String>) -> JwkDto {
+    let point = key.to_encoded_point(false);
+    JwkDto {
+        kty: "EC".to_string(),
+        crv: "P-256".to_string(),
+        x: b64(point.x().map(|x| x.to_vec()).unwrap_or_default()),
+        y: b64(point.y().map(|y| y.to_vec()).unwrap_or_default()),
+        kid,
+        key_use: Some("sig".to_string()),
+        alg: Some(ES256.to_string()),
+    }
+}
+
+pub fn from_jwk(jwk: &JwkDto) -> Option<VerifyingKey> {
+    if jwk.kty != "EC" || jwk.crv != "P-256" {
+        return None;
+    }
+    let x = general_purpose::URL_SAFE_NO_PAD.decode(&jwk.x).ok()?;
+    let y = general_purpose::URL_SAFE_NO_PAD.decode(&jwk.y).ok()?;
+    if x.len() != 32 || y.len() != 32 {
+        return None;
+    }
+    let point = EncodedPoint::from_affine_coordinates(x.as_slice().into(), y.as_slice().into(), false);
+    VerifyingKey::from_encoded_point(&point).ok()
+}
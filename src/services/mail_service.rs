@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::errors::ApiError;
+use crate::models::mail_model::MailMessage;
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: MailMessage) -> Result<(), ApiError>;
+}
+
+/// Delivers mail through an SMTP relay.
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// `tls` is `starttls` (upgrade on connect), `tls` (implicit TLS, usually
+    /// port 465) or `none` for local relays such as MailHog.
+    pub fn new(
+        host: &str,
+        port: u16,
+        tls: &str,
+        credentials: Option<(String, String)>,
+        from: &str,
+    ) -> Result<Self, String> {
+        let from: Mailbox = from.parse().map_err(|e| format!("invalid sender address: {}", e))?;
+        let mut builder = match tls {
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(|e| e.to_string())?,
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(|e| e.to_string())?,
+        }
+        .port(port);
+        if let Some((username, password)) = credentials {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), ApiError> {
+        let to: Mailbox = message
+            .to
+            .parse()
+            .map_err(|_| ApiError::InternalServerError)?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body)
+            .map_err(|_| ApiError::InternalServerError)?;
+        self.transport.send(email).await.map_err(|e| {
+            eprintln!("failed to send mail: {}", e);
+            ApiError::InternalServerError
+        })?;
+        Ok(())
+    }
+}
+
+/// Stands in for SMTP when `SMTP_HOST` is not set. Messages are dropped and
+/// only their recipient and subject are logged, since bodies carry reset and
+/// verification codes.
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), ApiError> {
+        println!("mail to {} not sent, SMTP_HOST is not set: {}", message.to, message.subject);
+        Ok(())
+    }
+}
@@ -1,2 +1,13 @@
 pub mod auth_service;
 pub mod base_service;
+pub mod audit_service;
+pub mod jwt_service;
+pub mod dpop_service;
+pub mod scim_filter;
+pub mod scim_service;
+pub mod mail_service;
+pub mod account_service;
+pub mod signature_service;
+pub mod webauthn_service;
+pub mod federation_service;
+pub mod device_service;
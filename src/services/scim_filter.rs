@@ -0,0 +1,178 @@
+use serde_json::Value;
+
+/// The subset of the RFC 7644 filter grammar supported by the SCIM list
+/// endpoints: `pr`, `eq`, `ne`, `co`, `sw` and `ew` comparisons joined with
+/// `and`/`or` (`and` binds tighter). Attribute names are case-insensitive and
+/// sub-attributes of multi-valued attributes match if any value matches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScimFilter {
+    Present(String),
+    Compare(String, Operator, Value),
+    And(Box<ScimFilter>, Box<ScimFilter>),
+    Or(Box<ScimFilter>, Box<ScimFilter>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+}
+
+/// Attributes whose values are compared case-sensitively (RFC 7643 `caseExact`).
+const CASE_EXACT: [&str; 2] = ["id", "externalid"];
+
+impl ScimFilter {
+    pub fn parse(input: &str) -> Option<Self> {
+        let tokens = tokenize(input)?;
+        let mut pos = 0;
+        let filter = parse_or(&tokens, &mut pos)?;
+        (pos == tokens.len()).then_some(filter)
+    }
+
+    pub fn matches(&self, resource: &Value) -> bool {
+        match self {
+            ScimFilter::Present(path) => lookup(resource, path)
+                .iter()
+                .any(|v| !v.is_null() && v.as_str() != Some("")),
+            ScimFilter::Compare(path, op, expected) => {
+                let case_exact = CASE_EXACT.contains(&path.to_lowercase().as_str());
+                let values = lookup(resource, path);
+                match op {
+                    Operator::Ne => !values.iter().any(|v| compare(v, Operator::Eq, expected, case_exact)),
+                    _ => values.iter().any(|v| compare(v, *op, expected, case_exact)),
+                }
+            }
+            ScimFilter::And(a, b) => a.matches(resource) && b.matches(resource),
+            ScimFilter::Or(a, b) => a.matches(resource) || b.matches(resource),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Option<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut literal = String::from("\"");
+            loop {
+                match chars.next()? {
+                    '\\' => literal.push(chars.next()?),
+                    '"' => break,
+                    other => literal.push(other),
+                }
+            }
+            tokens.push(literal);
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(word);
+        }
+    }
+    Some(tokens)
+}
+
+fn parse_or(tokens: &[String], pos: &mut usize) -> Option<ScimFilter> {
+    let mut left = parse_and(tokens, pos)?;
+    while tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("or")) {
+        *pos += 1;
+        left = ScimFilter::Or(Box::new(left), Box::new(parse_and(tokens, pos)?));
+    }
+    Some(left)
+}
+
+fn parse_and(tokens: &[String], pos: &mut usize) -> Option<ScimFilter> {
+    let mut left = parse_comparison(tokens, pos)?;
+    while tokens.get(*pos).is_some_and(|t| t.eq_ignore_ascii_case("and")) {
+        *pos += 1;
+        left = ScimFilter::And(Box::new(left), Box::new(parse_comparison(tokens, pos)?));
+    }
+    Some(left)
+}
+
+fn parse_comparison(tokens: &[String], pos: &mut usize) -> Option<ScimFilter> {
+    let path = tokens.get(*pos)?.clone();
+    if path.starts_with('"') {
+        return None;
+    }
+    let op = tokens.get(*pos + 1)?.to_lowercase();
+    if op == "pr" {
+        *pos += 2;
+        return Some(ScimFilter::Present(path));
+    }
+    let op = match op.as_str() {
+        "eq" => Operator::Eq,
+        "ne" => Operator::Ne,
+        "co" => Operator::Co,
+        "sw" => Operator::Sw,
+        "ew" => Operator::Ew,
+        _ => return None,
+    };
+    let value = parse_value(tokens.get(*pos + 2)?)?;
+    *pos += 3;
+    Some(ScimFilter::Compare(path, op, value))
+}
+
+fn parse_value(token: &str) -> Option<Value> {
+    if let Some(literal) = token.strip_prefix('"') {
+        return Some(Value::String(literal.to_string()));
+    }
+    match token {
+        "true" => Some(Value::Bool(true)),
+        "false" => Some(Value::Bool(false)),
+        "null" => Some(Value::Null),
+        _ => serde_json::from_str::<serde_json::Number>(token).ok().map(Value::Number),
+    }
+}
+
+/// Resolves a dotted attribute path, flattening multi-valued attributes.
+fn lookup<'a>(resource: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut current = vec![resource];
+    for segment in path.split('.') {
+        current = current
+            .into_iter()
+            .filter_map(|value| {
+                value
+                    .as_object()?
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(segment))
+                    .map(|(_, v)| v)
+            })
+            .flat_map(|value| match value {
+                Value::Array(items) => items.iter().collect(),
+                other => vec![other],
+            })
+            .collect();
+    }
+    current
+}
+
+fn compare(actual: &Value, op: Operator, expected: &Value, case_exact: bool) -> bool {
+    match (actual, expected) {
+        (Value::String(actual), Value::String(expected)) => {
+            let (actual, expected) = if case_exact {
+                (actual.clone(), expected.clone())
+            } else {
+                (actual.to_lowercase(), expected.to_lowercase())
+            };
+            match op {
+                Operator::Eq | Operator::Ne => actual == expected,
+                Operator::Co => actual.contains(&expected),
+                Operator::Sw => actual.starts_with(&expected),
+                Operator::Ew => actual.ends_with(&expected),
+            }
+        }
+        (actual, expected) => matches!(op, Operator::Eq | Operator::Ne) && actual == expected,
+    }
+}
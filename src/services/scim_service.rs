@@ -0,0 +1,424 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use crate::config::PasswordPolicy;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::models::scim_model::{
+    ScimEmailDto, ScimGroupDto, ScimGroupListDto, ScimListQuery, ScimMetaDto, ScimNameDto,
+    ScimPatchOperationDto, ScimPatchRequestDto, ScimReferenceDto, ScimUserDto, ScimUserListDto,
+    GROUP_SCHEMA, LIST_RESPONSE_SCHEMA, PATCH_OP_SCHEMA, USER_SCHEMA,
+};
+use crate::models::user_model::{GroupModel, UserAccount};
+use crate::repositories::{credentials_repository::CredentialRepository, group_repository::GroupRepository};
+use crate::services::account_service::AccountService;
+use crate::services::scim_filter::ScimFilter;
+use crate::validators::password_validator::validate_password;
+
+/// Scope a client token needs to call the SCIM endpoints.
+pub const SCIM_SCOPE: &str = "scim";
+pub const MAX_RESULTS: usize = 100;
+
+#[async_trait]
+pub trait ScimService: Send + Sync {
+    async fn list_users(&self, query: &ScimListQuery) -> Result<ScimUserListDto, ApiError>;
+    async fn get_user(&self, id: &str) -> Result<ScimUserDto, ApiError>;
+    async fn create_user(&self, user: ScimUserDto) -> Result<ScimUserDto, ApiError>;
+    async fn replace_user(&self, id: &str, user: ScimUserDto) -> Result<ScimUserDto, ApiError>;
+    async fn patch_user(&self, id: &str, patch: ScimPatchRequestDto) -> Result<ScimUserDto, ApiError>;
+    async fn delete_user(&self, id: &str) -> Result<(), ApiError>;
+    async fn list_groups(&self, query: &ScimListQuery) -> Result<ScimGroupListDto, ApiError>;
+    async fn get_group(&self, id: &str) -> Result<ScimGroupDto, ApiError>;
+    async fn create_group(&self, group: ScimGroupDto) -> Result<ScimGroupDto, ApiError>;
+    async fn replace_group(&self, id: &str, group: ScimGroupDto) -> Result<ScimGroupDto, ApiError>;
+    async fn patch_group(&self, id: &str, patch: ScimPatchRequestDto) -> Result<ScimGroupDto, ApiError>;
+    async fn delete_group(&self, id: &str) -> Result<(), ApiError>;
+}
+
+pub struct ScimServiceImpl<C: CredentialRepository, G: GroupRepository> {
+    credential_repository: C,
+    group_repository: G,
+    base_url: String,
+    account_service: Option<Arc<dyn AccountService>>,
+    password_policy: PasswordPolicy,
+}
+
+impl<C: CredentialRepository, G: GroupRepository> ScimServiceImpl<C, G> {
+    /// `base_url` is the public SCIM root (e.g. `https://host/scim/v2`) used
+    /// for `meta.location`.
+    pub fn new(credential_repository: C, group_repository: G, base_url: String) -> Self {
+        Self {
+            credential_repository,
+            group_repository,
+            base_url,
+            account_service: None,
+            password_policy: PasswordPolicy::default(),
+        }
+    }
+
+    /// Replaces the default rules new passwords are checked against.
+    pub fn with_password_policy(mut self, policy: PasswordPolicy) -> Self {
+        self.password_policy = policy;
+        self
+    }
+
+    /// Mails a verification code to every user created with an email address.
+    pub fn with_email_verification(mut self, account_service: Arc<dyn AccountService>) -> Self {
+        self.account_service = Some(account_service);
+        self
+    }
+
+    fn meta(&self, resource_type: &str, id: &str, created: chrono::DateTime<Utc>, last_modified: chrono::DateTime<Utc>) -> ScimMetaDto {
+        ScimMetaDto {
+            resource_type: resource_type.to_string(),
+            created,
+            last_modified,
+            location: format!("{}/{}s/{}", self.base_url, resource_type, id),
+        }
+    }
+
+    async fn user_dto(&self, account: UserAccount) -> ScimUserDto {
+        let groups = self
+            .group_repository
+            .list_groups()
+            .await
+            .into_iter()
+            .filter(|g| g.members.contains(&account.id))
+            .map(|g| ScimReferenceDto {
+                value: g.id,
+                display: Some(g.display_name),
+            })
+            .collect();
+        let name = (account.given_name.is_some() || account.family_name.is_some()).then(|| ScimNameDto {
+            given_name: account.given_name.clone(),
+            family_name: account.family_name.clone(),
+        });
+        ScimUserDto {
+            schemas: vec![USER_SCHEMA.to_string()],
+            meta: Some(self.meta("User", &account.id, account.created, account.last_modified)),
+            id: Some(account.id),
+            external_id: account.external_id,
+            user_name: account.username,
+            name,
+            display_name: account.display_name,
+            emails: account
+                .emails
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| ScimEmailDto {
+                    value,
+                    primary: Some(i == 0),
+                })
+                .collect(),
+            active: Some(account.active),
+            password: None,
+            groups,
+        }
+    }
+
+    async fn group_dto(&self, group: GroupModel) -> ScimGroupDto {
+        let mut members = Vec::new();
+        for id in &group.members {
+            members.push(ScimReferenceDto {
+                value: id.clone(),
+                display: self.credential_repository.find_user(id).await.map(|u| u.username),
+            });
+        }
+        ScimGroupDto {
+            schemas: vec![GROUP_SCHEMA.to_string()],
+            meta: Some(self.meta("Group", &group.id, group.created, group.last_modified)),
+            id: Some(group.id),
+            external_id: group.external_id,
+            display_name: group.display_name,
+            members,
+        }
+    }
+
+    /// Admin rights follow the username, so a provisioning client must not
+    /// change, take over or remove an admin account.
+    async fn check_not_admin(&self, username: &str) -> Result<(), ApiError> {
+        if self.credential_repository.is_admin(username).await {
+            return Err(ApiError::Forbidden);
+        }
+        Ok(())
+    }
+
+    async fn save_user(&self, mut account: UserAccount, user: ScimUserDto) -> Result<ScimUserDto, ApiError> {
+        self.check_not_admin(&account.username).await?;
+        self.check_not_admin(&user.user_name).await?;
+        if user.user_name.trim().is_empty() {
+            return Err(ApiError::ErrorCode(ErrorCodes::ScimInvalidValue));
+        }
+        account.username = user.user_name;
+        account.external_id = user.external_id;
+        let name = user.name.unwrap_or_default();
+        account.given_name = name.given_name;
+        account.family_name = name.family_name;
+        account.display_name = user.display_name;
+        // The primary address goes first; the store keeps a plain list.
+        let mut emails = user.emails;
+        emails.sort_by_key(|e| !e.primary.unwrap_or(false));
+        account.emails = emails.into_iter().map(|e| e.value).collect();
+        account.active = user.active.unwrap_or(true);
+        account.last_modified = Utc::now();
+        let password = match user.password {
+            Some(password) => Some(validate_password(&self.password_policy, &password, &account.username)?),
+            None => None,
+        };
+        if !self
+            .credential_repository
+            .save_user(account.clone(), password)
+            .await
+        {
+            return Err(ApiError::ErrorCode(ErrorCodes::ScimUniqueness));
+        }
+        Ok(self.user_dto(account).await)
+    }
+
+    async fn save_group(&self, mut group: GroupModel, dto: ScimGroupDto) -> Result<ScimGroupDto, ApiError> {
+        if dto.display_name.trim().is_empty() {
+            return Err(ApiError::ErrorCode(ErrorCodes::ScimInvalidValue));
+        }
+        let mut members: Vec<String> = Vec::new();
+        for member in dto.members {
+            if self.credential_repository.find_user(&member.value).await.is_none() {
+                return Err(ApiError::ErrorCode(ErrorCodes::ScimInvalidValue));
+            }
+            if !members.contains(&member.value) {
+                members.push(member.value);
+            }
+        }
+        group.display_name = dto.display_name;
+        group.external_id = dto.external_id;
+        group.members = members;
+        group.last_modified = Utc::now();
+        if !self.group_repository.save_group(group.clone()).await {
+            return Err(ApiError::ErrorCode(ErrorCodes::ScimUniqueness));
+        }
+        Ok(self.group_dto(group).await)
+    }
+
+    async fn find_user(&self, id: &str) -> Result<UserAccount, ApiError> {
+        self.credential_repository
+            .find_user(id)
+            .await
+            .ok_or(ApiError::ErrorCode(ErrorCodes::ScimResourceNotFound))
+    }
+
+    async fn find_group(&self, id: &str) -> Result<GroupModel, ApiError> {
+        self.group_repository
+            .find_group(id)
+            .await
+            .ok_or(ApiError::ErrorCode(ErrorCodes::ScimResourceNotFound))
+    }
+}
+
+/// Applies filtering and 1-based paging to already-rendered resources.
+fn page<T: serde::Serialize>(resources: Vec<T>, query: &ScimListQuery) -> Result<(usize, usize, Vec<T>), ApiError> {
+    let filter = match query.filter.as_deref().filter(|f| !f.trim().is_empty()) {
+        Some(filter) => Some(ScimFilter::parse(filter).ok_or(ApiError::ErrorCode(ErrorCodes::ScimInvalidFilter))?),
+        None => None,
+    };
+    let matching: Vec<T> = resources
+        .into_iter()
+        .filter(|r| {
+            filter
+                .as_ref()
+                .is_none_or(|f| f.matches(&serde_json::to_value(r).unwrap_or(Value::Null)))
+        })
+        .collect();
+    let total = matching.len();
+    let start_index = query.start_index.unwrap_or(1).max(1);
+    let count = query.count.unwrap_or(MAX_RESULTS).min(MAX_RESULTS);
+    let items = matching.into_iter().skip(start_index - 1).take(count).collect();
+    Ok((total, start_index, items))
+}
+
+/// Runs PATCH operations against the JSON form of a resource; the caller
+/// deserializes the result and stores it like a PUT.
+fn apply_patch<T: serde::Serialize + serde::de::DeserializeOwned>(
+    resource: &T,
+    patch: ScimPatchRequestDto,
+) -> Result<T, ApiError> {
+    let invalid = || ApiError::ErrorCode(ErrorCodes::ScimInvalidPatch);
+    if !patch.schemas.iter().any(|s| s == PATCH_OP_SCHEMA) {
+        return Err(invalid());
+    }
+    let mut value = serde_json::to_value(resource).map_err(|_| invalid())?;
+    for operation in &patch.operations {
+        apply_operation(&mut value, operation).ok_or_else(invalid)?;
+    }
+    serde_json::from_value(value).map_err(|_| invalid())
+}
+
+fn apply_operation(resource: &mut Value, operation: &ScimPatchOperationDto) -> Option<()> {
+    let op = operation.op.to_lowercase();
+    let Some(path) = operation.path.as_deref().filter(|p| !p.is_empty()) else {
+        // Without a path the value is an object of attributes to add or replace.
+        if op == "remove" {
+            return None;
+        }
+        for (key, value) in operation.value.as_ref()?.as_object()? {
+            set_attribute(resource.as_object_mut()?, key, value.clone(), op == "add");
+        }
+        return Some(());
+    };
+
+    if let Some((attribute, rest)) = path.split_once('[') {
+        // `members[value eq "id"]`: only removal of matching values is supported.
+        let filter = ScimFilter::parse(rest.strip_suffix(']')?)?;
+        if op != "remove" {
+            return None;
+        }
+        let items = find_key_mut(resource.as_object_mut()?, attribute)?.as_array_mut()?;
+        items.retain(|item| !filter.matches(item));
+        return Some(());
+    }
+
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let last = segments.pop()?;
+    let mut target = resource.as_object_mut()?;
+    for segment in segments {
+        let key = existing_key(target, segment).unwrap_or_else(|| segment.to_string());
+        let entry = target.entry(key).or_insert_with(|| Value::Object(Map::new()));
+        if entry.is_null() {
+            *entry = Value::Object(Map::new());
+        }
+        target = entry.as_object_mut()?;
+    }
+    match op.as_str() {
+        "add" | "replace" => set_attribute(target, last, operation.value.clone()?, op == "add"),
+        "remove" => {
+            if let Some(key) = existing_key(target, last) {
+                target.remove(&key);
+            }
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+/// SCIM attribute names are case-insensitive.
+fn existing_key(object: &Map<String, Value>, name: &str) -> Option<String> {
+    object.keys().find(|k| k.eq_ignore_ascii_case(name)).cloned()
+}
+
+fn find_key_mut<'a>(object: &'a mut Map<String, Value>, name: &str) -> Option<&'a mut Value> {
+    let key = existing_key(object, name)?;
+    object.get_mut(&key)
+}
+
+/// `add` appends to multi-valued attributes; everything else overwrites.
+fn set_attribute(object: &mut Map<String, Value>, name: &str, value: Value, append: bool) {
+    let key = existing_key(object, name).unwrap_or_else(|| name.to_string());
+    match (object.get_mut(&key), value) {
+        (Some(Value::Array(items)), Value::Array(new_items)) if append => items.extend(new_items),
+        (Some(Value::Array(items)), value) if append => items.push(value),
+        (_, value) => {
+            object.insert(key, value);
+        }
+    }
+}
+
+#[async_trait]
+impl<C: CredentialRepository + Send + Sync, G: GroupRepository + Send + Sync> ScimService
+    for ScimServiceImpl<C, G>
+{
+    async fn list_users(&self, query: &ScimListQuery) -> Result<ScimUserListDto, ApiError> {
+        let mut users = Vec::new();
+        for account in self.credential_repository.list_users().await {
+            users.push(self.user_dto(account).await);
+        }
+        let (total_results, start_index, resources) = page(users, query)?;
+        Ok(ScimUserListDto {
+            schemas: vec![LIST_RESPONSE_SCHEMA.to_string()],
+            total_results,
+            start_index,
+            items_per_page: resources.len(),
+            resources,
+        })
+    }
+
+    async fn get_user(&self, id: &str) -> Result<ScimUserDto, ApiError> {
+        let account = self.find_user(id).await?;
+        Ok(self.user_dto(account).await)
+    }
+
+    async fn create_user(&self, user: ScimUserDto) -> Result<ScimUserDto, ApiError> {
+        let account = UserAccount::new(&user.user_name);
+        let created = self.save_user(account, user).await?;
+        if let (Some(account_service), Some(id)) = (&self.account_service, &created.id) {
+            if !created.emails.is_empty() && account_service.send_verification(id).await.is_err() {
+                eprintln!("verification mail for {} was not sent", created.user_name);
+            }
+        }
+        Ok(created)
+    }
+
+    async fn replace_user(&self, id: &str, user: ScimUserDto) -> Result<ScimUserDto, ApiError> {
+        let account = self.find_user(id).await?;
+        self.save_user(account, user).await
+    }
+
+    async fn patch_user(&self, id: &str, patch: ScimPatchRequestDto) -> Result<ScimUserDto, ApiError> {
+        let account = self.find_user(id).await?;
+        let patched = apply_patch(&self.user_dto(account.clone()).await, patch)?;
+        self.save_user(account, patched).await
+    }
+
+    async fn delete_user(&self, id: &str) -> Result<(), ApiError> {
+        let account = self.find_user(id).await?;
+        self.check_not_admin(&account.username).await?;
+        if !self.credential_repository.delete_user(id).await {
+            return Err(ApiError::ErrorCode(ErrorCodes::ScimResourceNotFound));
+        }
+        self.group_repository.remove_member(id).await;
+        Ok(())
+    }
+
+    async fn list_groups(&self, query: &ScimListQuery) -> Result<ScimGroupListDto, ApiError> {
+        let mut groups = Vec::new();
+        for group in self.group_repository.list_groups().await {
+            groups.push(self.group_dto(group).await);
+        }
+        let (total_results, start_index, resources) = page(groups, query)?;
+        Ok(ScimGroupListDto {
+            schemas: vec![LIST_RESPONSE_SCHEMA.to_string()],
+            total_results,
+            start_index,
+            items_per_page: resources.len(),
+            resources,
+        })
+    }
+
+    async fn get_group(&self, id: &str) -> Result<ScimGroupDto, ApiError> {
+        let group = self.find_group(id).await?;
+        Ok(self.group_dto(group).await)
+    }
+
+    async fn create_group(&self, group: ScimGroupDto) -> Result<ScimGroupDto, ApiError> {
+        let model = GroupModel::new(&group.display_name);
+        self.save_group(model, group).await
+    }
+
+    async fn replace_group(&self, id: &str, group: ScimGroupDto) -> Result<ScimGroupDto, ApiError> {
+        let model = self.find_group(id).await?;
+        self.save_group(model, group).await
+    }
+
+    async fn patch_group(&self, id: &str, patch: ScimPatchRequestDto) -> Result<ScimGroupDto, ApiError> {
+        let model = self.find_group(id).await?;
+        let patched = apply_patch(&self.group_dto(model.clone()).await, patch)?;
+        self.save_group(model, patched).await
+    }
+
+    async fn delete_group(&self, id: &str) -> Result<(), ApiError> {
+        if self.group_repository.delete_group(id).await {
+            Ok(())
+        } else {
+            Err(ApiError::ErrorCode(ErrorCodes::ScimResourceNotFound))
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::errors::ApiError;
+
+pub const SIGNATURE_SCHEME: &str = "HMAC-SHA256";
+pub const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+pub const NONCE_HEADER: &str = "x-signature-nonce";
+
+/// Everything a signed request commits to. `headers` holds the values of
+/// the headers listed in `SignedHeaders`, in that order.
+#[derive(Debug, Clone)]
+pub struct SignedRequest {
+    pub client_id: String,
+    pub signature: String,
+    pub timestamp: i64,
+    pub nonce: String,
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl SignedRequest {
+    /// Parses `HMAC-SHA256 Credential=<client_id>, SignedHeaders=<a;b>, Signature=<hex>`
+    /// into the client id, the signed header names and the signature.
+    pub fn parse_authorization(header: &str) -> Option<(String, Vec<String>, String)> {
+        let params = header.strip_prefix(SIGNATURE_SCHEME)?.strip_prefix(' ')?;
+        let mut credential = None;
+        let mut signed_headers = None;
+        let mut signature = None;
+        for param in params.split(',') {
+            let (name, value) = param.trim().split_once('=')?;
+            match name {
+                "Credential" => credential = Some(value.to_string()),
+                "SignedHeaders" => {
+                    signed_headers = Some(
+                        value
+                            .split(';')
+                            .filter(|h| !h.is_empty())
+                            .map(str::to_ascii_lowercase)
+                            .collect(),
+                    )
+                }
+                "Signature" => signature = Some(value.to_ascii_lowercase()),
+                _ => return None,
+            }
+        }
+        Some((credential?, signed_headers.unwrap_or_default(), signature?))
+    }
+
+    /// The string both sides sign: scheme, timestamp, nonce, method, path,
+    /// sorted query pairs, `name:value` lines for the signed headers, their
+    /// names, and the hex SHA-256 of the body, joined by newlines.
+    pub fn canonical(&self) -> String {
+        let mut query: Vec<&str> = self.query.split('&').filter(|p| !p.is_empty()).collect();
+        query.sort_unstable();
+        let headers: Vec<String> = self
+            .headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name.to_ascii_lowercase(), value.trim()))
+            .collect();
+        let names: Vec<String> = self.headers.iter().map(|(name, _)| name.to_ascii_lowercase()).collect();
+        [
+            SIGNATURE_SCHEME.to_string(),
+            self.timestamp.to_string(),
+            self.nonce.clone(),
+            self.method.to_ascii_uppercase(),
+            self.path.clone(),
+            query.join("&"),
+            headers.join("\n"),
+            names.join(";"),
+            hex::encode(Sha256::digest(&self.body)),
+        ]
+        .join("\n")
+    }
+}
+
+/// Hex HMAC-SHA256 of `canonical` keyed with the client secret.
+pub fn sign(secret: &str, canonical: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(canonical.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Checks HMAC request signatures. Timestamps may be off by `max_skew_seconds`
+/// either way, and each client's nonces are remembered for as long as their
+/// timestamp is acceptable so a captured request cannot be replayed.
+pub struct SignatureVerifier {
+    max_skew_seconds: i64,
+    seen: Mutex<HashMap<(String, String), i64>>,
+}
+
+impl SignatureVerifier {
+    pub fn new(max_skew_seconds: i64) -> Self {
+        Self {
+            max_skew_seconds,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn verify(&self, request: &SignedRequest, secret: &str) -> Result<(), ApiError> {
+        let invalid = || ApiError::InvalidSignature;
+        let now = Utc::now().timestamp();
+        // The timestamp is chosen by the client, so the difference may overflow.
+        let skew = now.checked_sub(request.timestamp).map(i64::unsigned_abs);
+        if skew.is_none_or(|skew| skew > self.max_skew_seconds.unsigned_abs()) || request.nonce.is_empty() {
+            return Err(invalid());
+        }
+        if !constant_time_eq(sign(secret, &request.canonical()).as_bytes(), request.signature.as_bytes()) {
+            return Err(invalid());
+        }
+
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, expires| *expires >= now);
+        let key = (request.client_id.clone(), request.nonce.clone());
+        if seen.contains_key(&key) {
+            return Err(invalid());
+        }
+        seen.insert(key, request.timestamp + self.max_skew_seconds);
+        Ok(())
+    }
+}
+
+/// Compares secrets in time that depends only on their lengths.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl Default for SignatureVerifier {
+    fn default() -> Self {
+        Self::new(300)
+    }
+}
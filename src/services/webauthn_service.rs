@@ -0,0 +1,346 @@
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use ciborium::Value as Cbor;
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use p256::EncodedPoint;
+use rand::RngCore;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::models::webauthn_model::{
+    AuthenticatorSelectionDto, CreationOptionsDto, CredentialDescriptorDto, PubKeyCredParamDto,
+    RegistrationCredentialDto, RelyingPartyDto, RequestOptionsDto, WebauthnAssertion, WebauthnCredential,
+    WebauthnUserDto,
+};
+use crate::repositories::credentials_repository::CredentialRepository;
+
+pub const CEREMONY_TTL_SECONDS: i64 = 300;
+/// Most challenges kept at once, so unanswered ones cannot fill the memory.
+const MAX_PENDING_CEREMONIES: usize = 10_000;
+/// COSE algorithm id of ES256, the only one accepted.
+pub const COSE_ES256: i64 = -7;
+pub const PUBLIC_KEY_TYPE: &str = "public-key";
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+#[async_trait]
+pub trait WebauthnService: Send + Sync {
+    async fn registration_options(&self, username: &str) -> Result<CreationOptionsDto, ApiError>;
+    async fn register(
+        &self,
+        username: &str,
+        credential: RegistrationCredentialDto,
+    ) -> Result<WebauthnCredential, ApiError>;
+    async fn login_options(&self, username: Option<&str>) -> RequestOptionsDto;
+    /// Verifies an assertion and returns the username it authenticates.
+    async fn authenticate(&self, assertion: &WebauthnAssertion) -> Result<String, ApiError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ceremony {
+    Registration,
+    Authentication,
+}
+
+struct PendingCeremony {
+    ceremony: Ceremony,
+    user_id: Option<String>,
+    expires_at: i64,
+}
+
+/// What the authenticator data of a ceremony says, with the attested
+/// credential present only on registration.
+struct AuthenticatorData {
+    sign_count: u32,
+    credential: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Passkey registration and login (WebAuthn Level 2) for ES256 credentials
+/// with `none` attestation. Challenges live in memory and are single use.
+pub struct WebauthnServiceImpl<C: CredentialRepository> {
+    credential_repository: C,
+    rp_id: String,
+    rp_name: String,
+    origin: String,
+    pending: Mutex<HashMap<String, PendingCeremony>>,
+}
+
+impl<C: CredentialRepository> WebauthnServiceImpl<C> {
+    pub fn new(credential_repository: C, rp_id: String, rp_name: String, origin: String) -> Self {
+        Self {
+            credential_repository,
+            rp_id,
+            rp_name,
+            origin,
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn start(&self, ceremony: Ceremony, user_id: Option<String>) -> String {
+        let mut bytes = [0u8; 32];
+        rand::rng().fill_bytes(&mut bytes);
+        let challenge = b64(bytes);
+        let now = Utc::now().timestamp();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.expires_at > now);
+        if pending.len() >= MAX_PENDING_CEREMONIES {
+            // Make room by dropping the challenge closest to expiring.
+            let oldest = pending.iter().min_by_key(|(_, p)| p.expires_at).map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                pending.remove(&oldest);
+            }
+        }
+        pending.insert(
+            challenge.clone(),
+            PendingCeremony {
+                ceremony,
+                user_id,
+                expires_at: now + CEREMONY_TTL_SECONDS,
+            },
+        );
+        challenge
+    }
+
+    /// Checks `clientDataJSON` and consumes the challenge it answers.
+    fn finish(&self, client_data_json: &[u8], ceremony: Ceremony) -> Option<PendingCeremony> {
+        let expected_type = match ceremony {
+            Ceremony::Registration => "webauthn.create",
+            Ceremony::Authentication => "webauthn.get",
+        };
+        let client_data: Value = serde_json::from_slice(client_data_json).ok()?;
+        let field = |name: &str| client_data.get(name).and_then(Value::as_str);
+        if field("type")? != expected_type || field("origin")? != self.origin {
+            return None;
+        }
+        let pending = self.pending.lock().unwrap().remove(field("challenge")?)?;
+        if pending.ceremony != ceremony || pending.expires_at <= Utc::now().timestamp() {
+            return None;
+        }
+        Some(pending)
+    }
+
+    fn parse_authenticator_data(&self, data: &[u8]) -> Option<AuthenticatorData> {
+        if data.len() < 37 || data[..32] != Sha256::digest(self.rp_id.as_bytes())[..] {
+            return None;
+        }
+        let flags = data[32];
+        if flags & FLAG_USER_PRESENT == 0 {
+            return None;
+        }
+        let sign_count = u32::from_be_bytes(data[33..37].try_into().ok()?);
+        let credential = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+            // aaguid (16 bytes), credential id length (2), credential id, COSE key.
+            let rest = data.get(37 + 16..)?;
+            let id_len = u16::from_be_bytes(rest.get(..2)?.try_into().ok()?) as usize;
+            let id = rest.get(2..2 + id_len)?.to_vec();
+            let key: Cbor = ciborium::de::from_reader(rest.get(2 + id_len..)?).ok()?;
+            Some((id, cose_to_sec1(&key)?))
+        } else {
+            None
+        };
+        Some(AuthenticatorData {
+            sign_count,
+            credential,
+        })
+    }
+
+    fn descriptors(credentials: Vec<WebauthnCredential>) -> Vec<CredentialDescriptorDto> {
+        credentials
+            .into_iter()
+            .map(|c| CredentialDescriptorDto {
+                credential_type: PUBLIC_KEY_TYPE.to_string(),
+                id: c.id,
+            })
+            .collect()
+    }
+}
+
+fn b64(bytes: impl AsRef<[u8]>) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn unb64(value: &str) -> Option<Vec<u8>> {
+    general_purpose::URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')).ok()
+}
+
+/// Converts an EC2 P-256 COSE key (RFC 9053) to its SEC1 encoding.
+fn cose_to_sec1(key: &Cbor) -> Option<Vec<u8>> {
+    let map = key.as_map()?;
+    let get = |label: i64| {
+        map.iter()
+            .find(|(k, _)| k.as_integer().is_some_and(|k| i128::from(k) == label as i128))
+            .map(|(_, v)| v)
+    };
+    let int = |label: i64| get(label)?.as_integer().map(i128::from);
+    if int(1)? != 2 || int(3)? != COSE_ES256 as i128 || int(-1)? != 1 {
+        return None;
+    }
+    let x = get(-2)?.as_bytes()?;
+    let y = get(-3)?.as_bytes()?;
+    if x.len() != 32 || y.len() != 32 {
+        return None;
+    }
+    let point = EncodedPoint::from_affine_coordinates(x.as_slice().into(), y.as_slice().into(), false);
+    VerifyingKey::from_encoded_point(&point).ok()?;
+    Some(point.as_bytes().to_vec())
+}
+
+fn attestation_auth_data(attestation_object: &[u8]) -> Option<Vec<u8>> {
+    let object: Cbor = ciborium::de::from_reader(attestation_object).ok()?;
+    let map = object.as_map()?;
+    let get = |name: &str| map.iter().find(|(k, _)| k.as_text() == Some(name)).map(|(_, v)| v);
+    // Without attestation the authenticator's make and model are not checked.
+    if get("fmt")?.as_text()? != "none" {
+        return None;
+    }
+    get("authData")?.as_bytes().cloned()
+}
+
+#[async_trait]
+impl<C: CredentialRepository + Send + Sync> WebauthnService for WebauthnServiceImpl<C> {
+    async fn registration_options(&self, username: &str) -> Result<CreationOptionsDto, ApiError> {
+        let user = self
+            .credential_repository
+            .find_user_by_username(username)
+            .await
+            .ok_or(ApiError::NotFound)?;
+        let existing = self.credential_repository.list_webauthn_credentials(&user.id).await;
+        Ok(CreationOptionsDto {
+            challenge: self.start(Ceremony::Registration, Some(user.id.clone())),
+            rp: RelyingPartyDto {
+                id: self.rp_id.clone(),
+                name: self.rp_name.clone(),
+            },
+            user: WebauthnUserDto {
+                id: b64(user.id.as_bytes()),
+                name: user.username.clone(),
+                display_name: user.display_name.unwrap_or(user.username),
+            },
+            pub_key_cred_params: vec![PubKeyCredParamDto {
+                credential_type: PUBLIC_KEY_TYPE.to_string(),
+                alg: COSE_ES256,
+            }],
+            timeout: CEREMONY_TTL_SECONDS as u64 * 1000,
+            attestation: "none".to_string(),
+            exclude_credentials: Self::descriptors(existing),
+            authenticator_selection: AuthenticatorSelectionDto {
+                resident_key: "preferred".to_string(),
+                user_verification: "preferred".to_string(),
+            },
+        })
+    }
+
+    async fn register(
+        &self,
+        username: &str,
+        credential: RegistrationCredentialDto,
+    ) -> Result<WebauthnCredential, ApiError> {
+        let failed = || ApiError::ErrorCode(ErrorCodes::WebauthnRegistrationFailed);
+        let user = self
+            .credential_repository
+            .find_user_by_username(username)
+            .await
+            .ok_or(ApiError::NotFound)?;
+        if credential.credential_type != PUBLIC_KEY_TYPE {
+            return Err(failed());
+        }
+        let client_data = unb64(&credential.response.client_data_json).ok_or_else(failed)?;
+        let pending = self.finish(&client_data, Ceremony::Registration).ok_or_else(failed)?;
+        if pending.user_id.as_deref() != Some(user.id.as_str()) {
+            return Err(failed());
+        }
+        let attestation = unb64(&credential.response.attestation_object).ok_or_else(failed)?;
+        let auth_data = attestation_auth_data(&attestation).ok_or_else(failed)?;
+        let parsed = self.parse_authenticator_data(&auth_data).ok_or_else(failed)?;
+        let (id, public_key) = parsed.credential.ok_or_else(failed)?;
+        let id = b64(id);
+        if id != credential.id || self.credential_repository.find_webauthn_credential(&id).await.is_some() {
+            return Err(failed());
+        }
+        let stored = WebauthnCredential {
+            id,
+            user_id: user.id,
+            public_key,
+            sign_count: parsed.sign_count,
+            created: Utc::now(),
+        };
+        self.credential_repository
+            .save_webauthn_credential(stored.clone())
+            .await;
+        Ok(stored)
+    }
+
+    async fn login_options(&self, username: Option<&str>) -> RequestOptionsDto {
+        let user = match username {
+            Some(username) => self.credential_repository.find_user_by_username(username).await,
+            None => None,
+        };
+        let allow_credentials = match &user {
+            Some(user) => Self::descriptors(self.credential_repository.list_webauthn_credentials(&user.id).await),
+            None => Vec::new(),
+        };
+        RequestOptionsDto {
+            // Unknown usernames get a challenge with no credentials, like a
+            // discoverable login. The list does show which accounts have passkeys.
+            challenge: self.start(Ceremony::Authentication, user.map(|u| u.id)),
+            rp_id: self.rp_id.clone(),
+            timeout: CEREMONY_TTL_SECONDS as u64 * 1000,
+            allow_credentials,
+            user_verification: "preferred".to_string(),
+        }
+    }
+
+    async fn authenticate(&self, assertion: &WebauthnAssertion) -> Result<String, ApiError> {
+        let unauthorized = || ApiError::Unauthorized;
+        let mut credential = self
+            .credential_repository
+            .find_webauthn_credential(&assertion.credential_id)
+            .await
+            .ok_or_else(unauthorized)?;
+        let user = self
+            .credential_repository
+            .find_user(&credential.user_id)
+            .await
+            .filter(|u| u.active)
+            .ok_or_else(unauthorized)?;
+        if let Some(handle) = &assertion.user_handle {
+            if unb64(handle).as_deref() != Some(user.id.as_bytes()) {
+                return Err(unauthorized());
+            }
+        }
+
+        let client_data = unb64(&assertion.client_data_json).ok_or_else(unauthorized)?;
+        let pending = self
+            .finish(&client_data, Ceremony::Authentication)
+            .ok_or_else(unauthorized)?;
+        if pending.user_id.as_ref().is_some_and(|id| *id != user.id) {
+            return Err(unauthorized());
+        }
+        let auth_data = unb64(&assertion.authenticator_data).ok_or_else(unauthorized)?;
+        let parsed = self.parse_authenticator_data(&auth_data).ok_or_else(unauthorized)?;
+
+        let key = VerifyingKey::from_sec1_bytes(&credential.public_key).map_err(|_| unauthorized())?;
+        let signature = unb64(&assertion.signature)
+            .and_then(|der| Signature::from_der(&der).ok())
+            .ok_or_else(unauthorized)?;
+        let mut signed = auth_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data));
+        key.verify(&signed, &signature).map_err(|_| unauthorized())?;
+
+        // A counter that does not move forward points to a cloned authenticator.
+        if (parsed.sign_count != 0 || credential.sign_count != 0) && parsed.sign_count <= credential.sign_count {
+            return Err(unauthorized());
+        }
+        credential.sign_count = parsed.sign_count;
+        self.credential_repository
+            .save_webauthn_credential(credential)
+            .await;
+        Ok(user.username)
+    }
+}
@@ -1,12 +1,36 @@
-use crate::models::error_response::{ErrorResponse, ValidationProblem};
+use crate::models::account_model::{ForgotPasswordDto, ResetPasswordDto, VerifyEmailDto};
+use crate::models::device_model::{
+    DeviceAuthorizationRequestDto, DeviceAuthorizationResponseDto, DeviceVerificationDto,
+};
+use crate::models::error_response::{ErrorResponse, OAuthErrorResponse, ValidationProblem};
+use crate::models::federation_model::LinkRedirectDto;
 use crate::models::message_model::{CreateMessageModelDto, MessageResponseDto};
+use crate::models::audit_model::{AuditAction, AuditEntry, AuditOutcome, AuditVerificationDto};
+use crate::models::oidc_model::{
+    ConfirmationDto, DiscoveryDocumentDto, IntrospectionResponseDto, JwkDto, JwksDto, TokenRequestDto,
+};
+use crate::models::scim_model::{
+    ScimAttributeDto, ScimAuthenticationSchemeDto, ScimEmailDto, ScimFilterSupportDto, ScimGroupDto,
+    ScimGroupListDto, ScimMetaDto, ScimNameDto, ScimPatchOperationDto, ScimPatchRequestDto,
+    ScimReferenceDto, ScimSchemaDto, ScimSchemaListDto, ScimSupportedDto, ScimUserDto, ScimUserListDto,
+    ServiceProviderConfigDto,
+};
+use crate::models::webauthn_model::{
+    AttestationResponseDto, AuthenticatorSelectionDto, CreationOptionsDto, CredentialDescriptorDto,
+    LoginOptionsRequestDto, PubKeyCredParamDto, RegisteredCredentialDto, RegistrationCredentialDto,
+    RelyingPartyDto, RequestOptionsDto, WebauthnUserDto,
+};
+use crate::middleware::validator::{Keyword, Validate};
+use crate::models::session_model::{LoginResponseDto, RevokedSessionsDto, SessionResponseDto};
 use crate::models::{auth_request::AuthRequestDto, token_model::TokenResponseDto};
 use utoipa::{
     openapi::{
         self,
+        schema::{Array, ArrayItems, Object, Schema, SchemaFormat, SchemaType, Type},
         security::{ApiKey, ApiKeyValue, SecurityScheme},
+        RefOr, Response as ApiResponse,
     },
-    Modify, OpenApi,
+    Modify, Number, OpenApi,
 };
 
 use std::str::FromStr;
@@ -26,7 +50,49 @@ use warp::{
         crate::controllers::base_controller::handle_create_message,
         crate::controllers::base_controller::handle_search_messages,
         crate::controllers::auth_controller::generate_token,
+        crate::controllers::auth_controller::login,
+        crate::controllers::auth_controller::logout,
+        crate::controllers::account_controller::forgot_password,
+        crate::controllers::account_controller::reset_password,
+        crate::controllers::account_controller::verify_email,
+        crate::controllers::webauthn_controller::registration_options,
+        crate::controllers::webauthn_controller::register,
+        crate::controllers::webauthn_controller::login_options,
+        crate::controllers::device_controller::authorize,
+        crate::controllers::device_controller::verification_page,
+        crate::controllers::device_controller::verify,
+        crate::controllers::federation_controller::login,
+        crate::controllers::federation_controller::link,
+        crate::controllers::federation_controller::callback,
         crate::controllers::protected_controller::protected_endpoint,
+        crate::controllers::protected_controller::signed_echo,
+        crate::controllers::session_controller::list_sessions,
+        crate::controllers::session_controller::revoke_session,
+        crate::controllers::session_controller::logout_all,
+        crate::controllers::session_controller::admin_list_sessions,
+        crate::controllers::session_controller::admin_revoke_session,
+        crate::controllers::session_controller::admin_logout_all,
+        crate::controllers::audit_controller::query_audit_log,
+        crate::controllers::audit_controller::verify_audit_log,
+        crate::controllers::oidc_controller::openid_configuration,
+        crate::controllers::oidc_controller::jwks,
+        crate::controllers::oidc_controller::revoke_token,
+        crate::controllers::oidc_controller::introspect_token,
+        crate::controllers::oidc_controller::rotate_keys,
+        crate::controllers::scim_controller::service_provider_config,
+        crate::controllers::scim_controller::schemas,
+        crate::controllers::scim_controller::list_users,
+        crate::controllers::scim_controller::get_user,
+        crate::controllers::scim_controller::create_user,
+        crate::controllers::scim_controller::replace_user,
+        crate::controllers::scim_controller::patch_user,
+        crate::controllers::scim_controller::delete_user,
+        crate::controllers::scim_controller::list_groups,
+        crate::controllers::scim_controller::get_group,
+        crate::controllers::scim_controller::create_group,
+        crate::controllers::scim_controller::replace_group,
+        crate::controllers::scim_controller::patch_group,
+        crate::controllers::scim_controller::delete_group,
     ),
     info(
         title = "Rust Base Backend API ",
@@ -39,11 +105,60 @@ use warp::{
             MessageResponseDto,
             AuthRequestDto,
             TokenResponseDto,
+            SessionResponseDto,
+            LoginResponseDto,
+            RevokedSessionsDto,
+            ForgotPasswordDto,
+            ResetPasswordDto,
+            VerifyEmailDto,
+            CreationOptionsDto,
+            RelyingPartyDto,
+            WebauthnUserDto,
+            PubKeyCredParamDto,
+            CredentialDescriptorDto,
+            AuthenticatorSelectionDto,
+            RequestOptionsDto,
+            LoginOptionsRequestDto,
+            RegistrationCredentialDto,
+            AttestationResponseDto,
+            RegisteredCredentialDto,
+            DeviceAuthorizationRequestDto,
+            DeviceAuthorizationResponseDto,
+            DeviceVerificationDto,
+            AuditEntry,
+            AuditAction,
+            AuditOutcome,
+            AuditVerificationDto,
+            DiscoveryDocumentDto,
+            JwkDto,
+            JwksDto,
+            TokenRequestDto,
+            IntrospectionResponseDto,
+            ConfirmationDto,
+            LinkRedirectDto,
+            ScimUserDto,
+            ScimGroupDto,
+            ScimNameDto,
+            ScimEmailDto,
+            ScimReferenceDto,
+            ScimMetaDto,
+            ScimUserListDto,
+            ScimGroupListDto,
+            ScimPatchRequestDto,
+            ScimPatchOperationDto,
+            ServiceProviderConfigDto,
+            ScimSupportedDto,
+            ScimFilterSupportDto,
+            ScimAuthenticationSchemeDto,
+            ScimSchemaDto,
+            ScimAttributeDto,
+            ScimSchemaListDto,
             ErrorResponse,
+            OAuthErrorResponse,
             ValidationProblem
         )
     ),
-    modifiers(&SecurityAddon)
+    modifiers(&SecurityAddon, &ValidationAddon)
 )]
 pub struct ApiDoc;
 
@@ -56,6 +171,140 @@ impl Modify for SecurityAddon {
             "api_key",
             SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("Authorization"))),
         );
+        components.add_security_scheme(
+            "hmac_signature",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "Authorization",
+                "HMAC-SHA256 Credential=<client_id>, SignedHeaders=<names>, Signature=<hex>, \
+                 with X-Signature-Timestamp and X-Signature-Nonce headers",
+            ))),
+        );
+    }
+}
+
+/// Writes the `#[validate(...)]` rules of the request DTOs into their schemas
+/// as JSON Schema keywords, and lists the error codes they can report on the
+/// 400 response of every operation that takes them.
+struct ValidationAddon;
+
+impl Modify for ValidationAddon {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let rules = [
+            ("CreateMessageModelDto", CreateMessageModelDto::schema()),
+            ("ForgotPasswordDto", ForgotPasswordDto::schema()),
+            ("ResetPasswordDto", ResetPasswordDto::schema()),
+            ("VerifyEmailDto", VerifyEmailDto::schema()),
+        ];
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        for (name, rules) in &rules {
+            let Some(RefOr::T(Schema::Object(object))) = components.schemas.get_mut(*name) else {
+                continue;
+            };
+            for field in &rules.fields {
+                if field.keywords.contains(&Keyword::Required) && !object.required.contains(&field.field) {
+                    object.required.push(field.field.clone());
+                }
+                if let Some(RefOr::T(property)) = object.properties.get_mut(&field.field) {
+                    apply_keywords(property, &field.keywords);
+                    if let Schema::Array(Array { items: ArrayItems::RefOrSchema(items), .. }) = property {
+                        if let RefOr::T(items) = items.as_mut() {
+                            apply_keywords(items, &field.items);
+                        }
+                    }
+                }
+            }
+        }
+
+        for path in openapi.paths.paths.values_mut() {
+            let operations = [&mut path.get, &mut path.put, &mut path.post, &mut path.patch, &mut path.delete];
+            for operation in operations.into_iter().flatten() {
+                let body = operation
+                    .request_body
+                    .as_ref()
+                    .and_then(|body| body.content.get("application/json"))
+                    .and_then(|content| content.schema.as_ref());
+                let Some(RefOr::Ref(reference)) = body else {
+                    continue;
+                };
+                let name = reference.ref_location.rsplit('/').next().unwrap_or_default();
+                let Some((_, rules)) = rules.iter().find(|(dto, _)| *dto == name) else {
+                    continue;
+                };
+                if rules.codes.is_empty() {
+                    continue;
+                }
+                let codes: Vec<String> = rules.codes.iter().map(|code| format!("{} {:?}", code.clone() as u16, code)).collect();
+                let response = operation
+                    .responses
+                    .responses
+                    .entry("400".to_string())
+                    .or_insert_with(|| RefOr::T(ApiResponse::new("Bad request")));
+                if let RefOr::T(response) = response {
+                    response.description = format!("{}. Error codes: {}", response.description, codes.join(", "));
+                    let codes = rules.codes.iter().map(|code| code.clone() as u16).collect::<Vec<_>>();
+                    response
+                        .extensions
+                        .get_or_insert_with(Default::default)
+                        .insert("x-error-codes".to_string(), serde_json::json!(codes));
+                }
+            }
+        }
+    }
+}
+
+fn apply_keywords(schema: &mut Schema, keywords: &[Keyword]) {
+    match schema {
+        Schema::Object(object) => keywords.iter().for_each(|keyword| apply_to_object(object, keyword)),
+        Schema::Array(array) => {
+            for keyword in keywords {
+                match keyword {
+                    Keyword::Required => array.schema_type = without_null(array.schema_type.clone()),
+                    Keyword::MinLength(min) => array.min_items = Some(array.min_items.map_or(*min, |m| m.max(*min))),
+                    Keyword::MaxLength(max) => array.max_items = Some(array.max_items.map_or(*max, |m| m.min(*max))),
+                    Keyword::UniqueItems => array.unique_items = true,
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn apply_to_object(object: &mut Object, keyword: &Keyword) {
+    match keyword {
+        Keyword::Required => object.schema_type = without_null(object.schema_type.clone()),
+        Keyword::MinLength(min) => object.min_length = Some(object.min_length.map_or(*min, |m| m.max(*min))),
+        Keyword::MaxLength(max) => object.max_length = Some(object.max_length.map_or(*max, |m| m.min(*max))),
+        Keyword::Minimum(min) => object.minimum = Some(number(*min)),
+        Keyword::Maximum(max) => object.maximum = Some(number(*max)),
+        Keyword::Pattern(pattern) => object.pattern = Some(pattern.clone()),
+        Keyword::Format(format) => object.format = Some(SchemaFormat::Custom(format.to_string())),
+        Keyword::Enum(allowed) => object.enum_values = Some(allowed.iter().map(|a| serde_json::json!(a)).collect()),
+        Keyword::UniqueItems => {}
+    }
+}
+
+/// A `not_null` field cannot be `null`, even when its Rust type is an `Option`.
+fn without_null(schema_type: SchemaType) -> SchemaType {
+    match schema_type {
+        SchemaType::Array(types) => {
+            let mut types: Vec<Type> = types.into_iter().filter(|t| *t != Type::Null).collect();
+            if types.len() == 1 {
+                SchemaType::Type(types.remove(0))
+            } else {
+                SchemaType::Array(types)
+            }
+        }
+        other => other,
+    }
+}
+
+fn number(value: f64) -> Number {
+    if value.fract() == 0.0 && value.abs() < isize::MAX as f64 {
+        Number::Int(value as isize)
+    } else {
+        Number::Float(value)
     }
 }
 
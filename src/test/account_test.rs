@@ -0,0 +1,227 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use warp::Filter;
+
+use crate::config::Config;
+use crate::controllers::{routes, routes_with_mailer};
+use crate::errors::ApiError;
+use crate::models::mail_model::MailMessage;
+use crate::services::mail_service::Mailer;
+use crate::test::support::{body, handle_rejection, path, post};
+
+/// Keeps sent mail in memory so tests can read the codes in it.
+#[derive(Clone, Default)]
+pub struct MemoryOutbox {
+    messages: Arc<Mutex<Vec<MailMessage>>>,
+}
+
+impl MemoryOutbox {
+    pub fn messages(&self) -> Vec<MailMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// Waits for mail sent in the background until `count` messages arrived.
+    pub async fn delivered(&self, count: usize) -> Vec<MailMessage> {
+        for _ in 0..100 {
+            let messages = self.messages();
+            if messages.len() >= count {
+                return messages;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        self.messages()
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryOutbox {
+    async fn send(&self, message: MailMessage) -> Result<(), ApiError> {
+        self.messages.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+fn account_config(email_verification: bool) -> (Arc<Config>, MemoryOutbox) {
+    dotenv::dotenv().ok();
+    let config = Arc::new(Config {
+        smtp_host: None,
+        email_verification,
+        scim_client_secret: Some("scim secret".to_string()),
+        ..Config::from_env()
+    });
+    (config, MemoryOutbox::default())
+}
+
+fn code(message: &MailMessage, label: &str) -> String {
+    message
+        .body
+        .lines()
+        .find_map(|line| line.strip_prefix(label))
+        .unwrap()
+        .trim()
+        .to_string()
+}
+
+fn filter(
+    config: &Arc<Config>,
+    outbox: &MemoryOutbox,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static {
+    use warp::Reply;
+    routes_with_mailer(Arc::clone(config), Arc::new(outbox.clone()))
+        .unwrap()
+        .recover(handle_rejection)
+        .map(Reply::into_response)
+}
+
+async fn create_user<F>(filter: &F, config: &Config, username: &str, email: &str, password: &str)
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = post(
+        filter,
+        &path(config, "auth/token"),
+        None,
+        json!({ "grant_type": "client", "client_id": "scim", "client_secret": "scim secret", "scope": "scim" }),
+    )
+    .await;
+    let bearer = format!("Bearer {}", body(&res)["token"].as_str().unwrap());
+    let res = post(
+        filter,
+        "/scim/v2/Users",
+        Some(&bearer),
+        json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": username,
+            "emails": [{ "value": email, "primary": true }],
+            "password": password
+        }),
+    )
+    .await;
+    assert_eq!(res.status(), 201);
+}
+
+async fn login<F>(filter: &F, config: &Config, username: &str, password: &str) -> warp::http::Response<warp::hyper::body::Bytes>
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    post(
+        filter,
+        &path(config, "auth/token"),
+        None,
+        json!({ "grant_type": "user", "username": username, "password": password }),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn mail_settings_are_checked_at_startup() {
+    use warp::Reply;
+    dotenv::dotenv().ok();
+    let config = Arc::new(Config {
+        smtp_host: Some("localhost".to_string()),
+        mail_from: "not an address".to_string(),
+        ..Config::from_env()
+    });
+    let errors = routes(config).err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("invalid SMTP configuration: invalid sender address"));
+
+    // Without SMTP_HOST mail is only logged, and the flow still answers.
+    let (config, _) = account_config(false);
+    let filter = routes(Arc::clone(&config))
+        .unwrap()
+        .recover(handle_rejection)
+        .map(Reply::into_response);
+    create_user(&filter, &config, "carol", "carol@example.com", "correct horse battery").await;
+    let res = post(&filter, &path(&config, "auth/password/forgot"), None, json!({ "email": "carol@example.com" })).await;
+    assert_eq!(res.status(), 202);
+}
+
+#[tokio::test]
+async fn password_reset_flow() {
+    let (config, outbox) = account_config(false);
+    let filter = filter(&config, &outbox);
+    create_user(&filter, &config, "alice", "alice@example.com", "old-password").await;
+    let res = login(&filter, &config, "alice", "old-password").await;
+    assert_eq!(res.status(), 200);
+    let session = format!("Bearer {}", body(&res)["token"].as_str().unwrap());
+
+    let res = post(&filter, &path(&config, "auth/password/forgot"), None, json!({ "email": "nobody@example.com" })).await;
+    assert_eq!(res.status(), 202);
+    assert!(outbox.messages().is_empty());
+
+    let res = post(&filter, &path(&config, "auth/password/forgot"), None, json!({ "email": "ALICE@example.com" })).await;
+    assert_eq!(res.status(), 202);
+    let mails = outbox.delivered(1).await;
+    assert_eq!(mails.len(), 1);
+    assert_eq!(mails[0].to, "ALICE@example.com");
+    let token = code(&mails[0], "Reset code:");
+
+    let res = post(&filter, &path(&config, "auth/password/reset"), None, json!({ "token": "bogus", "password": "x" })).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2001);
+
+    let res = post(
+        &filter,
+        &path(&config, "auth/password/reset"),
+        None,
+        json!({ "token": token, "password": "new-password" }),
+    )
+    .await;
+    assert_eq!(res.status(), 204);
+
+    let res = post(
+        &filter,
+        &path(&config, "auth/password/reset"),
+        None,
+        json!({ "token": token, "password": "another" }),
+    )
+    .await;
+    assert_eq!(res.status(), 400);
+
+    assert_eq!(login(&filter, &config, "alice", "old-password").await.status(), 401);
+    assert_eq!(login(&filter, &config, "alice", "new-password").await.status(), 200);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&path(&config, "protected"))
+        .header("Authorization", &session)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn reset_requires_token_and_password() {
+    let (config, outbox) = account_config(false);
+    let filter = filter(&config, &outbox);
+    let res = post(&filter, &path(&config, "auth/password/reset"), None, json!({ "token": "abc" })).await;
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn unverified_users_cannot_log_in() {
+    let (config, outbox) = account_config(true);
+    let filter = filter(&config, &outbox);
+    create_user(&filter, &config, "bob", "bob@example.com", "secret-password").await;
+
+    let res = login(&filter, &config, "bob", "secret-password").await;
+    assert_eq!(res.status(), 403);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2003);
+
+    let mails = outbox.messages();
+    assert_eq!(mails.len(), 1);
+    assert_eq!(mails[0].to, "bob@example.com");
+    let token = code(&mails[0], "Verification code:");
+
+    let res = post(&filter, &path(&config, "auth/email/verify"), None, json!({ "token": token })).await;
+    assert_eq!(res.status(), 204);
+    let res = post(&filter, &path(&config, "auth/email/verify"), None, json!({ "token": token })).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2002);
+
+    assert_eq!(login(&filter, &config, "bob", "secret-password").await.status(), 200);
+    assert_eq!(login(&filter, &config, "admin", "password").await.status(), 200);
+}
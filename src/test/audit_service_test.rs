@@ -0,0 +1,111 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+
+use crate::models::audit_model::{AuditAction, AuditEvent, AuditOutcome, AuditQuery};
+use crate::repositories::audit_repository::{
+    AuditRepository, InMemoryAuditRepository, JsonlAuditRepository,
+};
+use crate::services::audit_service::{verify_chain, AuditService, AuditServiceImpl, GENESIS_HASH};
+
+fn event(action: AuditAction, outcome: AuditOutcome, actor: &str) -> AuditEvent {
+    AuditEvent {
+        action,
+        outcome,
+        actor: Some(actor.to_string()),
+        client_ip: Some("127.0.0.1".to_string()),
+        target: None,
+    }
+}
+
+#[tokio::test]
+async fn entries_are_chained() {
+    let service = AuditServiceImpl::new(InMemoryAuditRepository::new());
+    let first = service
+        .record(event(AuditAction::TokenIssued, AuditOutcome::Success, "admin"))
+        .await;
+    let second = service
+        .record(event(AuditAction::LoginFailed, AuditOutcome::Failure, "mallory"))
+        .await;
+
+    assert_eq!(first.sequence, 1);
+    assert_eq!(first.previous_hash, GENESIS_HASH);
+    assert_eq!(second.sequence, 2);
+    assert_eq!(second.previous_hash, first.hash);
+    assert_eq!(service.verify().await, (true, 2));
+}
+
+#[tokio::test]
+async fn tampering_breaks_the_chain() {
+    let repository = InMemoryAuditRepository::new();
+    let service = AuditServiceImpl::new(repository);
+    service
+        .record(event(AuditAction::TokenIssued, AuditOutcome::Success, "admin"))
+        .await;
+    service
+        .record(event(AuditAction::SessionRevoked, AuditOutcome::Success, "admin"))
+        .await;
+
+    let mut entries = service.query(&AuditQuery::default()).await;
+    assert!(verify_chain(&entries));
+    entries[0].actor = Some("someone-else".to_string());
+    assert!(!verify_chain(&entries));
+}
+
+#[tokio::test]
+async fn query_filters_entries() {
+    let service = AuditServiceImpl::new(InMemoryAuditRepository::new());
+    service
+        .record(event(AuditAction::TokenIssued, AuditOutcome::Success, "admin"))
+        .await;
+    service
+        .record(event(AuditAction::LoginFailed, AuditOutcome::Failure, "admin"))
+        .await;
+    service
+        .record(event(AuditAction::TokenIssued, AuditOutcome::Success, "client"))
+        .await;
+
+    let failures = service
+        .query(&AuditQuery {
+            outcome: Some(AuditOutcome::Failure),
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(failures.len(), 1);
+
+    let admin_tokens = service
+        .query(&AuditQuery {
+            action: Some(AuditAction::TokenIssued),
+            actor: Some("admin".to_string()),
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(admin_tokens.len(), 1);
+
+    let limited = service
+        .query(&AuditQuery {
+            limit: Some(2),
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(limited.len(), 2);
+}
+
+#[tokio::test]
+async fn jsonl_sink_persists_and_resumes_chain() {
+    let path = std::env::temp_dir().join(format!("audit-{}.jsonl", rand::random::<u64>()));
+
+    let service = AuditServiceImpl::new(JsonlAuditRepository::new(&path));
+    service
+        .record(event(AuditAction::TokenIssued, AuditOutcome::Success, "admin"))
+        .await;
+
+    let reopened = AuditServiceImpl::new(JsonlAuditRepository::new(&path));
+    let entry = reopened
+        .record(event(AuditAction::SessionsRevokedAll, AuditOutcome::Success, "admin"))
+        .await;
+    assert_eq!(entry.sequence, 2);
+    assert_eq!(reopened.verify().await, (true, 2));
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents.lines().count(), 2);
+    let _ = std::fs::remove_file(&path);
+}
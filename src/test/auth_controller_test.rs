@@ -4,11 +4,19 @@ use warp::Reply;
 
 use crate::controllers::auth_controller::generate_token;
 use crate::models::auth_request::AuthRequestDto;
+use crate::models::audit_model::{AuditAction, AuditOutcome, AuditQuery};
+use crate::models::session_model::ClientContext;
 use crate::repositories::{
-    credentials_repository::InMemoryCredentialRepository, token_repository::InMemoryTokenRepository,
+    audit_repository::InMemoryAuditRepository, credentials_repository::InMemoryCredentialRepository,
+    token_repository::InMemoryTokenRepository,
 };
+use crate::services::audit_service::{AuditService, AuditServiceImpl};
 use crate::services::auth_service::{AuthService, AuthServiceImpl};
 
+fn audit() -> Arc<dyn AuditService> {
+    Arc::new(AuditServiceImpl::new(InMemoryAuditRepository::new()))
+}
+
 #[tokio::test]
 async fn handler_generate_token() {
     let token_repo = InMemoryTokenRepository::new();
@@ -17,12 +25,18 @@ async fn handler_generate_token() {
     let request = AuthRequestDto::Client {
         client_id: "client".to_string(),
         client_secret: "secret".to_string(),
+        scope: None,
     };
-    let reply = generate_token(Arc::new(service), request)
+    let audit = audit();
+    let reply = generate_token(Arc::new(service), Arc::clone(&audit), ClientContext::default(), request)
         .await
         .unwrap()
         .into_response();
     assert_eq!(reply.status(), 200);
+    let entries = audit.query(&AuditQuery::default()).await;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, AuditAction::TokenIssued);
+    assert_eq!(entries[0].actor.as_deref(), Some("client"));
 }
 
 #[tokio::test]
@@ -33,7 +47,12 @@ async fn handler_generate_token_invalid_credentials() {
     let request = AuthRequestDto::Client {
         client_id: "client".to_string(),
         client_secret: "wrong".to_string(),
+        scope: None,
     };
-    let result = generate_token(Arc::new(service), request).await;
+    let audit = audit();
+    let result = generate_token(Arc::new(service), Arc::clone(&audit), ClientContext::default(), request).await;
     assert!(result.is_err());
+    let entries = audit.query(&AuditQuery::default()).await;
+    assert_eq!(entries[0].action, AuditAction::LoginFailed);
+    assert_eq!(entries[0].outcome, AuditOutcome::Failure);
 }
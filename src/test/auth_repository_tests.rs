@@ -3,20 +3,57 @@
 use crate::repositories::credentials_repository::{
     CredentialRepository, InMemoryCredentialRepository,
 };
+use crate::models::session_model::SessionModel;
 use crate::repositories::token_repository::{InMemoryTokenRepository, TokenRepository};
 use chrono::{Duration, Utc};
 use hex;
 use sha2::{Digest, Sha256};
 
+fn session(id: &str, subject: &str) -> SessionModel {
+    SessionModel {
+        id: id.to_string(),
+        subject: subject.to_string(),
+        grant_type: "user".to_string(),
+        issued_at: Utc::now(),
+        expires_at: Utc::now() + Duration::minutes(5),
+        user_agent: Some("test-agent".to_string()),
+        client_ip: Some("127.0.0.1".to_string()),
+        csrf_hash: None,
+        scope: None,
+        jkt: None,
+    }
+}
+
 #[tokio::test]
 async fn store_and_validate_token() {
     let repo = InMemoryTokenRepository::new();
     let token = "test";
     let hashed = Sha256::digest(token.as_bytes());
     let hashed_hex = hex::encode(hashed);
-    repo.store_token(hashed_hex.clone(), Utc::now() + Duration::minutes(5))
+    repo.store_token(hashed_hex.clone(), session("s1", "admin"))
         .await;
-    assert!(repo.is_valid(&hashed_hex).await);
+    assert!(repo.find_session(&hashed_hex).await.is_some());
+}
+
+#[tokio::test]
+async fn list_and_revoke_sessions() {
+    let repo = InMemoryTokenRepository::new();
+    repo.store_token("h1".to_string(), session("s1", "admin")).await;
+    repo.store_token("h2".to_string(), session("s2", "admin")).await;
+    repo.store_token("h3".to_string(), session("s3", "other")).await;
+
+    let sessions = repo.list_sessions("admin", "user").await;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].user_agent.as_deref(), Some("test-agent"));
+
+    assert!(!repo.revoke_session("other", "user", "s1").await);
+    assert!(repo.revoke_session("admin", "user", "s1").await);
+    assert!(repo.find_session("h1").await.is_none());
+    assert!(repo.find_session("h2").await.is_some());
+
+    assert_eq!(repo.revoke_all("admin", "user").await, 1);
+    assert!(repo.find_session("h2").await.is_none());
+    assert!(repo.find_session("h3").await.is_some());
 }
 
 #[tokio::test]
@@ -1,6 +1,7 @@
 #![allow(dead_code, unused_imports, unused_variables)]
 
 use crate::models::auth_request::AuthRequestDto;
+use crate::models::session_model::ClientContext;
 use crate::repositories::credentials_repository::InMemoryCredentialRepository;
 use crate::repositories::token_repository::InMemoryTokenRepository;
 use crate::services::auth_service::{AuthService, AuthServiceImpl};
@@ -13,9 +14,10 @@ async fn generate_and_validate_token() {
     let request = AuthRequestDto::User {
         username: "admin".to_string(),
         password: "password".to_string(),
+        scope: None,
     };
-    let token = service.generate_token(request).await.unwrap().token;
-    assert!(service.validate_token(&token).await);
+    let token = service.generate_token(request, ClientContext::default()).await.unwrap().token;
+    assert!(service.authenticate(&token).await.is_some());
 }
 
 #[tokio::test]
@@ -26,6 +28,102 @@ async fn reject_invalid_credentials() {
     let request = AuthRequestDto::User {
         username: "admin".to_string(),
         password: "wrong".to_string(),
+        scope: None,
     };
-    assert!(service.generate_token(request).await.is_err());
+    assert!(service.generate_token(request, ClientContext::default()).await.is_err());
+}
+
+#[tokio::test]
+async fn authenticate_and_revoke_sessions() {
+    let token_repo = InMemoryTokenRepository::new();
+    let cred_repo = InMemoryCredentialRepository::new();
+    let service = AuthServiceImpl::new(token_repo, cred_repo);
+    let context = ClientContext {
+        user_agent: Some("test-agent".to_string()),
+        client_ip: Some("127.0.0.1".to_string()),
+        dpop_jkt: None,
+    };
+    let user = || AuthRequestDto::User {
+        username: "admin".to_string(),
+        password: "password".to_string(),
+        scope: None,
+    };
+    let first = service.generate_token(user(), context.clone()).await.unwrap().token;
+    let second = service.generate_token(user(), context).await.unwrap().token;
+
+    let principal = service.authenticate(&first).await.unwrap();
+    assert_eq!(principal.subject, "admin");
+    assert_eq!(principal.grant_type, "user");
+    assert!(principal.is_admin);
+
+    let sessions = service.list_sessions("admin", "user").await;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].client_ip.as_deref(), Some("127.0.0.1"));
+
+    service
+        .revoke_session("admin", "user", &principal.session_id)
+        .await
+        .unwrap();
+    assert!(service.authenticate(&first).await.is_none());
+    assert!(service.authenticate(&second).await.is_some());
+    assert!(service
+        .revoke_session("admin", "user", &principal.session_id)
+        .await
+        .is_err());
+
+    assert_eq!(service.revoke_all_sessions("admin", "user").await, 1);
+    assert!(service.authenticate(&second).await.is_none());
+}
+
+#[tokio::test]
+async fn client_principal_is_not_admin() {
+    let token_repo = InMemoryTokenRepository::new();
+    let cred_repo = InMemoryCredentialRepository::new();
+    let service = AuthServiceImpl::new(token_repo, cred_repo);
+    let request = AuthRequestDto::Client {
+        client_id: "client".to_string(),
+        client_secret: "secret".to_string(),
+        scope: None,
+    };
+    let token = service
+        .generate_token(request, ClientContext::default())
+        .await
+        .unwrap()
+        .token;
+    let principal = service.authenticate(&token).await.unwrap();
+    assert_eq!(principal.grant_type, "client");
+    assert!(!principal.is_admin);
+}
+
+#[tokio::test]
+async fn browser_session_binds_csrf_token() {
+    let token_repo = InMemoryTokenRepository::new();
+    let cred_repo = InMemoryCredentialRepository::new();
+    let service = AuthServiceImpl::new(token_repo, cred_repo);
+    let request = AuthRequestDto::User {
+        username: "admin".to_string(),
+        password: "password".to_string(),
+        scope: None,
+    };
+    let session = service
+        .create_browser_session(request, ClientContext::default())
+        .await
+        .unwrap();
+    assert!(service.authenticate(&session.token).await.is_some());
+    assert!(service.validate_csrf(&session.token, &session.csrf_token).await);
+    assert!(!service.validate_csrf(&session.token, "forged").await);
+
+    let bearer = service
+        .generate_token(
+            AuthRequestDto::User {
+                username: "admin".to_string(),
+                password: "password".to_string(),
+                scope: None,
+            },
+            ClientContext::default(),
+        )
+        .await
+        .unwrap()
+        .token;
+    assert!(!service.validate_csrf(&bearer, &session.csrf_token).await);
 }
@@ -0,0 +1,121 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde::Deserialize;
+use serde_json::Value;
+use warp::Filter;
+
+use crate::config::BodyLimits;
+use crate::middleware::body::json_body;
+use crate::middleware::validator::{validated_json, Validate};
+use crate::test::support::handle_rejection;
+
+#[derive(Debug, Deserialize, Validate)]
+struct NoteDto {
+    #[validate(not_empty(code = NotEmpty))]
+    text: String,
+    tags: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrictNoteDto {
+    text: String,
+}
+
+const LIMITS: BodyLimits = BodyLimits {
+    max_bytes: 64,
+    max_depth: 3,
+};
+
+async fn post<F>(filter: &F, content_type: Option<&str>, body: &str) -> (u16, Value)
+where
+    F: Filter + 'static,
+    F::Extract: warp::Reply + Send,
+{
+    let mut request = warp::test::request().method("POST").path("/notes").body(body);
+    if let Some(content_type) = content_type {
+        request = request.header("content-type", content_type);
+    }
+    let res = request.reply(filter).await;
+    let body = serde_json::from_slice(res.body()).unwrap_or(Value::Null);
+    (res.status().as_u16(), body)
+}
+
+fn notes() -> impl Filter<Extract = (impl warp::Reply,), Error = std::convert::Infallible> + Clone {
+    warp::path("notes")
+        .and(validated_json::<NoteDto>(LIMITS))
+        .map(|note: NoteDto| note.text)
+        .recover(handle_rejection)
+}
+
+#[tokio::test]
+async fn body_must_be_json() {
+    let filter = notes();
+    let json = Some("application/json");
+    assert_eq!(post(&filter, json, r#"{"text":"hi"}"#).await.0, 200);
+    assert_eq!(post(&filter, Some("application/json; charset=utf-8"), r#"{"text":"hi"}"#).await.0, 200);
+    assert_eq!(post(&filter, Some("application/scim+json"), r#"{"text":"hi"}"#).await.0, 200);
+
+    for content_type in [None, Some("text/plain"), Some("application/x-www-form-urlencoded")] {
+        let (status, body) = post(&filter, content_type, r#"{"text":"hi"}"#).await;
+        assert_eq!(status, 415, "{:?}", content_type);
+        assert_eq!(body["details"][0]["error_code"], 1024);
+        assert_eq!(body["instance"], "notes");
+    }
+}
+
+#[tokio::test]
+async fn large_and_deep_bodies_are_refused() {
+    let filter = notes();
+    let json = Some("application/json");
+    let (status, body) = post(&filter, json, &format!(r#"{{"text":"{}"}}"#, "a".repeat(80))).await;
+    assert_eq!(status, 413);
+    assert_eq!(body["details"][0]["error_code"], 1023);
+    assert_eq!(body["details"][0]["message"], "The body exceeds 64 bytes");
+
+    assert_eq!(post(&filter, json, r#"{"text":"a","tags":[[1]]}"#).await.0, 200);
+    let (status, body) = post(&filter, json, r#"{"text":"a","tags":[[[1]]]}"#).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["details"][0]["error_code"], 1027);
+    // Brackets inside strings do not count.
+    assert_eq!(post(&filter, json, r#"{"text":"[[[{{\"]]"}"#).await.0, 200);
+}
+
+#[tokio::test]
+async fn parse_errors_point_at_line_and_column() {
+    let filter = notes();
+    let json = Some("application/json");
+    let (status, body) = post(&filter, json, "{\n  \"text\": \"hi\",\n  \"tags\": }").await;
+    assert_eq!(status, 400);
+    assert_eq!(body["details"][0]["error_code"], 1025);
+    assert_eq!(body["details"][0]["message"], "The body is not valid JSON at line 3, column 11");
+
+    let (status, body) = post(&filter, json, r#"{"text": 5}"#).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["details"][0]["error_code"], 1026);
+    assert_eq!(
+        body["details"][0]["message"],
+        "The body does not match the expected format at line 1, column 10: invalid type: integer `5`, expected a string"
+    );
+
+    // Validation still runs on bodies that parse.
+    let (status, body) = post(&filter, json, r#"{"text": ""}"#).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["details"][0]["field"], "/text");
+}
+
+#[tokio::test]
+async fn unknown_fields_are_refused_when_the_dto_denies_them() {
+    let json = Some("application/json");
+    let filter = notes();
+    assert_eq!(post(&filter, json, r#"{"text":"hi","mood":"ok"}"#).await.0, 200);
+
+    let strict = warp::path("notes")
+        .and(json_body::<StrictNoteDto>(LIMITS))
+        .map(|note: StrictNoteDto| note.text)
+        .recover(handle_rejection);
+    let (status, body) = post(&strict, json, r#"{"text":"hi","mood":"ok"}"#).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["details"][0]["error_code"], 1028);
+    assert_eq!(body["details"][0]["field"], "mood");
+    assert_eq!(body["details"][0]["message"], "mood is not a known field (line 1, column 19)");
+}
@@ -0,0 +1,190 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use warp::Filter;
+
+use crate::config::BodyLimits;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::middleware::validator::{validated_json, Keyword, Rule, Validate, Validator};
+use crate::test::support::handle_rejection;
+
+#[derive(Debug, Deserialize, Validate)]
+struct ClientDto {
+    #[validate(not_null(code = NotNull), one_of(&["client", "password"]))]
+    grant_type: Option<String>,
+    #[validate(when(Self::is_client), not_null(code = NotNull), not_empty(code = NotEmpty))]
+    client_secret: Option<String>,
+    #[validate(unless(Self::is_client), not_null(code = NotNull))]
+    password: Option<String>,
+    #[validate(equal_to_field(password))]
+    password_confirmation: Option<String>,
+}
+
+impl ClientDto {
+    fn is_client(&self) -> bool {
+        self.grant_type.as_deref() == Some("client")
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+struct BookingDto {
+    start_date: NaiveDate,
+    #[validate(after_field(start_date))]
+    end_date: Option<NaiveDate>,
+    #[validate(within_range(1, 10, code = MaxSize))]
+    min_guests: u32,
+    #[validate(at_least_field(min_guests))]
+    max_guests: u32,
+}
+
+type FieldErrors = Vec<(Option<String>, Vec<ErrorCodes>)>;
+
+fn failure(result: Result<(), ApiError>) -> FieldErrors {
+    match result {
+        Err(ApiError::FieldErrors(fields, _)) => fields
+            .into_iter()
+            .map(|(field, failures)| (field, failures.into_iter().map(|f| f.code).collect()))
+            .collect(),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+fn field(name: &str, codes: Vec<ErrorCodes>) -> (Option<String>, Vec<ErrorCodes>) {
+    (Some(name.to_string()), codes)
+}
+
+fn client(grant_type: &str, secret: Option<&str>, password: Option<&str>) -> ClientDto {
+    ClientDto {
+        grant_type: Some(grant_type.to_string()),
+        client_secret: secret.map(str::to_string),
+        password: password.map(str::to_string),
+        password_confirmation: password.map(str::to_string),
+    }
+}
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+}
+
+#[test]
+fn conditional_rules_only_apply_when_their_predicate_holds() {
+    assert!(client("client", Some("s3cret"), None).validate(None).is_ok());
+    assert!(client("password", None, Some("hunter2")).validate(None).is_ok());
+
+    assert_eq!(
+        failure(client("client", None, None).validate(None)),
+        vec![field("/client_secret", vec![ErrorCodes::NotNull])]
+    );
+    assert_eq!(
+        failure(client("client", Some(""), None).validate(None)),
+        vec![field("/client_secret", vec![ErrorCodes::NotEmpty])]
+    );
+    assert_eq!(
+        failure(client("password", None, None).validate(None)),
+        vec![field("/password", vec![ErrorCodes::NotNull])]
+    );
+}
+
+#[test]
+fn cross_field_rules_are_reported_on_the_checked_field() {
+    let mut dto = client("password", None, Some("hunter2"));
+    dto.password_confirmation = Some("hunter3".to_string());
+    assert_eq!(
+        failure(dto.validate(None)),
+        vec![field("/password_confirmation", vec![ErrorCodes::FieldMismatch])]
+    );
+
+    let booking = |end: Option<u32>, min: u32, max: u32| BookingDto {
+        start_date: date(10),
+        end_date: end.map(date),
+        min_guests: min,
+        max_guests: max,
+    };
+    assert!(booking(Some(12), 2, 2).validate(None).is_ok());
+    // A missing value has nothing to compare with.
+    assert!(booking(None, 2, 4).validate(None).is_ok());
+    assert_eq!(
+        failure(booking(Some(10), 3, 2).validate(None)),
+        vec![
+            field("/endDate", vec![ErrorCodes::NotAfterField]),
+            field("/maxGuests", vec![ErrorCodes::BelowField]),
+        ]
+    );
+}
+
+#[test]
+fn hand_written_rules_can_be_conditional_and_cross_field() {
+    let (min, max) = (5i64, 3i64);
+    let enabled = true;
+    let note = String::new();
+    let result = Validator::new(None)
+        .rule(Rule::new(Some(&max), Some("max".to_string()), None).greater_than_field(Some(&min), "min"))
+        .rule(
+            Rule::new(Some(&note), Some("note".to_string()), None)
+                .when(enabled)
+                .not_empty()
+                .with_error_code(ErrorCodes::NotEmpty),
+        )
+        .rule(
+            Rule::new(Some(&note), Some("reason".to_string()), None)
+                .unless(enabled)
+                .not_empty()
+                .with_error_code(ErrorCodes::NotEmpty),
+        )
+        .rule(
+            Rule::new(Some(&min), Some("limit".to_string()), None)
+                .different_from_field(Some(&5), "min")
+                .with_error_code(ErrorCodes::MaxSize),
+        )
+        .check();
+    assert_eq!(
+        failure(result),
+        vec![
+            field("/max", vec![ErrorCodes::NotGreaterThanField]),
+            field("/note", vec![ErrorCodes::NotEmpty]),
+            field("/limit", vec![ErrorCodes::MaxSize]),
+        ]
+    );
+
+    let nan = f64::NAN;
+    let rule = Rule::new(Some(&nan), Some("ratio".to_string()), None).at_most_field(Some(&1.0), "limit");
+    assert!(rule.check().is_err());
+}
+
+#[test]
+fn conditional_rules_add_codes_but_no_keywords_to_the_schema() {
+    let schema = ClientDto::schema();
+    let field = |name: &str| schema.fields.iter().find(|f| f.field == name).unwrap();
+    assert_eq!(
+        field("grant_type").keywords,
+        vec![Keyword::Required, Keyword::Enum(vec!["client".into(), "password".into()])]
+    );
+    assert!(field("client_secret").keywords.is_empty());
+    assert!(field("password").keywords.is_empty());
+    assert!(schema.codes.contains(&ErrorCodes::NotEmpty));
+    assert!(schema.codes.contains(&ErrorCodes::FieldMismatch));
+    assert!(BookingDto::schema().codes.contains(&ErrorCodes::NotAfterField));
+}
+
+#[tokio::test]
+async fn messages_name_the_other_field() {
+    let filter = warp::path("bookings")
+        .and(validated_json::<BookingDto>(BodyLimits::default()))
+        .map(|booking: BookingDto| booking.max_guests.to_string())
+        .recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/bookings")
+        .json(&json!({ "startDate": "2024-05-10", "endDate": "2024-05-09", "minGuests": 2, "maxGuests": 2 }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["field"], "/endDate");
+    assert_eq!(body["details"][0]["error_code"], 1036);
+    assert_eq!(body["details"][0]["message"], "endDate must be after startDate");
+}
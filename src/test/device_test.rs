@@ -0,0 +1,178 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use warp::Filter;
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::models::device_model::DEVICE_CODE_GRANT;
+use crate::test::support::{bearer, body, filter, handle_rejection, path, post};
+
+fn device_config(ttl_seconds: i64) -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        issuer: "https://auth.example.com".to_string(),
+        device_code_ttl_seconds: ttl_seconds,
+        device_poll_interval_seconds: 1,
+        ..Config::from_env()
+    })
+}
+
+async fn admin<F>(filter: &F, config: &Config) -> String
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    bearer(filter, config, json!({ "grant_type": "user", "username": "admin", "password": "password" })).await
+}
+
+async fn start<F>(filter: &F, config: &Config, scope: Option<&str>) -> Value
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = post(filter, &path(config, "auth/device"), None, json!({ "client_id": "client", "scope": scope })).await;
+    assert_eq!(res.status(), 200);
+    body(&res)
+}
+
+async fn poll<F>(filter: &F, config: &Config, device: &Value) -> warp::http::Response<warp::hyper::body::Bytes>
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let request = json!({
+        "grant_type": DEVICE_CODE_GRANT,
+        "device_code": device["device_code"],
+        "client_id": "client"
+    });
+    post(filter, &path(config, "auth/token"), None, request).await
+}
+
+fn error_code(res: &warp::http::Response<warp::hyper::body::Bytes>) -> Value {
+    body(res)["details"][0]["error_code"].clone()
+}
+
+/// The RFC 6749 `error` of a failed poll.
+fn oauth_error(res: &warp::http::Response<warp::hyper::body::Bytes>) -> Value {
+    body(res)["error"].clone()
+}
+
+#[tokio::test]
+async fn device_is_approved_and_polls_a_token() {
+    let config = device_config(600);
+    let filter = filter(&config);
+    let device = start(&filter, &config, Some("openid")).await;
+    let user_code = device["user_code"].as_str().unwrap();
+    assert_eq!(user_code.len(), 9);
+    assert_eq!(&user_code[4..5], "-");
+    assert_eq!(device["verification_uri"], "https://auth.example.com/api/v1/auth/device/verify");
+    assert_eq!(device["interval"], 1);
+    assert_eq!(device["expires_in"], 600);
+
+    let res = poll(&filter, &config, &device).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(oauth_error(&res), "authorization_pending");
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&format!("{}?user_code={}", path(&config, "auth/device/verify"), user_code))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    assert!(String::from_utf8_lossy(res.body()).contains(user_code));
+    assert_eq!(res.headers()["x-frame-options"], "DENY");
+    assert_eq!(res.headers()["content-security-policy"], "frame-ancestors 'none'");
+
+    // Codes are accepted however the user types them.
+    let typed = user_code.replace('-', " ").to_lowercase();
+    let admin = admin(&filter, &config).await;
+    let res = post(&filter, &path(&config, "auth/device/verify"), Some(&admin), json!({ "user_code": typed })).await;
+    assert_eq!(res.status(), 204);
+    let res = post(&filter, &path(&config, "auth/device/verify"), Some(&admin), json!({ "user_code": user_code })).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(error_code(&res), 2014);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let res = poll(&filter, &config, &device).await;
+    assert_eq!(res.status(), 200);
+    let token = body(&res);
+    assert!(token["id_token"].is_string());
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&path(&config, "auth/sessions"))
+        .header("Authorization", format!("Bearer {}", token["token"].as_str().unwrap()))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+
+    // The code is single use.
+    let res = poll(&filter, &config, &device).await;
+    assert_eq!(oauth_error(&res), "invalid_grant");
+}
+
+#[tokio::test]
+async fn polling_semantics() {
+    let config = device_config(600);
+    let filter = filter(&config);
+
+    let device = start(&filter, &config, None).await;
+    assert_eq!(oauth_error(&poll(&filter, &config, &device).await), "authorization_pending");
+    let res = poll(&filter, &config, &device).await;
+    assert_eq!(res.status(), 400);
+    let error = body(&res);
+    assert_eq!(error["error"], "slow_down");
+    assert_eq!(error["interval"], 6);
+    assert!(error["error_description"].as_str().unwrap().contains("wait 5 more seconds"));
+    assert!(error.get("details").is_none());
+
+    let device = start(&filter, &config, None).await;
+    let admin = admin(&filter, &config).await;
+    let res = post(
+        &filter,
+        &path(&config, "auth/device/verify"),
+        Some(&admin),
+        json!({ "user_code": device["user_code"], "approve": false }),
+    )
+    .await;
+    assert_eq!(res.status(), 204);
+    assert_eq!(oauth_error(&poll(&filter, &config, &device).await), "access_denied");
+
+    let device = start(&filter, &config, None).await;
+    let request = json!({ "grant_type": DEVICE_CODE_GRANT, "device_code": device["device_code"], "client_id": "other" });
+    let res = post(&filter, &path(&config, "auth/token"), None, request).await;
+    assert_eq!(oauth_error(&res), "invalid_grant");
+
+    let config = device_config(0);
+    let filter = self::filter(&config);
+    let device = start(&filter, &config, None).await;
+    assert_eq!(oauth_error(&poll(&filter, &config, &device).await), "expired_token");
+}
+
+#[tokio::test]
+async fn device_authorization_errors() {
+    let config = device_config(600);
+    let filter = filter(&config);
+
+    let res = post(&filter, &path(&config, "auth/device"), None, json!({ "client_id": "unknown" })).await;
+    assert_eq!(res.status(), 401);
+    let res = post(&filter, &path(&config, "auth/device"), None, json!({ "client_id": "client", "scope": "scim" })).await;
+    assert_eq!(res.status(), 400);
+
+    let device = start(&filter, &config, None).await;
+    let res = post(&filter, &path(&config, "auth/device/verify"), None, json!({ "user_code": device["user_code"] })).await;
+    assert_eq!(res.status(), 401);
+
+    let client = bearer(
+        &filter,
+        &config,
+        json!({ "grant_type": "client", "client_id": "client", "client_secret": "secret" }),
+    )
+    .await;
+    let res = post(&filter, &path(&config, "auth/device/verify"), Some(&client), json!({ "user_code": device["user_code"] })).await;
+    assert_eq!(res.status(), 403);
+
+    let admin = admin(&filter, &config).await;
+    let res = post(&filter, &path(&config, "auth/device/verify"), Some(&admin), json!({ "user_code": "BCDF-GHJK" })).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(error_code(&res), 2014);
+}
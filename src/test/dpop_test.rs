@@ -0,0 +1,234 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use chrono::Utc;
+use p256::ecdsa::SigningKey;
+use rand::RngCore;
+use rand_core::OsRng;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use warp::Filter;
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::services::dpop_service::{access_token_hash, jwk_thumbprint, DpopVerifier};
+use crate::services::jwt_service::{encode_es256_with_header, to_jwk};
+use crate::test::support::{handle_rejection, path};
+
+fn dpop_config() -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        issuer: "https://auth.example.com".to_string(),
+        ..Config::from_env()
+    })
+}
+
+fn jti() -> String {
+    let mut bytes = [0u8; 12];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn proof_with_iat(key: &SigningKey, method: &str, url: &str, token: Option<&str>, iat: i64) -> String {
+    let header = json!({
+        "typ": "dpop+jwt",
+        "alg": "ES256",
+        "jwk": to_jwk(key.verifying_key(), None),
+    });
+    let mut claims = json!({ "jti": jti(), "htm": method, "htu": url, "iat": iat });
+    if let Some(token) = token {
+        claims["ath"] = json!(access_token_hash(token));
+    }
+    encode_es256_with_header(key, &header, &claims)
+}
+
+fn proof(key: &SigningKey, method: &str, url: &str, token: Option<&str>) -> String {
+    proof_with_iat(key, method, url, token, Utc::now().timestamp())
+}
+
+#[test]
+fn verifier_checks_request_binding_and_replay() {
+    let verifier = DpopVerifier::default();
+    let key = SigningKey::random(&mut OsRng);
+    let url = "https://auth.example.com/api/v1/protected";
+
+    let valid = proof(&key, "GET", url, Some("token"));
+    let jkt = verifier.verify(&valid, "GET", url, Some("token")).unwrap();
+    assert_eq!(jkt, jwk_thumbprint(&to_jwk(key.verifying_key(), None)));
+    assert!(verifier.verify(&valid, "GET", url, Some("token")).is_err());
+
+    assert!(verifier
+        .verify(&proof(&key, "POST", url, Some("token")), "GET", url, Some("token"))
+        .is_err());
+    assert!(verifier
+        .verify(&proof(&key, "GET", "https://evil.example.com/", Some("token")), "GET", url, Some("token"))
+        .is_err());
+    assert!(verifier
+        .verify(&proof(&key, "GET", url, Some("other")), "GET", url, Some("token"))
+        .is_err());
+    assert!(verifier
+        .verify(&proof(&key, "GET", url, None), "GET", url, Some("token"))
+        .is_err());
+    let stale = proof_with_iat(&key, "GET", url, Some("token"), Utc::now().timestamp() - 600);
+    assert!(verifier.verify(&stale, "GET", url, Some("token")).is_err());
+    for iat in [i64::MIN, i64::MIN + 1, i64::MAX] {
+        let extreme = proof_with_iat(&key, "GET", url, Some("token"), iat);
+        assert!(verifier.verify(&extreme, "GET", url, Some("token")).is_err());
+    }
+    let with_query = proof(&key, "GET", &format!("{}?page=2", url), Some("token"));
+    assert!(verifier.verify(&with_query, "GET", url, Some("token")).is_ok());
+}
+
+#[test]
+fn replay_is_refused_in_the_last_second_of_a_proof() {
+    // With no allowed age a proof expires in the second it was issued.
+    let verifier = DpopVerifier::new(0);
+    let key = SigningKey::random(&mut OsRng);
+    let url = "https://auth.example.com/api/v1/protected";
+
+    // Retry in case the clock ticks between issuing and checking.
+    let valid = (0..3)
+        .map(|_| proof(&key, "GET", url, None))
+        .find(|valid| verifier.verify(valid, "GET", url, None).is_ok())
+        .expect("a fresh proof is accepted");
+    assert!(verifier.verify(&valid, "GET", url, None).is_err());
+}
+
+async fn dpop_token(filter: &(impl Filter<Extract = impl warp::Reply, Error = std::convert::Infallible> + Clone + 'static), config: &Config, key: &SigningKey) -> Value {
+    let token_path = path(config, "auth/token");
+    let res = warp::test::request()
+        .method("POST")
+        .path(&token_path)
+        .header("DPoP", proof(key, "POST", &format!("{}{}", config.issuer, token_path), None))
+        .json(&json!({
+            "grant_type": "client",
+            "client_id": "client",
+            "client_secret": "secret"
+        }))
+        .reply(filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    serde_json::from_slice(res.body()).unwrap()
+}
+
+#[tokio::test]
+async fn dpop_bound_token_requires_fresh_proof() {
+    let config = dpop_config();
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+    let key = SigningKey::random(&mut OsRng);
+
+    let issued = dpop_token(&filter, &config, &key).await;
+    assert_eq!(issued["token_type"], "DPoP");
+    let token = issued["token"].as_str().unwrap();
+
+    let protected = path(&config, "protected");
+    let url = format!("{}{}", config.issuer, protected);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&protected)
+        .header("Authorization", format!("Bearer {}", token))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&protected)
+        .header("Authorization", format!("DPoP {}", token))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+
+    let valid = proof(&key, "GET", &url, Some(token));
+    let res = warp::test::request()
+        .method("GET")
+        .path(&protected)
+        .header("Authorization", format!("DPoP {}", token))
+        .header("DPoP", &valid)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&protected)
+        .header("Authorization", format!("DPoP {}", token))
+        .header("DPoP", &valid)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+
+    let other_key = SigningKey::random(&mut OsRng);
+    let res = warp::test::request()
+        .method("GET")
+        .path(&protected)
+        .header("Authorization", format!("DPoP {}", token))
+        .header("DPoP", proof(&other_key, "GET", &url, Some(token)))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn plain_token_stays_bearer() {
+    let config = dpop_config();
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&json!({
+            "grant_type": "client",
+            "client_id": "client",
+            "client_secret": "secret"
+        }))
+        .reply(&filter)
+        .await;
+    let issued: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(issued["token_type"], "Bearer");
+    let token = issued["token"].as_str().unwrap();
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&path(&config, "protected"))
+        .header("Authorization", format!("DPoP {}", token))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .header("DPoP", "not-a-proof")
+        .json(&json!({
+            "grant_type": "client",
+            "client_id": "client",
+            "client_secret": "secret"
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn dpop_bound_token_is_refused_as_a_cookie() {
+    let config = Arc::new(Config {
+        session_cookies: true,
+        issuer: "https://auth.example.com".to_string(),
+        ..Config::from_env()
+    });
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+    let key = SigningKey::random(&mut OsRng);
+
+    let issued = dpop_token(&filter, &config, &key).await;
+    let cookie = format!("session={}", issued["token"].as_str().unwrap());
+
+    for endpoint in ["protected", "auth/sessions"] {
+        let res = warp::test::request()
+            .method("GET")
+            .path(&path(&config, endpoint))
+            .header("Cookie", &cookie)
+            .reply(&filter)
+            .await;
+        assert_eq!(res.status(), 401, "{}", endpoint);
+    }
+}
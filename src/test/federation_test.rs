@@ -0,0 +1,536 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use base64::{engine::general_purpose, Engine as _};
+use p256::ecdsa::SigningKey;
+use rand_core::OsRng;
+use rsa::signature::{SignatureEncoding, Signer};
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPrivateKey;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use warp::Filter;
+
+use crate::config::{Config, OidcProviderConfig};
+use crate::controllers::routes;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::repositories::credentials_repository::{CredentialRepository, InMemoryCredentialRepository};
+use crate::services::federation_service::{FederationService, FederationServiceImpl};
+use crate::services::jwt_service::{encode_es256, to_jwk};
+use crate::test::support::{body, filter, get, handle_rejection, path};
+
+const ISSUER: &str = "https://auth.example.com";
+const CLIENT_ID: &str = "backend";
+const CLIENT_SECRET: &str = "backend-secret";
+
+fn b64(bytes: impl AsRef<[u8]>) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Authorization codes handed out by the mock provider, with the ID token
+/// its token endpoint returns for each and the PKCE challenge it must match.
+type Grants = Arc<Mutex<HashMap<String, (String, String)>>>;
+
+/// A minimal OpenID provider: discovery, JWKS with an ES256 and an RS256
+/// key, and a token endpoint that checks the client secret and PKCE.
+struct MockIdp {
+    issuer: String,
+    ec_key: SigningKey,
+    rsa_key: RsaPrivateKey,
+    grants: Grants,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl Drop for MockIdp {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+impl MockIdp {
+    async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let ec_key = SigningKey::random(&mut OsRng);
+        let rsa_key = RsaPrivateKey::new(&mut OsRng, 1024).unwrap();
+        let grants: Grants = Arc::new(Mutex::new(HashMap::new()));
+
+        let discovery_document = json!({
+            "issuer": issuer,
+            "authorization_endpoint": format!("{}/authorize", issuer),
+            "token_endpoint": format!("{}/token", issuer),
+            "jwks_uri": format!("{}/jwks", issuer),
+        });
+        let public = rsa_key.to_public_key();
+        let jwks_document = json!({ "keys": [
+            to_jwk(ec_key.verifying_key(), Some("ec-1".to_string())),
+            {
+                "kty": "RSA",
+                "kid": "rsa-1",
+                "use": "sig",
+                "alg": "RS256",
+                "n": b64(public.n().to_bytes_be()),
+                "e": b64(public.e().to_bytes_be())
+            }
+        ]});
+
+        let discovery = warp::path!(".well-known" / "openid-configuration")
+            .map(move || warp::reply::json(&discovery_document));
+        let jwks = warp::path!("jwks").map(move || warp::reply::json(&jwks_document));
+        let token_grants = Arc::clone(&grants);
+        let token = warp::post()
+            .and(warp::path!("token"))
+            .and(warp::body::form::<HashMap<String, String>>())
+            .map(move |form: HashMap<String, String>| {
+                let field = |name: &str| form.get(name).map(String::as_str).unwrap_or_default();
+                let grant = token_grants.lock().unwrap().remove(field("code"));
+                let verified = field("grant_type") == "authorization_code"
+                    && field("client_id") == CLIENT_ID
+                    && field("client_secret") == CLIENT_SECRET
+                    && grant
+                        .as_ref()
+                        .is_some_and(|(_, challenge)| b64(Sha256::digest(field("code_verifier").as_bytes())) == *challenge);
+                match grant {
+                    Some((id_token, _)) if verified => warp::reply::with_status(
+                        warp::reply::json(&json!({
+                            "access_token": "provider-access-token",
+                            "token_type": "Bearer",
+                            "id_token": id_token
+                        })),
+                        warp::http::StatusCode::OK,
+                    ),
+                    _ => warp::reply::with_status(
+                        warp::reply::json(&json!({ "error": "invalid_grant" })),
+                        warp::http::StatusCode::BAD_REQUEST,
+                    ),
+                }
+            });
+
+        let (tx, rx) = oneshot::channel();
+        let server = warp::serve(discovery.or(jwks).or(token))
+            .incoming(listener)
+            .graceful(async {
+                rx.await.ok();
+            });
+        tokio::spawn(server.run());
+
+        Self {
+            issuer,
+            ec_key,
+            rsa_key,
+            grants,
+            shutdown: Some(tx),
+        }
+    }
+
+    fn provider(&self) -> OidcProviderConfig {
+        OidcProviderConfig {
+            name: "mock".to_string(),
+            issuer: self.issuer.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: CLIENT_SECRET.to_string(),
+            scopes: "openid email profile".to_string(),
+            link_by_email: false,
+        }
+    }
+
+    /// Standard claims for `sub` answering the login URL's nonce.
+    fn claims(&self, authorize_url: &str, sub: &str) -> Value {
+        let params = query(authorize_url);
+        let now = chrono::Utc::now().timestamp();
+        json!({
+            "iss": self.issuer,
+            "aud": CLIENT_ID,
+            "sub": sub,
+            "iat": now,
+            "exp": now + 300,
+            "nonce": params["nonce"]
+        })
+    }
+
+    fn sign_es256(&self, claims: &Value) -> String {
+        encode_es256(&self.ec_key, Some("ec-1"), claims)
+    }
+
+    fn sign_rs256(&self, claims: &Value) -> String {
+        let header = json!({ "alg": "RS256", "typ": "JWT", "kid": "rsa-1" });
+        let signing_input = format!("{}.{}", b64(header.to_string()), b64(claims.to_string()));
+        let key = rsa::pkcs1v15::SigningKey::<Sha256>::new(self.rsa_key.clone());
+        format!("{}.{}", signing_input, b64(key.sign(signing_input.as_bytes()).to_bytes()))
+    }
+
+    /// The user approves the login: returns the callback query the
+    /// provider would redirect the browser back with.
+    fn approve(&self, authorize_url: &str, id_token: String) -> String {
+        let params = query(authorize_url);
+        let code = uuid::Uuid::new_v4().to_string();
+        self.grants
+            .lock()
+            .unwrap()
+            .insert(code.clone(), (id_token, params["code_challenge"].clone()));
+        format!("code={}&state={}", code, params["state"])
+    }
+}
+
+fn query(url: &str) -> HashMap<String, String> {
+    reqwest::Url::parse(url).unwrap().query_pairs().into_owned().collect()
+}
+
+fn federation_config(idp: &MockIdp) -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        issuer: ISSUER.to_string(),
+        oidc_providers: vec![idp.provider()],
+        ..Config::from_env()
+    })
+}
+
+/// Returns the provider URL and the `name=value` of the binding cookie.
+async fn start_login<F>(filter: &F, config: &Config) -> (String, String)
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = get(filter, &path(config, "auth/oidc/mock/login"), None).await;
+    assert_eq!(res.status(), 302);
+    let cookie = res.headers()["set-cookie"].to_str().unwrap();
+    (
+        res.headers()["location"].to_str().unwrap().to_string(),
+        cookie.split(';').next().unwrap().to_string(),
+    )
+}
+
+async fn callback<F>(filter: &F, config: &Config, cookie: &str, query: &str) -> warp::http::Response<warp::hyper::body::Bytes>
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    warp::test::request()
+        .method("GET")
+        .path(&format!("{}?{}", path(config, "auth/oidc/mock/callback"), query))
+        .header("cookie", cookie)
+        .reply(filter)
+        .await
+}
+
+/// Users that signed in through the mock provider, from the audit log.
+async fn federated_logins<F>(filter: &F, config: &Config) -> Vec<String>
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(config, "auth/token"))
+        .json(&json!({ "grant_type": "user", "username": "admin", "password": "password" }))
+        .reply(filter)
+        .await;
+    let admin = format!("Bearer {}", body(&res)["token"].as_str().unwrap());
+    let res = get(filter, &path(config, "admin/audit?action=token_issued"), Some(&admin)).await;
+    assert_eq!(res.status(), 200);
+    body(&res)
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["target"] == "oidc:mock")
+        .map(|e| e["actor"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn login_creates_and_links_user() {
+    let idp = MockIdp::start().await;
+    let config = federation_config(&idp);
+    let filter = filter(&config);
+
+    let (url, cookie) = start_login(&filter, &config).await;
+    assert!(url.starts_with(&format!("{}/authorize?", idp.issuer)));
+    let params = query(&url);
+    assert_eq!(params["client_id"], CLIENT_ID);
+    assert_eq!(params["response_type"], "code");
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert_eq!(params["redirect_uri"], format!("{}{}", ISSUER, path(&config, "auth/oidc/mock/callback")));
+
+    let mut claims = idp.claims(&url, "subject-1");
+    claims["preferred_username"] = json!("alice");
+    claims["email"] = json!("alice@example.com");
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+    assert_eq!(res.status(), 200);
+    let token = format!("Bearer {}", body(&res)["token"].as_str().unwrap());
+    assert_eq!(get(&filter, &path(&config, "protected"), Some(&token)).await.status(), 200);
+
+    // The link wins over the profile claims on later logins.
+    let (url, cookie) = start_login(&filter, &config).await;
+    let mut claims = idp.claims(&url, "subject-1");
+    claims["preferred_username"] = json!("renamed");
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_rs256(&claims))).await;
+    assert_eq!(res.status(), 200);
+
+    // A different subject claiming the same username gets its own account.
+    let (url, cookie) = start_login(&filter, &config).await;
+    let mut claims = idp.claims(&url, "subject-2");
+    claims["preferred_username"] = json!("alice");
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+    assert_eq!(res.status(), 200);
+
+    // Without LINK_BY_EMAIL a verified email never reaches an existing user.
+    let (url, cookie) = start_login(&filter, &config).await;
+    let mut claims = idp.claims(&url, "subject-3");
+    claims["email"] = json!("alice@example.com");
+    claims["email_verified"] = json!(true);
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+    assert_eq!(res.status(), 200);
+
+    assert_eq!(
+        federated_logins(&filter, &config).await,
+        vec!["alice", "alice", "alice-2", "alice@example.com"]
+    );
+}
+
+/// Claims of a login by `sub` with a verified `email`.
+fn verified(idp: &MockIdp, url: &str, sub: &str, email: &str) -> Value {
+    let mut claims = idp.claims(url, sub);
+    claims["email"] = json!(email);
+    claims["email_verified"] = json!(true);
+    claims
+}
+
+/// Starts linking the provider account to the user behind `bearer`, returning the
+/// authorization url and the binding cookie.
+async fn start_link<F>(filter: &F, config: &Config, bearer: &str) -> (String, String)
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(config, "auth/oidc/mock/link"))
+        .header("Authorization", bearer)
+        .reply(filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let url = body(&res)["url"].as_str().unwrap().to_string();
+    let cookie = res.headers()["set-cookie"].to_str().unwrap().split(';').next().unwrap().to_string();
+    (url, cookie)
+}
+
+#[tokio::test]
+async fn email_matches_wait_for_the_user_to_link() {
+    let idp = MockIdp::start().await;
+    let config = Arc::new(Config {
+        issuer: ISSUER.to_string(),
+        oidc_providers: vec![OidcProviderConfig {
+            link_by_email: true,
+            ..idp.provider()
+        }],
+        ..Config::from_env()
+    });
+    let filter = filter(&config);
+
+    let (url, cookie) = start_login(&filter, &config).await;
+    let mut claims = idp.claims(&url, "subject-1");
+    claims["preferred_username"] = json!("alice");
+    claims["email"] = json!("alice@example.com");
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+    assert_eq!(res.status(), 200);
+    let alice = format!("Bearer {}", body(&res)["token"].as_str().unwrap());
+
+    // Another provider account with alice's verified email is not let in.
+    let (url, cookie) = start_login(&filter, &config).await;
+    let claims = verified(&idp, &url, "subject-2", "alice@example.com");
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+    assert_eq!(res.status(), 409);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2015);
+
+    // Alice confirms by starting the link from her own session.
+    let (url, cookie) = start_link(&filter, &config, &alice).await;
+    let claims = verified(&idp, &url, "subject-2", "alice@example.com");
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+    assert_eq!(res.status(), 200);
+
+    let (url, cookie) = start_login(&filter, &config).await;
+    let claims = verified(&idp, &url, "subject-2", "alice@example.com");
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(federated_logins(&filter, &config).await, vec!["alice", "alice", "alice"]);
+
+    // A provider account linked to someone else cannot be taken over.
+    let (url, cookie) = start_login(&filter, &config).await;
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&idp.claims(&url, "subject-3")))).await;
+    let bob = format!("Bearer {}", body(&res)["token"].as_str().unwrap());
+    let (url, cookie) = start_link(&filter, &config, &bob).await;
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&idp.claims(&url, "subject-1")))).await;
+    assert_eq!(res.status(), 409);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2016);
+
+    let res = warp::test::request().method("POST").path(&path(&config, "auth/oidc/mock/link")).reply(&filter).await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn admins_are_never_offered_by_email() {
+    let idp = MockIdp::start().await;
+    let repository = InMemoryCredentialRepository::new();
+    let mut admin = repository.find_user_by_username("admin").await.unwrap();
+    admin.emails = vec!["root@example.com".to_string()];
+    assert!(repository.save_user(admin, None).await);
+    let federation = FederationServiceImpl::new(
+        repository.clone(),
+        vec![OidcProviderConfig {
+            link_by_email: true,
+            ..idp.provider()
+        }],
+        format!("{}/api/v1/auth/oidc", ISSUER),
+    );
+
+    let redirect = federation.login_url("mock", None).await.unwrap();
+    let claims = verified(&idp, &redirect.url, "subject-1", "root@example.com");
+    let callback = query(&format!("http://callback/?{}", idp.approve(&redirect.url, idp.sign_es256(&claims))));
+    let result = federation
+        .complete_login("mock", &callback["code"], &callback["state"], Some(&redirect.binding))
+        .await;
+    assert!(matches!(result, Err(ApiError::ErrorCode(ErrorCodes::FederatedLoginFailed))));
+    assert!(repository.find_linked_user("mock", "subject-1").await.is_none());
+}
+
+#[tokio::test]
+async fn rejects_invalid_id_tokens() {
+    let idp = MockIdp::start().await;
+    let config = federation_config(&idp);
+    let filter = filter(&config);
+
+    let tamper: Vec<(&str, Value)> = vec![
+        ("iss", json!("https://evil.example.com")),
+        ("aud", json!("someone-else")),
+        ("exp", json!(chrono::Utc::now().timestamp() - 600)),
+        ("nonce", json!("replayed")),
+    ];
+    for (claim, value) in tamper {
+        let (url, cookie) = start_login(&filter, &config).await;
+        let mut claims = idp.claims(&url, "subject-1");
+        claims[claim] = value;
+        let res = callback(&filter, &config, &cookie, &idp.approve(&url, idp.sign_es256(&claims))).await;
+        assert_eq!(res.status(), 401, "tampered {}", claim);
+        assert_eq!(body(&res)["details"][0]["error_code"], 2007);
+    }
+
+    // Signed by a key that is not in the provider's JWKS.
+    let (url, cookie) = start_login(&filter, &config).await;
+    let claims = idp.claims(&url, "subject-1");
+    let forged = encode_es256(&SigningKey::random(&mut OsRng), Some("ec-1"), &claims);
+    let res = callback(&filter, &config, &cookie, &idp.approve(&url, forged)).await;
+    assert_eq!(res.status(), 401);
+
+    // An audience list naming this client is accepted.
+    let (url, cookie) = start_login(&filter, &config).await;
+    let mut claims = idp.claims(&url, "subject-1");
+    claims["aud"] = json!(["other", CLIENT_ID]);
+    let query = idp.approve(&url, idp.sign_es256(&claims));
+    let res = callback(&filter, &config, &cookie, &query).await;
+    assert_eq!(res.status(), 200);
+
+    // State is single use.
+    let res = callback(&filter, &config, &cookie, &query).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2006);
+}
+
+#[tokio::test]
+async fn callbacks_need_the_browser_that_started_the_login() {
+    let idp = MockIdp::start().await;
+    let config = federation_config(&idp);
+    let filter = filter(&config);
+
+    let res = get(&filter, &path(&config, "auth/oidc/mock/login"), None).await;
+    let cookie = res.headers()["set-cookie"].to_str().unwrap();
+    assert!(cookie.starts_with("oidc_login="));
+    assert!(cookie.contains(&format!("; Path={};", path(&config, "auth/oidc/mock/callback"))));
+    assert!(cookie.contains("; HttpOnly") && cookie.contains("; SameSite=Lax"));
+
+    // An attacker's callback URL opened in a browser with another binding,
+    // or none, does not log that browser in to the attacker's account.
+    let (_, victim) = start_login(&filter, &config).await;
+    for cookie in [victim.as_str(), ""] {
+        let (url, _) = start_login(&filter, &config).await;
+        let query = idp.approve(&url, idp.sign_es256(&idp.claims(&url, "attacker")));
+        let res = callback(&filter, &config, cookie, &query).await;
+        assert_eq!(res.status(), 400);
+        assert_eq!(body(&res)["details"][0]["error_code"], 2006);
+    }
+
+    let (url, cookie) = start_login(&filter, &config).await;
+    let query = idp.approve(&url, idp.sign_es256(&idp.claims(&url, "subject-1")));
+    let res = callback(&filter, &config, &cookie, &query).await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn callback_errors() {
+    let idp = MockIdp::start().await;
+    let config = federation_config(&idp);
+    let filter = filter(&config);
+
+    let res = get(&filter, &path(&config, "auth/oidc/unknown/login"), None).await;
+    assert_eq!(res.status(), 404);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2005);
+
+    let (url, cookie) = start_login(&filter, &config).await;
+    let res = callback(&filter, &config, &cookie, "code=abc").await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2006);
+
+    let res = callback(&filter, &config, &cookie, "code=abc&state=forged").await;
+    assert_eq!(res.status(), 400);
+
+    let res = callback(&filter, &config, &cookie, "error=access_denied&error_description=cancelled").await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2007);
+
+    // The provider refuses a code it never issued.
+    let res = callback(&filter, &config, &cookie, &format!("code=unknown&state={}", query(&url)["state"])).await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn unreachable_provider() {
+    let idp = MockIdp::start().await;
+    let config = Arc::new(Config {
+        oidc_providers: vec![OidcProviderConfig {
+            issuer: "http://127.0.0.1:9".to_string(),
+            ..idp.provider()
+        }],
+        issuer: ISSUER.to_string(),
+        ..Config::from_env()
+    });
+    let res = get(&filter(&config), &path(&config, "auth/oidc/mock/login"), None).await;
+    assert_eq!(res.status(), 502);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2008);
+}
+
+#[tokio::test]
+async fn hanging_provider_times_out() {
+    let idp = MockIdp::start().await;
+    // Accepts connections but never answers them.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((connection, _)) = listener.accept().await {
+            connections.push(connection);
+        }
+    });
+    let config = Arc::new(Config {
+        oidc_providers: vec![OidcProviderConfig {
+            issuer: format!("http://{}", address),
+            ..idp.provider()
+        }],
+        oidc_timeout_seconds: 1,
+        issuer: ISSUER.to_string(),
+        ..Config::from_env()
+    });
+    let started = std::time::Instant::now();
+    let res = get(&filter(&config), &path(&config, "auth/oidc/mock/login"), None).await;
+    assert_eq!(res.status(), 502);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2008);
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+}
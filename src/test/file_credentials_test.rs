@@ -0,0 +1,196 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use argon2::password_hash::{PasswordHasher, SaltString};
+use argon2::Argon2;
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use warp::Filter;
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::repositories::credentials_repository::CredentialRepository;
+use crate::repositories::file_credentials_repository::FileCredentialRepository;
+use crate::test::support::{body, filter, handle_rejection, path};
+
+fn bcrypt_hash(password: &str) -> String {
+    bcrypt::hash(password, 4).unwrap()
+}
+
+fn argon2_hash(password: &str) -> String {
+    let salt = SaltString::encode_b64(b"file-credentials").unwrap();
+    Argon2::default().hash_password(password.as_bytes(), &salt).unwrap().to_string()
+}
+
+fn sha_hash(password: &str) -> String {
+    format!("{{SHA}}{}", general_purpose::STANDARD.encode(Sha1::digest(password.as_bytes())))
+}
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}", name, uuid::Uuid::new_v4()));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn users_file() -> String {
+    format!(
+        "# managed by config management\n\nroot:{}:admin\nalice:{}\nbob:{}\n",
+        bcrypt_hash("root secret"),
+        argon2_hash("alice secret"),
+        sha_hash("bob secret")
+    )
+}
+
+fn clients_file() -> String {
+    format!(
+        "reporting:{}:openid scim\nsigner:{{PLAIN}}signing secret\n",
+        bcrypt_hash("client secret")
+    )
+}
+
+fn file_config(users: &Path, clients: &Path) -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        credentials_users_file: Some(users.to_string_lossy().to_string()),
+        credentials_clients_file: Some(clients.to_string_lossy().to_string()),
+        credentials_reload_seconds: 1,
+        ..Config::from_env()
+    })
+}
+
+async fn token<F>(filter: &F, config: &Config, request: Value) -> warp::http::Response<warp::hyper::body::Bytes>
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    warp::test::request()
+        .method("POST")
+        .path(&path(config, "auth/token"))
+        .json(&request)
+        .reply(filter)
+        .await
+}
+
+fn user(username: &str, password: &str) -> Value {
+    json!({ "grant_type": "user", "username": username, "password": password })
+}
+
+async fn get<F>(filter: &F, path: &str, res: &warp::http::Response<warp::hyper::body::Bytes>) -> u16
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    warp::test::request()
+        .method("GET")
+        .path(path)
+        .header("Authorization", format!("Bearer {}", body(res)["token"].as_str().unwrap()))
+        .reply(filter)
+        .await
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn users_and_clients_come_from_the_files() {
+    let users = temp_file("users", &users_file());
+    let clients = temp_file("clients", &clients_file());
+    let config = file_config(&users, &clients);
+    let filter = filter(&config);
+
+    for (username, password) in [("root", "root secret"), ("alice", "alice secret"), ("bob", "bob secret")] {
+        assert_eq!(token(&filter, &config, user(username, password)).await.status(), 200, "{}", username);
+        assert_eq!(token(&filter, &config, user(username, "wrong")).await.status(), 401, "{}", username);
+    }
+    // The seeded store is not used.
+    assert_eq!(token(&filter, &config, user("admin", "password")).await.status(), 401);
+
+    let root = token(&filter, &config, user("root", "root secret")).await;
+    assert_eq!(get(&filter, &path(&config, "admin/audit"), &root).await, 200);
+    let alice = token(&filter, &config, user("alice", "alice secret")).await;
+    assert_eq!(get(&filter, &path(&config, "admin/audit"), &alice).await, 403);
+
+    let request = json!({ "grant_type": "client", "client_id": "reporting", "client_secret": "client secret", "scope": "scim" });
+    let client = token(&filter, &config, request).await;
+    assert_eq!(client.status(), 200);
+    assert_eq!(get(&filter, "/scim/v2/Users", &client).await, 200);
+    let request = json!({ "grant_type": "client", "client_id": "reporting", "client_secret": "secret" });
+    assert_eq!(token(&filter, &config, request).await.status(), 401);
+
+    // File users cannot be changed through the API.
+    let repository = FileCredentialRepository::open(users.to_str(), clients.to_str()).unwrap();
+    let account = repository.find_user_by_username("alice").await.unwrap();
+    assert_eq!(account.id, "alice");
+    assert!(!repository.save_user(account, Some("new password".to_string())).await);
+    assert!(!repository.delete_user("alice").await);
+    assert_eq!(repository.client_secret("signer").await.as_deref(), Some("signing secret"));
+    assert_eq!(repository.client_secret("reporting").await, None);
+    assert!(repository.client_exists("reporting").await);
+}
+
+#[test]
+fn malformed_lines_are_reported_with_line_numbers() {
+    let users = temp_file(
+        "users",
+        &format!(
+            "root:{}:admin\n\n# comment\nnohash\nplain:{{PLAIN}}secret\nroot:{}\ncarol:$2b$12$short\ndave:{}:owner\n",
+            bcrypt_hash("root secret"),
+            bcrypt_hash("again"),
+            sha_hash("dave")
+        ),
+    );
+    let clients = temp_file("clients", "reporting:md5hash:openid\n");
+    let errors = FileCredentialRepository::open(users.to_str(), clients.to_str()).err().unwrap();
+    let users = users.display().to_string();
+    let clients = clients.display().to_string();
+    let lines: Vec<&str> = errors
+        .iter()
+        .map(|e| e.split(": ").next().unwrap())
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            format!("{}:4", users),
+            format!("{}:5", users),
+            format!("{}:6", users),
+            format!("{}:7", users),
+            format!("{}:8", users),
+            format!("{}:1", clients),
+        ]
+    );
+    assert!(errors[1].ends_with("plain text passwords are only allowed for clients"));
+    assert!(errors[2].ends_with("duplicate user root"));
+    assert!(errors[4].ends_with("unknown role owner"));
+
+    let missing = FileCredentialRepository::open(Some("/nonexistent/users"), None).err().unwrap();
+    assert_eq!(missing.len(), 1);
+    assert!(missing[0].starts_with("/nonexistent/users: "));
+
+    // Startup reports the same errors instead of panicking.
+    let errors = routes(file_config(Path::new("/nonexistent/users"), Path::new(&clients))).err().unwrap();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].starts_with("/nonexistent/users: "));
+    assert!(errors[1].starts_with(&format!("{}:1: ", clients)));
+}
+
+#[tokio::test]
+async fn edits_are_picked_up_without_a_restart() {
+    let users = temp_file("users", &users_file());
+    let clients = temp_file("clients", &clients_file());
+    let config = file_config(&users, &clients);
+    let filter = filter(&config);
+    assert_eq!(token(&filter, &config, user("carol", "carol secret")).await.status(), 401);
+
+    std::fs::write(&users, format!("{}carol:{}\n", users_file(), bcrypt_hash("carol secret"))).unwrap();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(token(&filter, &config, user("carol", "carol secret")).await.status(), 200);
+
+    // A broken edit keeps the credentials that were loaded last.
+    std::fs::write(&users, format!("{}carol\n", users_file())).unwrap();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(token(&filter, &config, user("carol", "carol secret")).await.status(), 200);
+
+    std::fs::write(&users, users_file()).unwrap();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(token(&filter, &config, user("carol", "carol secret")).await.status(), 401);
+    assert_eq!(token(&filter, &config, user("alice", "alice secret")).await.status(), 200);
+}
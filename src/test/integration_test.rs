@@ -11,7 +11,7 @@ use tokio::time::{sleep, Duration};
 
 async fn spawn_server() -> (oneshot::Sender<()>, String) {
     std::env::set_var("PORT", "0");
-    let (shutdown, base) = run_server().await;
+    let (shutdown, base) = run_server().await.unwrap();
     // give the server time to start
     sleep(Duration::from_millis(100)).await;
     (shutdown, base)
@@ -66,6 +66,38 @@ async fn test_create_message_valid() {
     let _ = shutdown.send(());
 }
 
+#[tokio::test]
+async fn test_create_message_is_normalized() {
+    let (shutdown, base) = spawn_server().await;
+    let address = build_address(&base, "messages");
+    let client = reqwest::Client::new();
+    let response = client
+        .post(address.clone())
+        .json(&serde_json::json!({
+            "content": "  Cafe\u{301}\u{200B} au lait\u{7} "
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 201);
+
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["content"], "Caf\u{e9} au lait");
+
+    let response = client
+        .post(address)
+        .json(&serde_json::json!({ "content": " \u{200B} " }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["details"][0]["error_code"], ErrorCodes::NotEmpty as u16);
+
+    let _ = shutdown.send(());
+}
+
 #[tokio::test]
 async fn test_search_message_valid() {
     let (shutdown, base) = spawn_server().await;
@@ -97,6 +129,26 @@ async fn test_search_message_valid() {
     let _ = shutdown.send(());
 }
 
+#[tokio::test]
+async fn test_search_message_invalid_maxsize() {
+    let (shutdown, base) = spawn_server().await;
+
+    let address = build_address(&base, "messages");
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("{}/{}", address, "a".repeat(33)))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["details"][0]["field"], "message");
+    assert_eq!(body["details"][0]["error_code"], ErrorCodes::MaxSize as u16);
+
+    let _ = shutdown.send(());
+}
+
 #[tokio::test]
 async fn test_create_message_invalid_null() {
     let (shutdown, base) = spawn_server().await;
@@ -215,3 +267,202 @@ async fn test_auth_token_and_protected() {
 
     let _ = shutdown.send(());
 }
+
+#[cfg(test)]
+async fn request_user_token(client: &reqwest::Client, base: &str, user_agent: &str) -> String {
+    let body: Value = client
+        .post(build_address(base, "auth/token"))
+        .header("User-Agent", user_agent)
+        .json(&serde_json::json!({
+            "grant_type": "user",
+            "username": "admin",
+            "password": "password"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    body["token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_list_and_revoke_sessions() {
+    let (shutdown, base) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let laptop = request_user_token(&client, &base, "laptop").await;
+    let phone = request_user_token(&client, &base, "phone").await;
+
+    let sessions_addr = build_address(&base, "auth/sessions");
+    let unauth = client.get(sessions_addr.clone()).send().await.unwrap();
+    assert_eq!(unauth.status(), 401);
+
+    let response = client
+        .get(sessions_addr.clone())
+        .bearer_auth(&laptop)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let sessions: Value = response.json().await.unwrap();
+    let sessions = sessions.as_array().unwrap();
+    assert_eq!(sessions.len(), 2);
+    let phone_session = sessions
+        .iter()
+        .find(|s| s["user_agent"] == "phone")
+        .unwrap();
+    assert_eq!(phone_session["current"], false);
+    assert_eq!(phone_session["client_ip"], "127.0.0.1");
+    assert_eq!(phone_session["grant_type"], "user");
+
+    let revoke = client
+        .delete(format!("{}/{}", sessions_addr, phone_session["id"].as_str().unwrap()))
+        .bearer_auth(&laptop)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoke.status(), 204);
+
+    let protected_addr = build_address(&base, "protected");
+    let revoked = client
+        .get(protected_addr.clone())
+        .bearer_auth(&phone)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(revoked.status(), 401);
+
+    let missing = client
+        .delete(format!("{}/{}", sessions_addr, "unknown"))
+        .bearer_auth(&laptop)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), 404);
+
+    let logout_all = client
+        .post(build_address(&base, "auth/logout-all"))
+        .bearer_auth(&laptop)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(logout_all.status(), 200);
+    let body: Value = logout_all.json().await.unwrap();
+    assert_eq!(body["revoked"], 1);
+
+    let after = client
+        .get(protected_addr)
+        .bearer_auth(&laptop)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(after.status(), 401);
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn test_admin_sessions_require_admin() {
+    let (shutdown, base) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let client_token: Value = client
+        .post(build_address(&base, "auth/token"))
+        .json(&serde_json::json!({
+            "grant_type": "client",
+            "client_id": "client",
+            "client_secret": "secret"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let client_token = client_token["token"].as_str().unwrap();
+
+    let admin_sessions = build_address(&base, "admin/users/admin/sessions");
+    let forbidden = client
+        .get(admin_sessions.clone())
+        .bearer_auth(client_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), 403);
+
+    let admin = request_user_token(&client, &base, "console").await;
+    let response = client
+        .get(admin_sessions)
+        .bearer_auth(&admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let sessions: Value = response.json().await.unwrap();
+    assert_eq!(sessions.as_array().unwrap().len(), 1);
+
+    let logout_all = client
+        .post(build_address(&base, "admin/users/admin/logout-all"))
+        .bearer_auth(&admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(logout_all.status(), 200);
+    let body: Value = logout_all.json().await.unwrap();
+    assert_eq!(body["revoked"], 1);
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn test_audit_log_records_and_filters() {
+    let (shutdown, base) = spawn_server().await;
+    let client = reqwest::Client::new();
+
+    let failed = client
+        .post(build_address(&base, "auth/token"))
+        .json(&serde_json::json!({
+            "grant_type": "user",
+            "username": "admin",
+            "password": "wrong"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(failed.status(), 401);
+
+    let admin = request_user_token(&client, &base, "auditor").await;
+
+    let audit_addr = build_address(&base, "admin/audit");
+    let unauth = client.get(audit_addr.clone()).send().await.unwrap();
+    assert_eq!(unauth.status(), 401);
+
+    let response = client
+        .get(format!("{}?action=login_failed&actor=admin", audit_addr))
+        .bearer_auth(&admin)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let entries: Value = response.json().await.unwrap();
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["outcome"], "failure");
+    assert_eq!(entries[0]["client_ip"], "127.0.0.1");
+
+    let verify: Value = client
+        .get(build_address(&base, "admin/audit/verify"))
+        .bearer_auth(&admin)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(verify["valid"], true);
+    assert_eq!(verify["entries"], 2);
+
+    let _ = shutdown.send(());
+}
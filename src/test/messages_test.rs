@@ -0,0 +1,102 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde::Deserialize;
+use serde_json::{json, Value};
+use warp::Filter;
+
+use crate::config::BodyLimits;
+use crate::errors::error_codes::{ErrorCodes, ERROR_CODES};
+use crate::errors::messages::{load_dir, negotiate, render, BASE_LOCALE};
+use crate::errors::recover_localized;
+use crate::middleware::validator::{validated_json, validated_param, Validate};
+
+fn message(code: ErrorCodes, locale: &str, field: Option<&str>, args: &[(&'static str, String)]) -> String {
+    render(&ERROR_CODES.read().unwrap()[&code], locale, field, args)
+}
+
+#[test]
+fn accept_language_picks_the_preferred_supported_locale() {
+    assert_eq!(negotiate(Some("es"), "en"), "es");
+    assert_eq!(negotiate(Some("es-MX,en;q=0.5"), "en"), "es");
+    assert_eq!(negotiate(Some("de, en;q=0.2, es;q=0.8"), "en"), "es");
+    assert_eq!(negotiate(Some("es;q=0, en;q=0.1"), "en"), "en");
+    assert_eq!(negotiate(Some("de-AT"), "es"), "es");
+    assert_eq!(negotiate(Some("*"), BASE_LOCALE), BASE_LOCALE);
+    assert_eq!(negotiate(None, "es"), "es");
+}
+
+#[test]
+fn templates_are_filled_from_the_rule_arguments() {
+    let max = [("max", "32".to_string())];
+    assert_eq!(message(ErrorCodes::MaxSize, "en", Some("query"), &max), "query must not exceed 32");
+    assert_eq!(message(ErrorCodes::MaxSize, "es", Some("/displayName"), &max), "displayName no puede superar 32");
+    assert_eq!(message(ErrorCodes::NotNull, "es", None, &[]), "El valor no puede ser nulo");
+    // Unknown locales use English, and values are not expanded again.
+    let braces = [("max", "{field}".to_string())];
+    assert_eq!(message(ErrorCodes::MaxSize, "xx", Some("name"), &braces), "name must not exceed {field}");
+}
+
+#[test]
+fn catalogs_are_loaded_from_a_directory() {
+    let dir = std::env::temp_dir().join(format!("locales-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("PT.json"), r#"{ "messages": { "1002": "{field} não pode estar vazio" } }"#).unwrap();
+    std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+    assert_eq!(load_dir(dir.to_str().unwrap()), Ok(vec!["pt".to_string()]));
+    assert_eq!(negotiate(Some("pt-BR"), "en"), "pt");
+    assert_eq!(message(ErrorCodes::NotEmpty, "pt", Some("name"), &[]), "name não pode estar vazio");
+    // Codes the catalog leaves out fall back to English.
+    assert_eq!(message(ErrorCodes::NotNull, "pt", Some("name"), &[]), "name must not be null");
+
+    std::fs::write(dir.join("broken.json"), "{").unwrap();
+    let errors = load_dir(dir.to_str().unwrap()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("broken.json"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct ProfileDto {
+    #[validate(max_length(4, code = MaxSize))]
+    name: String,
+}
+
+#[tokio::test]
+async fn problems_are_answered_in_the_negotiated_language() {
+    let routes = warp::path("profiles")
+        .and(validated_json::<ProfileDto>(BodyLimits::default()))
+        .map(|profile: ProfileDto| profile.name)
+        .or(warp::path!("search" / ..)
+            .and(validated_param::<String, _>("query", |rule| rule.max_length(3).with_error_code(ErrorCodes::MaxSize)))
+            .map(|query: String| query));
+    let filter = recover_localized(routes, "en".to_string());
+    let post = |language: Option<&str>| {
+        let mut request = warp::test::request()
+            .method("POST")
+            .path("/profiles")
+            .json(&json!({ "name": "Augusta" }));
+        if let Some(language) = language {
+            request = request.header("accept-language", language);
+        }
+        request.reply(&filter)
+    };
+
+    let res = post(Some("es-ES,en;q=0.5")).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(res.headers()["content-language"], "es");
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["message"], "name no puede superar 4");
+
+    let res = post(None).await;
+    assert_eq!(res.headers()["content-language"], "en");
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["message"], "name must not exceed 4");
+
+    let res = warp::test::request()
+        .path("/search/long")
+        .header("accept-language", "es")
+        .reply(&filter)
+        .await;
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["message"], "query no puede superar 3");
+}
@@ -7,3 +7,23 @@ pub mod auth_repository_tests;
 pub mod auth_service_test;
 pub mod auth_controller_test;
 pub mod protected_controller_test;
+pub mod audit_service_test;
+pub mod session_cookie_test;
+pub mod oidc_test;
+pub mod dpop_test;
+pub mod scim_test;
+pub mod account_test;
+pub mod signature_test;
+pub mod webauthn_test;
+pub mod federation_test;
+pub mod device_test;
+pub mod password_policy_test;
+pub mod file_credentials_test;
+pub mod validate_test;
+pub mod validation_types_test;
+pub mod messages_test;
+pub mod openapi_test;
+pub mod body_test;
+pub mod cross_field_test;
+pub mod normalize_test;
+pub mod support;
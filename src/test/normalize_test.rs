@@ -0,0 +1,112 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use warp::Filter;
+
+use crate::config::BodyLimits;
+use crate::errors::error_codes::ErrorCodes;
+use crate::middleware::normalizer::{Normalize, Normalizer};
+use crate::middleware::validator::{validated_json, validated_query, Validate};
+use crate::test::support::handle_rejection;
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+struct SignupDto {
+    #[validate(normalize(trim, lowercase), not_empty(code = NotEmpty), email)]
+    email: String,
+    #[validate(normalize(strip_control, collapse_whitespace, trim), max_length(12, code = MaxSize))]
+    display_name: Option<String>,
+    #[validate(normalize(trim, lowercase), each(not_empty(code = NotEmpty)))]
+    tags: Vec<String>,
+    #[validate(nested)]
+    address: Option<AddressDto>,
+    #[validate(nested)]
+    contacts: HashMap<String, AddressDto>,
+    note: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct AddressDto {
+    #[validate(normalize(nfc, trim), max_length(8, code = MaxSize))]
+    city: String,
+}
+
+#[test]
+fn each_normalizer_rewrites_text() {
+    let apply = |normalizer: Normalizer, value: &str| normalizer.apply_to(value);
+    assert_eq!(apply(Normalizer::Trim, " \t hi there\n"), "hi there");
+    assert_eq!(apply(Normalizer::CollapseWhitespace, " hi \t\n  there "), " hi there ");
+    assert_eq!(apply(Normalizer::Nfc, "Cafe\u{301}"), "Caf\u{e9}");
+    assert_eq!(
+        apply(Normalizer::StripControl, "\u{feff}a\u{0}b\u{1b}[0m\u{200b}c\u{200d}\nd\te"),
+        "ab[0mc\nd\te"
+    );
+    assert_eq!(apply(Normalizer::Lowercase, "ÉLAN Vital"), "élan vital");
+
+    let mut values = vec![Some(" A ".to_string()), None];
+    values.normalize_with(&[Normalizer::Trim, Normalizer::Lowercase]);
+    assert_eq!(values, vec![Some("a".to_string()), None]);
+}
+
+#[test]
+fn derived_dtos_normalize_every_declared_field() {
+    let mut dto: SignupDto = serde_json::from_value(json!({
+        "email": "  Ana@Example.COM ",
+        "displayName": "\u{200b} Ana \t  María\u{7} ",
+        "tags": [" Rust ", "WEB"],
+        "address": { "city": " Sevilla\u{301} " },
+        "contacts": { "work": { "city": "Cadiz " } },
+        "note": "  kept as sent "
+    }))
+    .unwrap();
+    dto.normalize();
+
+    assert_eq!(dto.email, "ana@example.com");
+    assert_eq!(dto.display_name.as_deref(), Some("Ana María"));
+    assert_eq!(dto.tags, vec!["rust", "web"]);
+    assert_eq!(dto.address.as_ref().unwrap().city, "Sevill\u{e1}");
+    assert_eq!(dto.contacts["work"].city, "Cadiz");
+    assert_eq!(dto.note, "  kept as sent ");
+    assert!(dto.validate(None).is_ok());
+}
+
+#[tokio::test]
+async fn rules_run_on_the_normalized_body() {
+    let filter = warp::path("signups")
+        .and(validated_json::<SignupDto>(BodyLimits::default()))
+        .map(|dto: SignupDto| format!("{}|{}", dto.email, dto.display_name.unwrap_or_default()))
+        .recover(handle_rejection);
+    let post = |body: Value| warp::test::request().method("POST").path("/signups").json(&body).reply(&filter);
+
+    // Too long before collapsing, short enough after.
+    let res = post(json!({ "email": " ANA@example.com", "displayName": "  Ana    Maria ", "tags": [], "contacts": {}, "note": "" })).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body(), "ana@example.com|Ana Maria");
+
+    let res = post(json!({ "email": "   ", "tags": ["  "], "contacts": {}, "note": "" })).await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["field"], "/email");
+    assert_eq!(body["details"][0]["error_code"], ErrorCodes::NotEmpty as u16);
+    assert!(body["details"].as_array().unwrap().iter().any(|d| d["field"] == "/tags/0"));
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct FilterDto {
+    #[validate(normalize(trim, lowercase), one_of(&["asc", "desc"]))]
+    order: Option<String>,
+}
+
+#[tokio::test]
+async fn query_strings_are_normalized() {
+    let filter = warp::path("items")
+        .and(validated_query::<FilterDto>())
+        .map(|query: FilterDto| query.order.unwrap_or_default())
+        .recover(handle_rejection);
+
+    let res = warp::test::request().path("/items?order=%20DESC%20").reply(&filter).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body(), "desc");
+}
@@ -0,0 +1,288 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde_json::{json, Value};
+use std::sync::Arc;
+use warp::Filter;
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::services::jwt_service::{decode_unverified, from_jwk, verify_es256, Es256JwtService, JwtService};
+use crate::test::support::{body, handle_rejection, path};
+
+fn oidc_config() -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        issuer: "https://auth.example.com".to_string(),
+        token_audience: "example-api".to_string(),
+        ..Config::from_env()
+    })
+}
+
+/// Checks a token against the published key with its `kid`, the way a
+/// relying party would.
+fn verify(jwt: &impl JwtService, token: &str) -> Option<Value> {
+    let (header, _) = decode_unverified(token)?;
+    let kid = header.get("kid")?.as_str()?;
+    let jwk = jwt.jwks().keys.into_iter().find(|k| k.kid.as_deref() == Some(kid))?;
+    verify_es256(token, &from_jwk(&jwk)?)
+}
+
+#[test]
+fn sign_and_verify_across_rotation() {
+    let jwt = Es256JwtService::new();
+    let old = jwt.sign(&json!({ "sub": "admin" }));
+    assert_eq!(verify(&jwt, &old).unwrap()["sub"], "admin");
+
+    jwt.rotate();
+    assert_eq!(jwt.jwks().keys.len(), 2);
+    assert!(verify(&jwt, &old).is_some());
+
+    let new = jwt.sign(&json!({ "sub": "admin" }));
+    let (old_header, _) = decode_unverified(&old).unwrap();
+    let (new_header, _) = decode_unverified(&new).unwrap();
+    assert_ne!(old_header["kid"], new_header["kid"]);
+
+    jwt.rotate();
+    assert!(verify(&jwt, &old).is_none());
+    assert!(verify(&jwt, &new).is_some());
+}
+
+#[test]
+fn verify_rejects_tampered_tokens() {
+    let jwt = Es256JwtService::new();
+    let token = jwt.sign(&json!({ "sub": "admin" }));
+    let (signing_input, signature) = token.rsplit_once('.').unwrap();
+    let forged_claims = base64::Engine::encode(
+        &base64::engine::general_purpose::URL_SAFE_NO_PAD,
+        json!({ "sub": "mallory" }).to_string(),
+    );
+    let header = signing_input.split('.').next().unwrap();
+    let forged = format!("{}.{}.{}", header, forged_claims, signature);
+    assert!(verify(&jwt, &forged).is_none());
+    assert!(verify(&Es256JwtService::new(), &token).is_none());
+}
+
+#[tokio::test]
+async fn discovery_document_points_at_issuer() {
+    let config = oidc_config();
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/.well-known/openid-configuration")
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let doc = body(&res);
+    assert_eq!(doc["issuer"], "https://auth.example.com");
+    assert_eq!(doc["jwks_uri"], "https://auth.example.com/.well-known/jwks.json");
+    assert_eq!(
+        doc["token_endpoint"],
+        format!("https://auth.example.com{}", path(&config, "auth/token"))
+    );
+    assert_eq!(doc["id_token_signing_alg_values_supported"], json!(["ES256"]));
+    assert_eq!(
+        doc["grant_types_supported"],
+        json!(["user", "client", "webauthn", "urn:ietf:params:oauth:grant-type:device_code"])
+    );
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/.well-known/jwks.json")
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let jwks = body(&res);
+    assert_eq!(jwks["keys"][0]["kty"], "EC");
+    assert_eq!(jwks["keys"][0]["use"], "sig");
+}
+
+#[tokio::test]
+async fn openid_scope_returns_id_token() {
+    let config = oidc_config();
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&json!({
+            "grant_type": "user",
+            "username": "admin",
+            "password": "password",
+            "scope": "openid"
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let token = body(&res);
+    let id_token = token["id_token"].as_str().unwrap();
+    let (header, claims) = decode_unverified(id_token).unwrap();
+    assert_eq!(header["alg"], "ES256");
+    assert_eq!(claims["iss"], "https://auth.example.com");
+    assert_eq!(claims["aud"], "example-api");
+    assert_eq!(claims["sub"], "admin");
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/.well-known/jwks.json")
+        .reply(&filter)
+        .await;
+    let jwks = body(&res);
+    assert_eq!(jwks["keys"][0]["kid"], header["kid"]);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&json!({
+            "grant_type": "client",
+            "client_id": "client",
+            "client_secret": "secret",
+            "scope": "openid"
+        }))
+        .reply(&filter)
+        .await;
+    assert!(body(&res).get("id_token").is_none());
+}
+
+#[tokio::test]
+async fn introspect_and_revoke_tokens() {
+    let config = oidc_config();
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+    let issue = |request: Value| {
+        let filter = filter.clone();
+        let config = Arc::clone(&config);
+        async move {
+            let res = warp::test::request()
+                .method("POST")
+                .path(&path(&config, "auth/token"))
+                .json(&request)
+                .reply(&filter)
+                .await;
+            body(&res)
+        }
+    };
+    let call = |endpoint: &'static str, bearer: String, token: String| {
+        let filter = filter.clone();
+        let config = Arc::clone(&config);
+        async move {
+            warp::test::request()
+                .method("POST")
+                .path(&path(&config, endpoint))
+                .header("Authorization", format!("Bearer {}", bearer))
+                .json(&json!({ "token": token }))
+                .reply(&filter)
+                .await
+        }
+    };
+
+    let issued = issue(json!({
+        "grant_type": "user",
+        "username": "admin",
+        "password": "password",
+        "scope": "openid"
+    }))
+    .await;
+    let token = issued["token"].as_str().unwrap().to_string();
+    let id_token = issued["id_token"].as_str().unwrap().to_string();
+    let client = |scope: Option<&str>| {
+        let mut request = json!({ "grant_type": "client", "client_id": "client", "client_secret": "secret" });
+        if let Some(scope) = scope {
+            request["scope"] = json!(scope);
+        }
+        issue(request)
+    };
+    let introspector = client(Some("introspect")).await["token"].as_str().unwrap().to_string();
+    let plain = client(None).await["token"].as_str().unwrap().to_string();
+
+    let res = call("auth/introspect", introspector.clone(), token.clone()).await;
+    assert_eq!(res.status(), 200);
+    let introspection = body(&res);
+    assert_eq!(introspection["active"], true);
+    assert_eq!(introspection["sub"], "admin");
+    assert_eq!(introspection["scope"], "openid");
+
+    // ID tokens are never accepted as access tokens.
+    let res = call("auth/introspect", introspector.clone(), id_token).await;
+    assert_eq!(body(&res), json!({ "active": false }));
+
+    // Without the scope a client only sees its own tokens.
+    let res = call("auth/introspect", plain.clone(), token.clone()).await;
+    assert_eq!(body(&res), json!({ "active": false }));
+    let res = call("auth/introspect", plain.clone(), plain.clone()).await;
+    assert_eq!(body(&res)["sub"], "client");
+
+    // Only the holder or an admin revokes a token.
+    let res = call("auth/revoke", introspector.clone(), token.clone()).await;
+    assert_eq!(res.status(), 403);
+    let res = call("auth/revoke", token.clone(), plain.clone()).await;
+    assert_eq!(res.status(), 200);
+    let res = call("auth/introspect", introspector.clone(), plain.clone()).await;
+    assert_eq!(body(&res), json!({ "active": false }));
+
+    let res = call("auth/revoke", plain.clone(), token.clone()).await;
+    assert_eq!(res.status(), 401);
+    let res = call("auth/revoke", token.clone(), token.clone()).await;
+    assert_eq!(res.status(), 200);
+    let res = call("auth/introspect", introspector.clone(), token.clone()).await;
+    assert_eq!(body(&res), json!({ "active": false }));
+
+    let res = call("auth/revoke", introspector, "unknown".to_string()).await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn key_rotation_requires_admin() {
+    let config = oidc_config();
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+
+    let token_for = |body: Value| {
+        let filter = filter.clone();
+        let config = Arc::clone(&config);
+        async move {
+            let res = warp::test::request()
+                .method("POST")
+                .path(&path(&config, "auth/token"))
+                .json(&body)
+                .reply(&filter)
+                .await;
+            serde_json::from_slice::<Value>(res.body()).unwrap()["token"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        }
+    };
+    let client = token_for(json!({
+        "grant_type": "client",
+        "client_id": "client",
+        "client_secret": "secret"
+    }))
+    .await;
+    let admin = token_for(json!({
+        "grant_type": "user",
+        "username": "admin",
+        "password": "password"
+    }))
+    .await;
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "admin/keys/rotate"))
+        .header("Authorization", format!("Bearer {}", client))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 403);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "admin/keys/rotate"))
+        .header("Authorization", format!("Bearer {}", admin))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 204);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/.well-known/jwks.json")
+        .reply(&filter)
+        .await;
+    assert_eq!(body(&res)["keys"].as_array().unwrap().len(), 2);
+}
@@ -0,0 +1,95 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::OpenApi;
+
+use crate::errors::error_codes::ErrorCodes;
+use crate::middleware::validator::{Keyword, Validate};
+use crate::swagger::ApiDoc;
+
+fn spec() -> Value {
+    serde_json::to_value(ApiDoc::openapi()).unwrap()
+}
+
+#[test]
+fn dto_rules_become_json_schema_keywords() {
+    let spec = spec();
+    let dto = &spec["components"]["schemas"]["CreateMessageModelDto"];
+    assert_eq!(dto["required"], json!(["content"]));
+    assert_eq!(dto["properties"]["content"]["type"], "string");
+    assert_eq!(dto["properties"]["content"]["minLength"], 1);
+    assert_eq!(dto["properties"]["content"]["maxLength"], 32);
+
+    let reset = &spec["components"]["schemas"]["ResetPasswordDto"];
+    assert_eq!(reset["required"], json!(["token", "password"]));
+}
+
+#[test]
+fn operations_list_the_error_codes_of_their_body() {
+    let spec = spec();
+    let bad_request = &spec["paths"]["/api/v1/messages"]["post"]["responses"]["400"];
+    assert_eq!(bad_request["x-error-codes"], json!([1001, 1002, 1003]));
+    assert_eq!(
+        bad_request["description"],
+        "Bad request. Error codes: 1001 NotNull, 1002 NotEmpty, 1003 MaxSize"
+    );
+    // Operations without a validated body are left alone.
+    let listing = &spec["paths"]["/api/v1/messages"]["get"]["responses"]["400"];
+    assert!(listing.get("x-error-codes").is_none());
+
+    let search = &spec["paths"]["/api/v1/messages/{message}"]["get"]["parameters"][0]["schema"];
+    assert_eq!(search["maxLength"], 32);
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct LineDto {
+    #[validate(not_null, max_length(40))]
+    sku: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+struct OrderDto {
+    #[validate(within_range(1, 20, code = MaxSize))]
+    quantity: u8,
+    #[validate(one_of(&["eur", "usd"]), pattern("^[a-z]{3}$"))]
+    currency_code: String,
+    #[validate(email)]
+    contact: Option<String>,
+    #[validate(max_items(3), unique_items, each(not_empty(code = NotEmpty), max_length(10, code = MaxSize)))]
+    tags: Vec<String>,
+    #[validate(nested)]
+    lines: Vec<LineDto>,
+    #[validate(custom(|_: &String| None), custom(|_: &String| None, code = NotAllowedValue))]
+    note: String,
+}
+
+#[test]
+fn derived_schema_describes_every_rule() {
+    let schema = OrderDto::schema();
+    let field = |name: &str| schema.fields.iter().find(|f| f.field == name).unwrap();
+    assert_eq!(field("quantity").keywords, vec![Keyword::Minimum(1.0), Keyword::Maximum(20.0)]);
+    assert_eq!(
+        field("currencyCode").keywords,
+        vec![Keyword::Enum(vec!["eur".into(), "usd".into()]), Keyword::Pattern("^[a-z]{3}$".into())]
+    );
+    assert_eq!(field("contact").keywords, vec![Keyword::Format("email")]);
+    assert_eq!(field("tags").keywords, vec![Keyword::MaxLength(3), Keyword::UniqueItems]);
+    assert_eq!(field("tags").items, vec![Keyword::MinLength(1), Keyword::MaxLength(10)]);
+    assert!(schema.fields.iter().all(|f| f.field != "lines"));
+
+    assert_eq!(
+        schema.codes,
+        vec![
+            ErrorCodes::MaxSize,
+            ErrorCodes::NotAllowedValue,
+            ErrorCodes::PatternMismatch,
+            ErrorCodes::InvalidEmail,
+            ErrorCodes::TooManyItems,
+            ErrorCodes::DuplicateItems,
+            ErrorCodes::NotEmpty,
+            ErrorCodes::Nodeclared,
+        ]
+    );
+    assert_eq!(LineDto::schema().fields[0].keywords, vec![Keyword::Required, Keyword::MaxLength(40)]);
+}
@@ -0,0 +1,235 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde_json::{json, Value};
+use std::sync::Arc;
+use warp::Filter;
+
+use crate::config::{Config, PasswordPolicy};
+use crate::controllers::routes_with_mailer;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::models::mail_model::MailMessage;
+use crate::test::account_test::MemoryOutbox;
+use crate::validators::password_validator::{normalize_password, validate_password};
+use crate::test::support::{body, handle_rejection, path, post};
+
+fn codes(result: Result<String, ApiError>) -> Vec<ErrorCodes> {
+    match result {
+        Err(ApiError::MultipleErrors(Some(failures), field, _)) => {
+            assert_eq!(field.as_deref(), Some("password"));
+            failures.into_iter().map(|f| f.code).collect()
+        }
+        Err(e) => panic!("unexpected error {:?}", e),
+        Ok(_) => Vec::new(),
+    }
+}
+
+#[test]
+fn default_policy() {
+    let policy = PasswordPolicy::default();
+    assert_eq!(validate_password(&policy, "correct horse battery", "alice").unwrap(), "correct horse battery");
+    assert_eq!(codes(validate_password(&policy, "short", "alice")), vec![ErrorCodes::PasswordTooShort]);
+    assert_eq!(codes(validate_password(&policy, "Password123", "alice")), vec![ErrorCodes::PasswordTooCommon]);
+    assert_eq!(
+        codes(validate_password(&policy, "xx-ALICE-xx", "alice")),
+        vec![ErrorCodes::PasswordContainsUsername]
+    );
+    assert_eq!(
+        codes(validate_password(&policy, &"a".repeat(129), "alice")),
+        vec![ErrorCodes::PasswordTooLong]
+    );
+    // Length counts characters, not bytes.
+    assert!(validate_password(&policy, "ñandúñandú", "alice").is_ok());
+}
+
+#[test]
+fn character_classes_report_every_missing_class() {
+    let policy = PasswordPolicy {
+        require_uppercase: true,
+        require_lowercase: true,
+        require_digit: true,
+        require_symbol: true,
+        ..PasswordPolicy::default()
+    };
+    assert_eq!(
+        codes(validate_password(&policy, "lowercaseonly", "alice")),
+        vec![
+            ErrorCodes::PasswordMissingUppercase,
+            ErrorCodes::PasswordMissingDigit,
+            ErrorCodes::PasswordMissingSymbol
+        ]
+    );
+    assert!(validate_password(&policy, "Tr0ub4dor&3", "alice").is_ok());
+
+    let relaxed = PasswordPolicy {
+        min_length: 4,
+        disallow_username: false,
+        reject_common: false,
+        ..PasswordPolicy::default()
+    };
+    assert!(validate_password(&relaxed, "alice1", "alice").is_ok());
+    assert!(validate_password(&relaxed, "password", "alice").is_ok());
+}
+
+#[test]
+fn passwords_are_nfkc_normalized() {
+    // "é" precomposed and as "e" + combining acute accent.
+    assert_eq!(normalize_password("caf\u{e9}"), normalize_password("cafe\u{301}"));
+    // Full-width letters fold to ASCII.
+    assert_eq!(normalize_password("\u{ff30}\u{ff41}\u{ff53}\u{ff53}"), "Pass");
+    assert_eq!(
+        validate_password(&PasswordPolicy::default(), "cafe\u{301} con leche", "alice").unwrap(),
+        "caf\u{e9} con leche"
+    );
+}
+
+#[test]
+fn non_latin_passphrases_are_measured_in_characters() {
+    let policy = PasswordPolicy::default();
+    // 128 characters but 384 bytes of UTF-8.
+    let japanese = "ひらけごま".repeat(25) + "ひらけ";
+    assert!(validate_password(&policy, &japanese, "alice").is_ok());
+    assert_eq!(
+        codes(validate_password(&policy, &(japanese + "ご"), "alice")),
+        vec![ErrorCodes::PasswordTooLong]
+    );
+    assert!(validate_password(&policy, &"пароль-".repeat(18), "alice").is_ok());
+    assert!(validate_password(&policy, "كلمة السر", "alice").is_ok());
+    assert_eq!(codes(validate_password(&policy, "密码密码", "alice")), vec![ErrorCodes::PasswordTooShort]);
+    // The length is taken after NFKC, which expands "㍿" to four characters.
+    assert!(validate_password(&policy, &"㍿".repeat(32), "alice").is_ok());
+    assert_eq!(
+        codes(validate_password(&policy, &"㍿".repeat(33), "alice")),
+        vec![ErrorCodes::PasswordTooLong]
+    );
+}
+
+fn policy_config(policy: PasswordPolicy) -> (Arc<Config>, MemoryOutbox) {
+    dotenv::dotenv().ok();
+    let config = Arc::new(Config {
+        smtp_host: None,
+        password_policy: policy,
+        scim_client_secret: Some("scim secret".to_string()),
+        ..Config::from_env()
+    });
+    (config, MemoryOutbox::default())
+}
+
+fn error_codes(res: &warp::http::Response<warp::hyper::body::Bytes>) -> Vec<u64> {
+    body(res)["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["error_code"].as_u64().unwrap())
+        .collect()
+}
+
+fn filter(
+    config: &Arc<Config>,
+    outbox: &MemoryOutbox,
+) -> impl Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static {
+    use warp::Reply;
+    routes_with_mailer(Arc::clone(config), Arc::new(outbox.clone()))
+        .unwrap()
+        .recover(handle_rejection)
+        .map(Reply::into_response)
+}
+
+async fn scim_bearer<F>(filter: &F, config: &Config) -> String
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = post(
+        filter,
+        &path(config, "auth/token"),
+        None,
+        json!({ "grant_type": "client", "client_id": "scim", "client_secret": "scim secret", "scope": "scim" }),
+    )
+    .await;
+    format!("Bearer {}", body(&res)["token"].as_str().unwrap())
+}
+
+fn scim_user(username: &str, password: &str) -> Value {
+    json!({
+        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+        "userName": username,
+        "emails": [{ "value": format!("{}@example.com", username), "primary": true }],
+        "password": password
+    })
+}
+
+#[tokio::test]
+async fn scim_user_creation_and_password_change_follow_policy() {
+    let (config, outbox) = policy_config(PasswordPolicy {
+        require_digit: true,
+        ..PasswordPolicy::default()
+    });
+    let filter = filter(&config, &outbox);
+    let bearer = scim_bearer(&filter, &config).await;
+
+    let res = post(&filter, "/scim/v2/Users", Some(&bearer), scim_user("carol", "carol")).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(error_codes(&res), vec![1101, 1105, 1107]);
+    assert_eq!(body(&res)["details"][0]["field"], "password");
+
+    let res = post(&filter, "/scim/v2/Users", Some(&bearer), scim_user("carol", "long enough 4 me")).await;
+    assert_eq!(res.status(), 201);
+    let id = body(&res)["id"].as_str().unwrap().to_string();
+
+    let change = |password: &str| {
+        json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{ "op": "replace", "path": "password", "value": password }]
+        })
+    };
+    let res = warp::test::request()
+        .method("PATCH")
+        .path(&format!("/scim/v2/Users/{}", id))
+        .header("Authorization", &bearer)
+        .json(&change("letmein123"))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(error_codes(&res), vec![1108]);
+
+    // Accounts without a password, such as federated ones, are unaffected.
+    let mut user = scim_user("dave", "");
+    user.as_object_mut().unwrap().remove("password");
+    let res = post(&filter, "/scim/v2/Users", Some(&bearer), user).await;
+    assert_eq!(res.status(), 201);
+}
+
+#[tokio::test]
+async fn reset_keeps_the_code_when_the_policy_rejects_the_password() {
+    let (config, outbox) = policy_config(PasswordPolicy::default());
+    let filter = filter(&config, &outbox);
+    let bearer = scim_bearer(&filter, &config).await;
+    let res = post(&filter, "/scim/v2/Users", Some(&bearer), scim_user("erin", "first passphrase")).await;
+    assert_eq!(res.status(), 201);
+
+    let res = post(&filter, &path(&config, "auth/password/forgot"), None, json!({ "email": "erin@example.com" })).await;
+    assert_eq!(res.status(), 202);
+    let mail = outbox.delivered(1).await.remove(0);
+    let token = mail
+        .body
+        .lines()
+        .find_map(|line| line.strip_prefix("Reset code:"))
+        .unwrap()
+        .trim()
+        .to_string();
+
+    let reset = |password: &str| json!({ "token": token, "password": password });
+    let res = post(&filter, &path(&config, "auth/password/reset"), None, reset("erin-2024!")).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(error_codes(&res), vec![1107]);
+
+    let res = post(&filter, &path(&config, "auth/password/reset"), None, reset("second passphrase")).await;
+    assert_eq!(res.status(), 204);
+    let res = post(
+        &filter,
+        &path(&config, "auth/token"),
+        None,
+        json!({ "grant_type": "user", "username": "erin", "password": "second passphrase" }),
+    )
+    .await;
+    assert_eq!(res.status(), 200);
+}
@@ -0,0 +1,416 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde_json::{json, Value};
+use std::sync::Arc;
+use warp::Filter;
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::services::scim_filter::ScimFilter;
+use crate::test::support::{body, filter, handle_rejection, path};
+
+const PATCH_OP: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+
+fn scim_config() -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        issuer: "https://auth.example.com".to_string(),
+        scim_client_secret: Some("scim secret".to_string()),
+        ..Config::from_env()
+    })
+}
+
+async fn token<F>(filter: &F, config: &Config, request: Value) -> String
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(config, "auth/token"))
+        .json(&request)
+        .reply(filter)
+        .await;
+    body(&res)["token"].as_str().unwrap().to_string()
+}
+
+async fn scim_token<F>(filter: &F, config: &Config) -> String
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    token(
+        filter,
+        config,
+        json!({
+            "grant_type": "client",
+            "client_id": "scim",
+            "client_secret": "scim secret",
+            "scope": "scim"
+        }),
+    )
+    .await
+}
+
+#[test]
+fn filter_parses_and_matches() {
+    let user = json!({
+        "userName": "BJensen",
+        "externalId": "abc",
+        "active": true,
+        "emails": [{ "value": "bjensen@example.com" }, { "value": "babs@example.org" }]
+    });
+    let matches = |f: &str| ScimFilter::parse(f).unwrap().matches(&user);
+    assert!(matches(r#"userName eq "bjensen""#));
+    assert!(matches(r#"USERNAME sw "bj""#));
+    assert!(!matches(r#"externalId eq "ABC""#));
+    assert!(matches(r#"emails.value ew "example.org""#));
+    assert!(matches(r#"active eq true and userName co "jens""#));
+    assert!(matches(r#"userName eq "nobody" or externalId pr"#));
+    assert!(!matches(r#"displayName pr"#));
+    assert!(matches(r#"userName ne "other""#));
+    assert!(ScimFilter::parse(r#"userName gt "a""#).is_none());
+    assert!(ScimFilter::parse(r#"userName eq "unterminated"#).is_none());
+    assert!(ScimFilter::parse("userName eq").is_none());
+}
+
+#[tokio::test]
+async fn scim_requires_dedicated_scope() {
+    let config = scim_config();
+    let filter = filter(&config);
+
+    let plain = token(
+        &filter,
+        &config,
+        json!({ "grant_type": "client", "client_id": "client", "client_secret": "secret" }),
+    )
+    .await;
+    let res = warp::test::request()
+        .method("GET")
+        .path("/scim/v2/Users")
+        .header("Authorization", format!("Bearer {}", plain))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 403);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&json!({
+            "grant_type": "user",
+            "username": "admin",
+            "password": "password",
+            "scope": "scim"
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/scim/v2/ServiceProviderConfig")
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "application/scim+json");
+    assert_eq!(body(&res)["patch"]["supported"], true);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/scim/v2/Schemas")
+        .reply(&filter)
+        .await;
+    assert_eq!(body(&res)["totalResults"], 2);
+}
+
+#[tokio::test]
+async fn provision_users_and_log_in() {
+    let config = scim_config();
+    let filter = filter(&config);
+    let bearer = format!("Bearer {}", scim_token(&filter, &config).await);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/scim/v2/Users")
+        .header("Authorization", &bearer)
+        .header("content-type", "application/scim+json")
+        .json(&json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "bjensen",
+            "name": { "givenName": "Barbara", "familyName": "Jensen" },
+            "emails": [{ "value": "bjensen@example.com", "primary": true }],
+            "password": "t1meMa$heen"
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 201);
+    let created = body(&res);
+    let id = created["id"].as_str().unwrap().to_string();
+    assert!(created.get("password").is_none());
+    assert_eq!(
+        res.headers()["location"],
+        format!("https://auth.example.com/scim/v2/Users/{}", id).as_str()
+    );
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/scim/v2/Users")
+        .header("Authorization", &bearer)
+        .json(&json!({ "userName": "bjensen" }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 409);
+
+    let login = json!({ "grant_type": "user", "username": "bjensen", "password": "t1meMa$heen" });
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&login)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/scim/v2/Users?filter=userName%20eq%20%22BJENSEN%22")
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    let list = body(&res);
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], id.as_str());
+
+    let res = warp::test::request()
+        .method("PATCH")
+        .path(&format!("/scim/v2/Users/{}", id))
+        .header("Authorization", &bearer)
+        .json(&json!({
+            "schemas": [PATCH_OP],
+            "Operations": [
+                { "op": "replace", "path": "active", "value": false },
+                { "op": "add", "path": "name.givenName", "value": "Babs" }
+            ]
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let patched = body(&res);
+    assert_eq!(patched["active"], false);
+    assert_eq!(patched["name"]["givenName"], "Babs");
+    assert_eq!(patched["name"]["familyName"], "Jensen");
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&login)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 401);
+
+    let res = warp::test::request()
+        .method("PUT")
+        .path(&format!("/scim/v2/Users/{}", id))
+        .header("Authorization", &bearer)
+        .json(&json!({ "userName": "barbara", "active": true }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let replaced = body(&res);
+    assert_eq!(replaced["userName"], "barbara");
+    assert!(replaced.get("name").is_none());
+
+    let res = warp::test::request()
+        .method("PATCH")
+        .path(&format!("/scim/v2/Users/{}", id))
+        .header("Authorization", &bearer)
+        .json(&json!({ "Operations": [{ "op": "replace", "path": "active", "value": false }] }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+
+    let res = warp::test::request()
+        .method("DELETE")
+        .path(&format!("/scim/v2/Users/{}", id))
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 204);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&format!("/scim/v2/Users/{}", id))
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 404);
+
+    let res = warp::test::request()
+        .method("GET")
+        .path("/scim/v2/Users?filter=userName%20gt%20%22a%22")
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+}
+
+#[tokio::test]
+async fn manage_group_membership() {
+    let config = scim_config();
+    let filter = filter(&config);
+    let bearer = format!("Bearer {}", scim_token(&filter, &config).await);
+
+    let mut user_ids = Vec::new();
+    for name in ["alice", "bob"] {
+        let res = warp::test::request()
+            .method("POST")
+            .path("/scim/v2/Users")
+            .header("Authorization", &bearer)
+            .json(&json!({ "userName": name }))
+            .reply(&filter)
+            .await;
+        user_ids.push(body(&res)["id"].as_str().unwrap().to_string());
+    }
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/scim/v2/Groups")
+        .header("Authorization", &bearer)
+        .json(&json!({
+            "displayName": "Engineering",
+            "members": [{ "value": user_ids[0] }]
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 201);
+    let group = body(&res);
+    let group_id = group["id"].as_str().unwrap().to_string();
+    assert_eq!(group["members"][0]["display"], "alice");
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/scim/v2/Groups")
+        .header("Authorization", &bearer)
+        .json(&json!({ "displayName": "Ghosts", "members": [{ "value": "missing" }] }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+
+    let res = warp::test::request()
+        .method("PATCH")
+        .path(&format!("/scim/v2/Groups/{}", group_id))
+        .header("Authorization", &bearer)
+        .json(&json!({
+            "schemas": [PATCH_OP],
+            "Operations": [
+                { "op": "add", "path": "members", "value": [{ "value": user_ids[1] }] },
+                { "op": "remove", "path": format!("members[value eq \"{}\"]", user_ids[0]) }
+            ]
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let members = body(&res)["members"].clone();
+    assert_eq!(members, json!([{ "value": user_ids[1], "display": "bob" }]));
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&format!("/scim/v2/Users/{}", user_ids[1]))
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    assert_eq!(body(&res)["groups"][0]["display"], "Engineering");
+
+    warp::test::request()
+        .method("DELETE")
+        .path(&format!("/scim/v2/Users/{}", user_ids[1]))
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    let res = warp::test::request()
+        .method("GET")
+        .path("/scim/v2/Groups?filter=displayName%20eq%20%22engineering%22")
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    let list = body(&res);
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["members"], json!([]));
+
+    let res = warp::test::request()
+        .method("DELETE")
+        .path(&format!("/scim/v2/Groups/{}", group_id))
+        .header("Authorization", &bearer)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 204);
+}
+
+#[tokio::test]
+async fn bodies_are_checked_against_the_store_before_the_handler() {
+    let config = scim_config();
+    let filter = filter(&config);
+    let bearer = format!("Bearer {}", scim_token(&filter, &config).await);
+    let post = |endpoint: &str, request: Value| {
+        warp::test::request()
+            .method("POST")
+            .path(endpoint)
+            .header("Authorization", &bearer)
+            .json(&request)
+            .reply(&filter)
+    };
+
+    let res = post("/scim/v2/Users", json!({ "userName": "admin" })).await;
+    assert_eq!(res.status(), 409);
+    assert_eq!(body(&res)["instance"], "scim/v2/Users");
+    assert_eq!(body(&res)["details"][0]["field"], "/userName");
+    assert_eq!(body(&res)["details"][0]["error_code"], 3004);
+
+    let res = post("/scim/v2/Users", json!({ "userName": "" })).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(body(&res)["details"][0]["error_code"], 3003);
+
+    let res = post(
+        "/scim/v2/Groups",
+        json!({ "displayName": "", "members": [{ "value": "missing-1" }, { "value": "missing-2" }] }),
+    )
+    .await;
+    assert_eq!(res.status(), 400);
+    let details = body(&res)["details"].as_array().unwrap().clone();
+    let fields: Vec<&str> = details.iter().map(|d| d["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, vec!["/displayName", "/members/0/value", "/members/1/value"]);
+}
+
+#[tokio::test]
+async fn admin_accounts_are_out_of_reach() {
+    let config = scim_config();
+    let filter = filter(&config);
+    let bearer = format!("Bearer {}", scim_token(&filter, &config).await);
+    let request = |method: &str, endpoint: &str, request: Value| {
+        warp::test::request()
+            .method(method)
+            .path(endpoint)
+            .header("Authorization", &bearer)
+            .json(&request)
+            .reply(&filter)
+    };
+
+    let res = request("GET", "/scim/v2/Users?filter=userName%20eq%20%22admin%22", Value::Null).await;
+    let admin = format!("/scim/v2/Users/{}", body(&res)["Resources"][0]["id"].as_str().unwrap());
+    let res = request(
+        "PATCH",
+        &admin,
+        json!({
+            "schemas": [PATCH_OP],
+            "Operations": [{ "op": "replace", "path": "password", "value": "taken over by scim" }]
+        }),
+    )
+    .await;
+    assert_eq!(res.status(), 403);
+    let res = request("DELETE", &admin, Value::Null).await;
+    assert_eq!(res.status(), 403);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&json!({ "grant_type": "user", "username": "admin", "password": "password" }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+}
@@ -0,0 +1,149 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::test::support::{handle_rejection, path};
+use warp::Filter;
+
+fn cookie_config(enabled: bool) -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        session_cookies: enabled,
+        session_cookie_secure: true,
+        session_cookie_same_site: "Strict".to_string(),
+        ..Config::from_env()
+    })
+}
+
+fn cookie_value(set_cookie: &str) -> String {
+    set_cookie
+        .split(';')
+        .next()
+        .and_then(|pair| pair.split_once('='))
+        .map(|(_, value)| value.to_string())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn login_sets_cookies_and_authorizes_requests() {
+    let config = cookie_config(true);
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/login"))
+        .json(&serde_json::json!({
+            "grant_type": "user",
+            "username": "admin",
+            "password": "password"
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+
+    let cookies: Vec<String> = res
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|v| v.to_str().unwrap().to_string())
+        .collect();
+    let session_cookie = cookies.iter().find(|c| c.starts_with("session=")).unwrap();
+    assert!(session_cookie.contains("HttpOnly"));
+    assert!(session_cookie.contains("Secure"));
+    assert!(session_cookie.contains("SameSite=Strict"));
+    let csrf_cookie = cookies.iter().find(|c| c.starts_with("csrf_token=")).unwrap();
+    assert!(!csrf_cookie.contains("HttpOnly"));
+
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    let csrf = body["csrf_token"].as_str().unwrap().to_string();
+    assert_eq!(cookie_value(csrf_cookie), csrf);
+    let session = cookie_value(session_cookie);
+
+    let protected = warp::test::request()
+        .method("GET")
+        .path(&path(&config, "protected"))
+        .header("cookie", format!("session={}", session))
+        .reply(&filter)
+        .await;
+    assert_eq!(protected.status(), 200);
+
+    let without_csrf = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/logout"))
+        .header("cookie", format!("session={}", session))
+        .reply(&filter)
+        .await;
+    assert_eq!(without_csrf.status(), 403);
+
+    let wrong_csrf = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/logout"))
+        .header("cookie", format!("session={}", session))
+        .header("x-csrf-token", "forged")
+        .reply(&filter)
+        .await;
+    assert_eq!(wrong_csrf.status(), 403);
+
+    let logout = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/logout"))
+        .header("cookie", format!("session={}", session))
+        .header("x-csrf-token", csrf)
+        .reply(&filter)
+        .await;
+    assert_eq!(logout.status(), 204);
+    assert!(logout
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .all(|v| v.to_str().unwrap().contains("Max-Age=0")));
+
+    let after = warp::test::request()
+        .method("GET")
+        .path(&path(&config, "protected"))
+        .header("cookie", format!("session={}", session))
+        .reply(&filter)
+        .await;
+    assert_eq!(after.status(), 401);
+}
+
+#[tokio::test]
+async fn cookies_are_ignored_when_session_mode_is_disabled() {
+    let config = cookie_config(false);
+    let filter = routes(Arc::clone(&config)).unwrap().recover(handle_rejection);
+
+    let login = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/login"))
+        .json(&serde_json::json!({
+            "grant_type": "user",
+            "username": "admin",
+            "password": "password"
+        }))
+        .reply(&filter)
+        .await;
+    assert_eq!(login.status(), 404);
+
+    let token = warp::test::request()
+        .method("POST")
+        .path(&path(&config, "auth/token"))
+        .json(&serde_json::json!({
+            "grant_type": "user",
+            "username": "admin",
+            "password": "password"
+        }))
+        .reply(&filter)
+        .await;
+    let body: Value = serde_json::from_slice(token.body()).unwrap();
+    let token = body["token"].as_str().unwrap();
+
+    let protected = warp::test::request()
+        .method("GET")
+        .path(&path(&config, "protected"))
+        .header("cookie", format!("session={}", token))
+        .reply(&filter)
+        .await;
+    assert_eq!(protected.status(), 401);
+}
@@ -0,0 +1,228 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use chrono::Utc;
+use dotenv::dotenv;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+use tokio::time::{sleep, Duration};
+
+use crate::config;
+use crate::errors::ApiError;
+use crate::server::run_server;
+use crate::services::signature_service::{
+    sign, SignatureVerifier, SignedRequest, NONCE_HEADER, SIGNATURE_SCHEME, TIMESTAMP_HEADER,
+};
+
+/// Adds a fresh timestamp and nonce to `request` and signs it for `client_id`,
+/// covering the headers named in `signed_headers`. `host` is filled in from
+/// the URL when it is signed but not set.
+#[cfg(test)]
+pub fn sign_reqwest(request: &mut reqwest::Request, client_id: &str, secret: &str, signed_headers: &[&str]) {
+    let timestamp = Utc::now().timestamp();
+    let nonce = uuid::Uuid::new_v4().to_string();
+    let url = request.url().clone();
+    let headers = request.headers_mut();
+    headers.insert(TIMESTAMP_HEADER, timestamp.to_string().parse().unwrap());
+    headers.insert(NONCE_HEADER, nonce.parse().unwrap());
+    if signed_headers.contains(&"host") && !headers.contains_key("host") {
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap(), port),
+            None => url.host_str().unwrap().to_string(),
+        };
+        headers.insert("host", host.parse().unwrap());
+    }
+    let signed = SignedRequest {
+        client_id: client_id.to_string(),
+        signature: String::new(),
+        timestamp,
+        nonce,
+        method: request.method().to_string(),
+        path: url.path().to_string(),
+        query: url.query().unwrap_or_default().to_string(),
+        headers: signed_headers
+            .iter()
+            .map(|name| (name.to_string(), request.headers()[*name].to_str().unwrap().to_string()))
+            .collect(),
+        body: request.body().and_then(|b| b.as_bytes()).unwrap_or_default().to_vec(),
+    };
+    let authorization = format!(
+        "{} Credential={}, SignedHeaders={}, Signature={}",
+        SIGNATURE_SCHEME,
+        client_id,
+        signed_headers.join(";"),
+        sign(secret, &signed.canonical())
+    );
+    request
+        .headers_mut()
+        .insert("authorization", authorization.parse().unwrap());
+}
+
+async fn spawn_server() -> (oneshot::Sender<()>, String) {
+    std::env::set_var("PORT", "0");
+    let (shutdown, base) = run_server().await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    (shutdown, base)
+}
+
+fn build_address(base: &str, endpoint: &str) -> String {
+    dotenv().ok();
+    let config = Arc::new(config::Config::from_env());
+    format!("{}/{}/{}", base, config.api_base, endpoint)
+}
+
+fn signed(secret: &str, timestamp: i64, nonce: &str, body: &[u8]) -> SignedRequest {
+    let mut request = SignedRequest {
+        client_id: "client".to_string(),
+        signature: String::new(),
+        timestamp,
+        nonce: nonce.to_string(),
+        method: "POST".to_string(),
+        path: "/api/v1/jobs".to_string(),
+        query: "b=2&a=1".to_string(),
+        headers: vec![("content-type".to_string(), "application/json".to_string())],
+        body: body.to_vec(),
+    };
+    request.signature = sign(secret, &request.canonical());
+    request
+}
+
+#[test]
+fn parses_authorization_header() {
+    let (client, headers, signature) =
+        SignedRequest::parse_authorization("HMAC-SHA256 Credential=client, SignedHeaders=Host;X-Request-Id, Signature=ABCD")
+            .unwrap();
+    assert_eq!(client, "client");
+    assert_eq!(headers, vec!["host", "x-request-id"]);
+    assert_eq!(signature, "abcd");
+    assert!(SignedRequest::parse_authorization("Bearer abc").is_none());
+    assert!(SignedRequest::parse_authorization("HMAC-SHA256 Credential=client").is_none());
+}
+
+#[test]
+fn canonical_form_sorts_query_and_hashes_body() {
+    let request = signed("secret", 1_700_000_000, "n-1", b"{}");
+    let canonical = request.canonical();
+    let lines: Vec<&str> = canonical.lines().collect();
+    assert_eq!(&lines[..8], &[
+        "HMAC-SHA256",
+        "1700000000",
+        "n-1",
+        "POST",
+        "/api/v1/jobs",
+        "a=1&b=2",
+        "content-type:application/json",
+        "content-type",
+    ]);
+    assert_eq!(lines[8], "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a");
+}
+
+#[test]
+fn verifier_accepts_once_and_rejects_tampering() {
+    let verifier = SignatureVerifier::new(60);
+    let now = Utc::now().timestamp();
+    let request = signed("secret", now, "n-1", b"{\"job\":1}");
+    assert!(verifier.verify(&request, "secret").is_ok());
+    assert!(matches!(verifier.verify(&request, "secret"), Err(ApiError::InvalidSignature)));
+
+    let mut tampered = signed("secret", now, "n-2", b"{\"job\":1}");
+    tampered.body = b"{\"job\":2}".to_vec();
+    assert!(verifier.verify(&tampered, "secret").is_err());
+
+    let mut tampered = signed("secret", now, "n-3", b"");
+    tampered.query = "a=1&b=3".to_string();
+    assert!(verifier.verify(&tampered, "secret").is_err());
+
+    assert!(verifier.verify(&signed("other", now, "n-4", b""), "secret").is_err());
+}
+
+#[test]
+fn verifier_enforces_clock_skew() {
+    let verifier = SignatureVerifier::new(60);
+    let now = Utc::now().timestamp();
+    assert!(verifier.verify(&signed("secret", now - 50, "early", b""), "secret").is_ok());
+    assert!(verifier.verify(&signed("secret", now + 50, "late", b""), "secret").is_ok());
+    assert!(verifier.verify(&signed("secret", now - 120, "stale", b""), "secret").is_err());
+    assert!(verifier.verify(&signed("secret", now + 120, "future", b""), "secret").is_err());
+    for timestamp in [i64::MIN, i64::MIN + 1, i64::MAX] {
+        assert!(verifier.verify(&signed("secret", timestamp, "extreme", b""), "secret").is_err());
+    }
+}
+
+#[tokio::test]
+async fn signed_requests_reach_protected_routes() {
+    let (shutdown, base) = spawn_server().await;
+    let client = reqwest::Client::new();
+    let address = format!("{}?b=2&a=1", build_address(&base, "protected"));
+
+    let mut request = client
+        .get(&address)
+        .header("x-request-id", "job-42")
+        .build()
+        .unwrap();
+    sign_reqwest(&mut request, "client", "secret", &["host", "x-request-id"]);
+    let replay = request.try_clone().unwrap();
+    let response = client.execute(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = client.execute(replay).await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let mut request = client.get(&address).build().unwrap();
+    sign_reqwest(&mut request, "client", "wrong-secret", &[]);
+    assert_eq!(client.execute(request).await.unwrap().status(), 401);
+
+    let mut request = client.get(&address).build().unwrap();
+    sign_reqwest(&mut request, "client", "secret", &[]);
+    *request.url_mut() = format!("{}?b=2&a=2", build_address(&base, "protected")).parse().unwrap();
+    assert_eq!(client.execute(request).await.unwrap().status(), 401);
+
+    let _ = shutdown.send(());
+}
+
+#[tokio::test]
+async fn signed_bodies_reach_the_handler() {
+    let (shutdown, base) = spawn_server().await;
+    let client = reqwest::Client::new();
+    let address = build_address(&base, "protected");
+    let document = serde_json::json!({ "job": 42, "tags": ["nightly"] });
+
+    let mut request = client.post(&address).json(&document).build().unwrap();
+    sign_reqwest(&mut request, "client", "secret", &["content-type"]);
+    let response = client.execute(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["document"], document);
+
+    let mut request = client.post(&address).json(&document).build().unwrap();
+    sign_reqwest(&mut request, "client", "secret", &["content-type"]);
+    *request.body_mut() = Some(serde_json::to_vec(&serde_json::json!({ "job": 43 })).unwrap().into());
+    assert_eq!(client.execute(request).await.unwrap().status(), 401);
+
+    let mut request = client
+        .post(&address)
+        .header("content-type", "application/json")
+        .body("{\"job\":")
+        .build()
+        .unwrap();
+    sign_reqwest(&mut request, "client", "secret", &[]);
+    assert_eq!(client.execute(request).await.unwrap().status(), 400);
+
+    let mut request = client.post(&address).body("job=42").build().unwrap();
+    sign_reqwest(&mut request, "client", "secret", &[]);
+    assert_eq!(client.execute(request).await.unwrap().status(), 415);
+
+    // Bodies past MAX_BODY_BYTES are refused before the signature is checked.
+    dotenv().ok();
+    let oversized = vec![b' '; config::Config::from_env().body_limits.max_bytes + 1];
+    for method in [reqwest::Method::GET, reqwest::Method::POST] {
+        let mut request = client
+            .request(method, &address)
+            .header("content-type", "application/json")
+            .body(oversized.clone())
+            .build()
+            .unwrap();
+        sign_reqwest(&mut request, "client", "secret", &["content-type"]);
+        assert_eq!(client.execute(request).await.unwrap().status(), 413);
+    }
+
+    let _ = shutdown.send(());
+}
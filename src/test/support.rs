@@ -0,0 +1,63 @@
+//! Helpers shared by the tests that drive the full route tree.
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::Arc;
+use warp::hyper::body::Bytes;
+use warp::{Filter, Rejection, Reply};
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::errors::{messages, problem_response};
+
+/// Answers a rejection with a problem response in English, for tests that
+/// recover without `recover_localized`.
+pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
+    Ok(problem_response(&err, messages::BASE_LOCALE))
+}
+
+pub fn path(config: &Config, endpoint: &str) -> String {
+    format!("/{}/{}", config.api_base.trim_matches('/'), endpoint)
+}
+
+pub fn body(res: &warp::http::Response<Bytes>) -> Value {
+    serde_json::from_slice(res.body()).unwrap()
+}
+
+/// All routes of `config`, with rejections answered as problems.
+pub fn filter(config: &Arc<Config>) -> impl Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static {
+    routes(Arc::clone(config)).unwrap()
+        .recover(handle_rejection)
+        .map(Reply::into_response)
+}
+
+pub async fn get<F>(filter: &F, path: &str, bearer: Option<&str>) -> warp::http::Response<Bytes>
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let mut builder = warp::test::request().method("GET").path(path);
+    if let Some(bearer) = bearer {
+        builder = builder.header("Authorization", bearer);
+    }
+    builder.reply(filter).await
+}
+
+pub async fn post<F>(filter: &F, path: &str, bearer: Option<&str>, request: Value) -> warp::http::Response<Bytes>
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let mut builder = warp::test::request().method("POST").path(path).json(&request);
+    if let Some(bearer) = bearer {
+        builder = builder.header("Authorization", bearer);
+    }
+    builder.reply(filter).await
+}
+
+/// Exchanges a token `request` at `auth/token` for an `Authorization` value.
+pub async fn bearer<F>(filter: &F, config: &Config, request: Value) -> String
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = post(filter, &path(config, "auth/token"), None, request).await;
+    assert_eq!(res.status(), 200);
+    format!("Bearer {}", body(&res)["token"].as_str().unwrap())
+}
@@ -0,0 +1,523 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use serde::Deserialize;
+use serde_json::{json, Value};
+use warp::Filter;
+
+use crate::config::BodyLimits;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::middleware::validator::{validated_json, Rule, Validate, Validator};
+use crate::test::support::handle_rejection;
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+struct InviteDto {
+    #[validate(not_null(code = NotNull), not_empty(code = NotEmpty), max_length(8, code = MaxSize))]
+    display_name: Option<String>,
+    #[serde(rename = "seats")]
+    #[validate(within_range(1, 10, code = MaxSize))]
+    seat_count: u32,
+    note: Option<String>,
+}
+
+type FieldErrors = Vec<(Option<String>, Vec<ErrorCodes>)>;
+
+fn failure(result: Result<(), ApiError>) -> (FieldErrors, Option<String>) {
+    match result {
+        Err(ApiError::FieldErrors(fields, instance)) => {
+            let fields = fields
+                .into_iter()
+                .map(|(field, failures)| (field, failures.into_iter().map(|f| f.code).collect()))
+                .collect();
+            (fields, instance)
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+fn field(name: &str, codes: Vec<ErrorCodes>) -> (Option<String>, Vec<ErrorCodes>) {
+    (Some(name.to_string()), codes)
+}
+
+fn invite(name: Option<&str>, seats: u32) -> InviteDto {
+    InviteDto {
+        display_name: name.map(str::to_string),
+        seat_count: seats,
+        note: None,
+    }
+}
+
+#[test]
+fn derived_rules_use_json_field_names() {
+    assert!(invite(Some("team"), 3).validate(None).is_ok());
+
+    let (fields, instance) = failure(invite(None, 3).validate(Some("api/v1/invites".to_string())));
+    assert_eq!(fields, vec![field("/displayName", vec![ErrorCodes::NotNull])]);
+    assert_eq!(instance.as_deref(), Some("api/v1/invites"));
+
+    let (fields, _) = failure(invite(Some(""), 3).validate(None));
+    assert_eq!(fields, vec![field("/displayName", vec![ErrorCodes::NotEmpty])]);
+    let (fields, _) = failure(invite(Some("a long name"), 3).validate(None));
+    assert_eq!(fields, vec![field("/displayName", vec![ErrorCodes::MaxSize])]);
+
+    let (fields, _) = failure(invite(Some("team"), 11).validate(None));
+    assert_eq!(fields, vec![field("/seats", vec![ErrorCodes::MaxSize])]);
+}
+
+#[test]
+fn every_invalid_field_is_reported() {
+    let (fields, _) = failure(invite(Some(""), 0).validate(None));
+    assert_eq!(
+        fields,
+        vec![
+            field("/displayName", vec![ErrorCodes::NotEmpty]),
+            field("/seats", vec![ErrorCodes::MaxSize]),
+        ]
+    );
+
+    let name = "team".to_string();
+    let empty = String::new();
+    let result = Validator::new(None)
+        .rule(Rule::new(Some(&name), Some("name".to_string()), None).not_empty().with_error_code(ErrorCodes::NotEmpty))
+        .rule(
+            Rule::new(Some(&empty), Some("code".to_string()), None)
+                .not_empty()
+                .with_error_code(ErrorCodes::NotEmpty)
+                .min_length(2)
+                .with_error_code(ErrorCodes::PasswordTooShort),
+        )
+        .rule(Rule::<String>::new(None, Some("owner".to_string()), None).not_null().with_error_code(ErrorCodes::NotNull))
+        .check();
+    let (fields, _) = failure(result);
+    assert_eq!(
+        fields,
+        vec![
+            field("/code", vec![ErrorCodes::NotEmpty, ErrorCodes::PasswordTooShort]),
+            field("/owner", vec![ErrorCodes::NotNull]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn validated_json_rejects_invalid_bodies() {
+    let filter = warp::path("invites")
+        .and(validated_json::<InviteDto>(BodyLimits::default()))
+        .map(|dto: InviteDto| dto.display_name.unwrap_or_default())
+        .recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/invites")
+        .json(&json!({ "displayName": "team", "seats": 2 }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body(), "team");
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/invites")
+        .json(&json!({ "displayName": "", "seats": 2 }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["instance"], "invites");
+    assert_eq!(body["details"][0]["field"], "/displayName");
+    assert_eq!(body["details"][0]["error_code"], 1002);
+}
+
+#[tokio::test]
+async fn one_problem_response_lists_every_field() {
+    let filter = warp::path("invites")
+        .and(validated_json::<InviteDto>(BodyLimits::default()))
+        .map(|dto: InviteDto| dto.display_name.unwrap_or_default())
+        .recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/invites")
+        .json(&json!({ "seats": 20 }))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["title"], "Multiple validation errors");
+    assert_eq!(body["instance"], "invites");
+    assert_eq!(body["details"][0]["field"], "/displayName");
+    assert_eq!(body["details"][0]["error_code"], 1001);
+    assert_eq!(body["details"][1]["field"], "/seats");
+    assert_eq!(body["details"][1]["error_code"], 1003);
+    assert_eq!(body["details"].as_array().unwrap().len(), 2);
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct ProfileDto {
+    #[validate(min_length(3), pattern("^[a-z0-9_]+$"))]
+    handle: String,
+    #[validate(email)]
+    email: Option<String>,
+    #[validate(url, starts_with("https://"))]
+    website: Option<String>,
+    #[validate(uuid)]
+    team_id: Option<String>,
+    #[validate(one_of(&["admin", "member", "guest"]))]
+    role: Option<String>,
+    #[validate(iso_date)]
+    birthday: Option<String>,
+    #[validate(iso_datetime)]
+    joined_at: Option<String>,
+    #[validate(ends_with(".pub"), contains("ssh"))]
+    key_file: Option<String>,
+}
+
+fn profile() -> ProfileDto {
+    ProfileDto {
+        handle: "ada_l".to_string(),
+        email: Some("ada@example.com".to_string()),
+        website: Some("https://ada.example.com/about".to_string()),
+        team_id: Some("67e55044-10b1-426f-9247-bb680e5fe0c8".to_string()),
+        role: Some("member".to_string()),
+        birthday: Some("1815-12-10".to_string()),
+        joined_at: Some("2024-05-01T13:45:00+02:00".to_string()),
+        key_file: Some("id_ssh_ed25519.pub".to_string()),
+    }
+}
+
+#[test]
+fn rule_library_accepts_valid_values() {
+    assert!(profile().validate(None).is_ok());
+    // Missing optional values are only checked by not_null.
+    let empty = ProfileDto {
+        email: None,
+        website: None,
+        team_id: None,
+        role: None,
+        birthday: None,
+        joined_at: None,
+        key_file: None,
+        ..profile()
+    };
+    assert!(empty.validate(None).is_ok());
+}
+
+#[test]
+fn rule_library_reports_its_own_codes() {
+    let invalid = ProfileDto {
+        handle: "Ad".to_string(),
+        email: Some("ada@".to_string()),
+        website: Some("http://".to_string()),
+        team_id: Some("67e55044-10b1-426f-9247".to_string()),
+        role: Some("Admin".to_string()),
+        birthday: Some("1815-13-10".to_string()),
+        joined_at: Some("2024-05-01 13:45:00".to_string()),
+        key_file: Some("id_rsa".to_string()),
+    };
+    let (fields, _) = failure(invalid.validate(None));
+    assert_eq!(
+        fields,
+        vec![
+            field("/handle", vec![ErrorCodes::MinSize, ErrorCodes::PatternMismatch]),
+            field("/email", vec![ErrorCodes::InvalidEmail]),
+            field("/website", vec![ErrorCodes::InvalidUrl, ErrorCodes::MissingPrefix]),
+            field("/team_id", vec![ErrorCodes::InvalidUuid]),
+            field("/role", vec![ErrorCodes::NotAllowedValue]),
+            field("/birthday", vec![ErrorCodes::InvalidDate]),
+            field("/joined_at", vec![ErrorCodes::InvalidDateTime]),
+            field("/key_file", vec![ErrorCodes::MissingSuffix, ErrorCodes::MissingSubstring]),
+        ]
+    );
+
+    // An explicit code replaces the rule's own.
+    let website = "ftp.example.com".to_string();
+    let result = Rule::new(Some(&website), Some("website".to_string()), None)
+        .url()
+        .with_error_code(ErrorCodes::NotEmpty)
+        .check();
+    assert!(matches!(result, Err(ApiError::MultipleErrors(Some(codes), _, _)) if codes == vec![ErrorCodes::NotEmpty]));
+}
+
+#[test]
+fn is_number_rejects_nan_and_infinity() {
+    for value in [f64::NAN, f64::INFINITY] {
+        let result = Rule::new(Some(&value), None, None).is_number().with_error_code(ErrorCodes::NotNull).check();
+        assert!(result.is_err());
+    }
+    assert!(Rule::new(Some(&1.5), None, None).is_number().check().is_ok());
+}
+
+fn no_spaces(value: &str) -> Option<ErrorCodes> {
+    value.contains(' ').then_some(ErrorCodes::PatternMismatch)
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct SlugDto {
+    #[validate(custom(|slug: &String| no_spaces(slug)))]
+    slug: String,
+}
+
+fn username_rule(value: &String, taken: std::sync::Arc<Vec<String>>) -> Rule<'_, String> {
+    Rule::new(Some(value), Some("username".to_string()), None)
+        .custom(|v: &String| (v.len() > 8).then_some(ErrorCodes::MaxSize))
+        .custom_async(move |v: &String| {
+            let taken = std::sync::Arc::clone(&taken);
+            let v = v.clone();
+            async move { taken.contains(&v).then_some(ErrorCodes::ScimUniqueness) }
+        })
+}
+
+#[tokio::test]
+async fn custom_and_async_rules() {
+    assert!(SlugDto { slug: "a-b".to_string() }.validate(None).is_ok());
+    let (fields, _) = failure(SlugDto { slug: "a b".to_string() }.validate(None));
+    assert_eq!(fields, vec![field("/slug", vec![ErrorCodes::PatternMismatch])]);
+
+    let taken = std::sync::Arc::new(vec!["admin".to_string()]);
+    let username = |name: &str| name.to_string();
+    let rule = |value| username_rule(value, std::sync::Arc::clone(&taken));
+
+    let free = username("ada");
+    assert!(rule(&free).check_async().await.is_ok());
+    let admin = username("admin");
+    // The sync check skips async rules.
+    assert!(rule(&admin).check().is_ok());
+    assert!(matches!(
+        rule(&admin).check_async().await,
+        Err(ApiError::MultipleErrors(Some(codes), _, _)) if codes == vec![ErrorCodes::ScimUniqueness]
+    ));
+
+    let long = username("a very long name");
+    let (fields, _) = failure(
+        Validator::new(None)
+            .rule_async(rule(&admin))
+            .await
+            .rule_async(rule(&long).custom_async(|_: &String| async { Some(ErrorCodes::NotEmpty) }).with_error_code(ErrorCodes::NotNull))
+            .await
+            .check(),
+    );
+    assert_eq!(
+        fields,
+        vec![
+            field("/username", vec![ErrorCodes::ScimUniqueness]),
+            field("/username", vec![ErrorCodes::MaxSize, ErrorCodes::NotNull]),
+        ]
+    );
+}
+
+macro_rules! cased_dto {
+    ($name:ident, $($rule:tt)*) => {
+        #[derive(Debug, Deserialize, Validate)]
+        #[serde($($rule)*)]
+        struct $name {
+            #[validate(not_empty(code = NotEmpty))]
+            display_name: String,
+        }
+    };
+}
+
+cased_dto!(LowerDto, rename_all = "lowercase");
+cased_dto!(UpperDto, rename_all = "UPPERCASE");
+cased_dto!(PascalDto, rename_all = "PascalCase");
+cased_dto!(CamelDto, rename_all = "camelCase");
+cased_dto!(SnakeDto, rename_all = "snake_case");
+cased_dto!(ScreamingSnakeDto, rename_all = "SCREAMING_SNAKE_CASE");
+cased_dto!(KebabDto, rename_all = "kebab-case");
+cased_dto!(ScreamingKebabDto, rename_all = "SCREAMING-KEBAB-CASE");
+cased_dto!(DeserializeOnlyDto, rename_all(serialize = "camelCase", deserialize = "kebab-case"));
+
+/// Reads an empty `display_name` under `key`, so serde fails the test if the
+/// pointer uses a name it would not accept.
+fn cased<T: serde::de::DeserializeOwned + Validate>(key: &str) -> FieldErrors {
+    let dto: T = serde_json::from_value(json!({ key: "" })).unwrap();
+    failure(dto.validate(None)).0
+}
+
+#[test]
+fn every_serde_casing_names_the_field() {
+    let expected = |name: &str| vec![field(&format!("/{}", name), vec![ErrorCodes::NotEmpty])];
+    assert_eq!(cased::<LowerDto>("display_name"), expected("display_name"));
+    assert_eq!(cased::<UpperDto>("DISPLAY_NAME"), expected("DISPLAY_NAME"));
+    assert_eq!(cased::<PascalDto>("DisplayName"), expected("DisplayName"));
+    assert_eq!(cased::<CamelDto>("displayName"), expected("displayName"));
+    assert_eq!(cased::<SnakeDto>("display_name"), expected("display_name"));
+    assert_eq!(cased::<ScreamingSnakeDto>("DISPLAY_NAME"), expected("DISPLAY_NAME"));
+    assert_eq!(cased::<KebabDto>("display-name"), expected("display-name"));
+    assert_eq!(cased::<ScreamingKebabDto>("DISPLAY-NAME"), expected("DISPLAY-NAME"));
+    assert_eq!(cased::<DeserializeOnlyDto>("display-name"), expected("display-name"));
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct AttachmentDto {
+    #[validate(not_empty(code = NotEmpty), max_length(12, code = MaxSize))]
+    name: String,
+    #[validate(url)]
+    href: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+struct PostDto {
+    #[validate(nested)]
+    cover_image: Option<AttachmentDto>,
+    #[validate(max_items(3), nested)]
+    attachments: Vec<AttachmentDto>,
+    #[validate(min_items(1), unique_items, each(not_empty(code = NotEmpty), max_length(8, code = MaxSize)))]
+    tags: Vec<String>,
+    #[validate(each(email))]
+    reviewers: std::collections::HashMap<String, String>,
+    #[validate(nested)]
+    links: std::collections::BTreeMap<String, AttachmentDto>,
+}
+
+fn attachment(name: &str) -> AttachmentDto {
+    AttachmentDto {
+        name: name.to_string(),
+        href: None,
+    }
+}
+
+fn post() -> PostDto {
+    PostDto {
+        cover_image: Some(attachment("cover.png")),
+        attachments: vec![attachment("a.txt"), attachment("b.txt")],
+        tags: vec!["rust".to_string(), "web".to_string()],
+        reviewers: [("lead".to_string(), "lead@example.com".to_string())].into(),
+        links: [("docs".to_string(), attachment("docs"))].into(),
+    }
+}
+
+#[test]
+fn nested_fields_are_reported_by_json_pointer() {
+    assert!(post().validate(None).is_ok());
+
+    let invalid = PostDto {
+        cover_image: Some(AttachmentDto {
+            name: String::new(),
+            href: Some("not a url".to_string()),
+        }),
+        attachments: vec![attachment("a.txt"), attachment("b.txt"), attachment("a name too long")],
+        tags: vec!["rust".to_string(), String::new(), "rust".to_string()],
+        reviewers: [
+            ("lead".to_string(), "lead@".to_string()),
+            ("qa/ops".to_string(), "qa@example.com".to_string()),
+            ("x~y".to_string(), "nope".to_string()),
+        ]
+        .into(),
+        links: [("docs".to_string(), attachment(""))].into(),
+    };
+    let (fields, _) = failure(invalid.validate(None));
+    assert_eq!(
+        fields,
+        vec![
+            field("/coverImage/name", vec![ErrorCodes::NotEmpty]),
+            field("/coverImage/href", vec![ErrorCodes::InvalidUrl]),
+            field("/attachments/2/name", vec![ErrorCodes::MaxSize]),
+            field("/tags", vec![ErrorCodes::DuplicateItems]),
+            field("/tags/1", vec![ErrorCodes::NotEmpty]),
+            field("/reviewers/lead", vec![ErrorCodes::InvalidEmail]),
+            field("/reviewers/x~0y", vec![ErrorCodes::InvalidEmail]),
+            field("/links/docs/name", vec![ErrorCodes::NotEmpty]),
+        ]
+    );
+
+    let crowded = PostDto {
+        cover_image: None,
+        attachments: (0..4).map(|_| attachment("a.txt")).collect(),
+        tags: Vec::new(),
+        ..post()
+    };
+    let (fields, _) = failure(crowded.validate(None));
+    assert_eq!(
+        fields,
+        vec![
+            field("/attachments", vec![ErrorCodes::TooManyItems]),
+            field("/tags", vec![ErrorCodes::TooFewItems]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn nested_problems_reach_the_response() {
+    let filter = warp::path("posts")
+        .and(validated_json::<Vec<AttachmentDto>>(BodyLimits::default()))
+        .map(|attachments: Vec<AttachmentDto>| attachments.len().to_string())
+        .recover(handle_rejection);
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/posts")
+        .json(&json!([{ "name": "a.txt" }, { "name": "", "href": "https://example.com/b" }]))
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["field"], "/1/name");
+    assert_eq!(body["details"][0]["error_code"], 1002);
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct PageQuery {
+    #[validate(within_range(1, 50, code = MaxSize))]
+    limit: Option<u32>,
+    #[validate(one_of(&["asc", "desc"]))]
+    order: Option<String>,
+}
+
+#[tokio::test]
+async fn query_params_and_headers_are_validated() {
+    use crate::middleware::validator::{validated_header, validated_param, validated_query};
+
+    let filter = warp::path("items")
+        .and(validated_param::<String, _>("slug", |rule| rule.max_length(5).with_error_code(ErrorCodes::MaxSize)))
+        .and(validated_query::<PageQuery>())
+        .and(validated_header("x-tenant", |rule| rule.uuid()))
+        .map(|slug: String, query: PageQuery, tenant: String| format!("{}:{}:{}", slug, query.limit.unwrap_or(10), tenant))
+        .recover(handle_rejection);
+    let tenant = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+    let get = |path: &str, tenant: Option<&str>| {
+        let mut request = warp::test::request().method("GET").path(path);
+        if let Some(tenant) = tenant {
+            request = request.header("x-tenant", tenant);
+        }
+        request.reply(&filter)
+    };
+
+    let res = get("/items/shoes?limit=20&order=asc", Some(tenant)).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body(), &format!("shoes:20:{}", tenant));
+    assert_eq!(get("/items/shoes", Some(tenant)).await.status(), 200);
+    // Limits apply to the decoded segment, not to its escapes.
+    let res = get("/items/sh%C3%B6e", Some(tenant)).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body(), &format!("shöe:10:{}", tenant));
+
+    let res = get("/items/sneakers", Some(tenant)).await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["instance"], "items/sneakers");
+    assert_eq!(body["details"][0]["field"], "slug");
+    assert_eq!(body["details"][0]["error_code"], 1003);
+
+    let res = get("/items/shoes?limit=80&order=up", Some(tenant)).await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["field"], "/limit");
+    assert_eq!(body["details"][1]["field"], "/order");
+    assert_eq!(body["details"][1]["error_code"], 1009);
+
+    let res = get("/items/shoes?limit=many", Some(tenant)).await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["error_code"], 1021);
+
+    let res = get("/items/shoes", None).await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["field"], "x-tenant");
+    assert_eq!(body["details"][0]["error_code"], 1022);
+
+    let res = get("/items/shoes", Some("tenant-1")).await;
+    assert_eq!(res.status(), 400);
+    let body: Value = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(body["details"][0]["error_code"], 1008);
+}
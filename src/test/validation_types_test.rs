@@ -0,0 +1,225 @@
+#![allow(dead_code, unused_imports, unused_variables, unused_macros)]
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::middleware::validator::{Rule, Validate, Validation};
+
+fn codes<T>(result: Result<Option<&T>, ApiError>) -> Vec<ErrorCodes> {
+    match result {
+        Ok(_) => Vec::new(),
+        Err(ApiError::MultipleErrors(Some(failures), _, _)) => failures.into_iter().map(|f| f.code).collect(),
+        Err(e) => panic!("unexpected error {:?}", e),
+    }
+}
+
+fn rule<T: Validation>(value: &T) -> Rule<'_, T> {
+    Rule::new(Some(value), None, None)
+}
+
+macro_rules! integer_ranges {
+    ($($t:ty),*) => {$(
+        let (low, inside, high): ($t, $t, $t) = (1, 5, 10);
+        let range = |value: &$t| codes(Rule::new(Some(value), None, None).within_range(2, 9).with_error_code(ErrorCodes::MaxSize).check());
+        assert!(range(&inside).is_empty(), "{}", stringify!($t));
+        assert_eq!(range(&low), vec![ErrorCodes::MaxSize], "{}", stringify!($t));
+        assert_eq!(range(&high), vec![ErrorCodes::MaxSize], "{}", stringify!($t));
+        // The bounds are inclusive.
+        assert!(range(&2).is_empty() && range(&9).is_empty(), "{}", stringify!($t));
+        assert_eq!(codes(Rule::<$t>::new(None, None, None).not_null().with_error_code(ErrorCodes::NotNull).check()), vec![ErrorCodes::NotNull]);
+        assert!(codes(Rule::<$t>::new(None, None, None).within_range(2, 9).check()).is_empty());
+    )*};
+}
+
+#[test]
+fn every_integer_width_has_within_range() {
+    integer_ranges!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+    let negative: i32 = -5;
+    assert!(codes(Rule::new(Some(&negative), None, None).within_range(-10, -1).check()).is_empty());
+    assert_eq!(codes(Rule::new(Some(&negative), None, None).within_range(0, 10).check()), vec![ErrorCodes::Nodeclared]);
+}
+
+macro_rules! float_rules {
+    ($($t:ty),*) => {$(
+        let whole: $t = 3.0;
+        let fraction: $t = 2.5;
+        assert!(codes(rule(&whole).is_integer().check()).is_empty());
+        assert_eq!(codes(rule(&fraction).is_integer().check()), vec![ErrorCodes::Nodeclared]);
+        assert!(codes(rule(&fraction).is_decimal().has_decimals().check()).is_empty());
+        assert_eq!(codes(rule(&whole).is_decimal().has_decimals().check()).len(), 2);
+        assert!(codes(rule(&fraction).is_number().check()).is_empty());
+        for invalid in [<$t>::NAN, <$t>::INFINITY, <$t>::NEG_INFINITY] {
+            assert_eq!(codes(rule(&invalid).is_number().check()).len(), 1, "{}", stringify!($t));
+        }
+        assert!(codes(rule(&fraction).within_range(2.0, 3.0).check()).is_empty());
+        assert_eq!(codes(rule(&fraction).within_range(2.6, 3.0).check()).len(), 1);
+    )*};
+}
+
+#[test]
+fn both_float_widths_have_the_decimal_rules() {
+    float_rules!(f32, f64);
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+#[test]
+fn dates_have_before_after_and_between() {
+    let day = date(2024, 5, 1);
+    let rule = || Rule::new(Some(&day), None, None);
+    assert!(codes(rule().before(date(2024, 5, 2)).after(date(2024, 4, 30)).check()).is_empty());
+    // Before and after are strict.
+    assert_eq!(codes(rule().before(day).check()), vec![ErrorCodes::DateTooLate]);
+    assert_eq!(codes(rule().after(day).check()), vec![ErrorCodes::DateTooEarly]);
+    // Between is inclusive.
+    assert!(codes(rule().between(day, date(2024, 12, 31)).check()).is_empty());
+    assert_eq!(codes(rule().between(date(2025, 1, 1), date(2025, 12, 31)).check()), vec![ErrorCodes::DateOutOfRange]);
+    assert_eq!(
+        codes(rule().before(date(2024, 1, 1)).with_error_code(ErrorCodes::InvalidDate).check()),
+        vec![ErrorCodes::InvalidDate]
+    );
+
+    let moment = Utc.with_ymd_and_hms(2024, 5, 1, 13, 45, 0).unwrap();
+    let second = chrono::Duration::seconds(1);
+    let rule = || Rule::new(Some(&moment), None, None);
+    assert!(codes(rule().before(moment + second).after(moment - second).check()).is_empty());
+    assert_eq!(codes(rule().before(moment).check()), vec![ErrorCodes::DateTooLate]);
+    assert_eq!(codes(rule().after(moment).check()), vec![ErrorCodes::DateTooEarly]);
+    assert!(codes(rule().between(moment, moment).check()).is_empty());
+    assert_eq!(codes(rule().between(moment + second, moment + second * 60).check()), vec![ErrorCodes::DateOutOfRange]);
+    assert!(codes(rule().within_range(moment - second, moment).check()).is_empty());
+}
+
+#[test]
+fn options_delegate_to_the_inner_value() {
+    let missing: Option<u8> = None;
+    let rule = |value: &Option<u8>| codes(Rule::new(Some(value), None, None).not_null().within_range(1, 5).check());
+    assert_eq!(rule(&missing), vec![ErrorCodes::Nodeclared]);
+    assert_eq!(rule(&Some(9)), vec![ErrorCodes::Nodeclared]);
+    assert!(rule(&Some(3)).is_empty());
+
+    let text = |value: Option<&str>| {
+        let value = value.map(str::to_string);
+        codes(Rule::new(Some(&value), None, None).not_empty().with_error_code(ErrorCodes::NotEmpty).email().check())
+    };
+    assert!(text(None).is_empty());
+    assert!(text(Some("ada@example.com")).is_empty());
+    assert_eq!(text(Some("")), vec![ErrorCodes::NotEmpty, ErrorCodes::InvalidEmail]);
+
+    let price: Option<f32> = Some(1.5);
+    assert_eq!(codes(Rule::new(Some(&price), None, None).is_integer().check()).len(), 1);
+    let due: Option<NaiveDate> = Some(date(2024, 5, 1));
+    assert_eq!(codes(Rule::new(Some(&due), None, None).after(date(2024, 6, 1)).check()), vec![ErrorCodes::DateTooEarly]);
+    let none: Option<NaiveDate> = None;
+    assert!(codes(Rule::new(Some(&none), None, None).after(date(2024, 6, 1)).check()).is_empty());
+}
+
+#[test]
+fn text_lengths_count_characters_not_bytes() {
+    let length = |value: &str| {
+        let value = value.to_string();
+        codes(
+            rule(&value)
+                .min_length(3)
+                .with_error_code(ErrorCodes::MinSize)
+                .max_length(5)
+                .with_error_code(ErrorCodes::MaxSize)
+                .check(),
+        )
+    };
+    // Five characters each, but 7, 15 and 20 bytes.
+    assert!(length("ñandú").is_empty());
+    assert!(length("日本語です").is_empty());
+    assert!(length("🦀🦀🦀🦀🦀").is_empty());
+    assert_eq!(length("ñandús"), vec![ErrorCodes::MaxSize]);
+    assert_eq!(length("日本語ですね"), vec![ErrorCodes::MaxSize]);
+    assert_eq!(length("語語"), vec![ErrorCodes::MinSize]);
+}
+
+#[test]
+fn lists_and_booleans() {
+    let tags = vec!["a".to_string(), "b".to_string(), "a".to_string()];
+    assert!(codes(rule(&tags).min_items(3).max_items(3).check()).is_empty());
+    assert_eq!(codes(rule(&tags).min_items(4).check()), vec![ErrorCodes::TooFewItems]);
+    assert_eq!(codes(rule(&tags).max_items(2).check()), vec![ErrorCodes::TooManyItems]);
+    assert_eq!(codes(rule(&tags).unique_items().check()), vec![ErrorCodes::DuplicateItems]);
+    assert!(codes(rule(&tags[..2].to_vec()).unique_items().check()).is_empty());
+    let sizes: Vec<usize> = vec![1, 2, 3];
+    assert!(codes(Rule::new(Some(&sizes), None, None).unique_items().check()).is_empty());
+    assert_eq!(codes(Rule::<Vec<usize>>::new(None, None, None).not_null().check()), vec![ErrorCodes::Nodeclared]);
+
+    let accepted = false;
+    assert!(codes(Rule::new(Some(&accepted), None, None).not_null().check()).is_empty());
+    assert_eq!(codes(Rule::<bool>::new(None, None, None).not_null().check()), vec![ErrorCodes::Nodeclared]);
+}
+
+#[derive(Debug, Validate)]
+struct BookingDto {
+    #[validate(within_range(1, 8, code = MaxSize))]
+    guests: u8,
+    #[validate(within_range(-20, 40))]
+    temperature: Option<i16>,
+    #[validate(within_range(0.0, 1.0), has_decimals)]
+    discount: Option<f32>,
+    #[validate(not_null, after(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()))]
+    check_in: Option<NaiveDate>,
+    #[validate(between(
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 59).unwrap()
+    ))]
+    requested_at: DateTime<Utc>,
+    #[validate(within_range(1, 100))]
+    nights: usize,
+}
+
+#[test]
+fn derived_rules_work_for_every_type() {
+    let booking = || BookingDto {
+        guests: 2,
+        temperature: Some(21),
+        discount: Some(0.25),
+        check_in: Some(date(2024, 5, 1)),
+        requested_at: Utc.with_ymd_and_hms(2024, 4, 1, 9, 0, 0).unwrap(),
+        nights: 3,
+    };
+    assert!(booking().validate(None).is_ok());
+
+    let invalid = BookingDto {
+        guests: 9,
+        temperature: Some(50),
+        discount: Some(1.0),
+        check_in: Some(date(2023, 12, 31)),
+        requested_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+        nights: 0,
+    };
+    let fields = match invalid.validate(None) {
+        Err(ApiError::FieldErrors(fields, _)) => fields,
+        other => panic!("unexpected result {:?}", other),
+    };
+    let names: Vec<&str> = fields.iter().map(|(field, _)| field.as_deref().unwrap()).collect();
+    assert_eq!(
+        names,
+        vec!["/guests", "/temperature", "/discount", "/check_in", "/requested_at", "/nights"]
+    );
+    assert_eq!(fields[0].1, vec![ErrorCodes::MaxSize]);
+    assert_eq!(fields[2].1, vec![ErrorCodes::Nodeclared]);
+    assert_eq!(fields[3].1, vec![ErrorCodes::DateTooEarly]);
+    assert_eq!(fields[4].1, vec![ErrorCodes::DateOutOfRange]);
+
+    let missing = BookingDto {
+        temperature: None,
+        discount: None,
+        check_in: None,
+        ..booking()
+    };
+    let fields = match missing.validate(None) {
+        Err(ApiError::FieldErrors(fields, _)) => fields,
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_eq!(fields.len(), 1);
+    assert_eq!(fields[0].0.as_deref(), Some("/check_in"));
+    assert_eq!(fields[0].1, vec![ErrorCodes::Nodeclared]);
+}
@@ -0,0 +1,254 @@
+#![allow(dead_code, unused_imports, unused_variables)]
+use base64::{engine::general_purpose, Engine as _};
+use ciborium::Value as Cbor;
+use p256::ecdsa::{signature::Signer, Signature, SigningKey};
+use rand_core::OsRng;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use warp::Filter;
+
+use crate::config::Config;
+use crate::controllers::routes;
+use crate::test::support::{bearer, body, filter, handle_rejection, path, post};
+
+const ORIGIN: &str = "https://auth.example.com";
+const RP_ID: &str = "auth.example.com";
+
+fn b64(bytes: impl AsRef<[u8]>) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn cbor(value: &Cbor) -> Vec<u8> {
+    let mut out = Vec::new();
+    ciborium::ser::into_writer(value, &mut out).unwrap();
+    out
+}
+
+/// A platform authenticator in software: one ES256 passkey with a
+/// signature counter, answering options the way a browser would.
+struct SoftwareAuthenticator {
+    key: SigningKey,
+    credential_id: Vec<u8>,
+    origin: String,
+    sign_count: u32,
+    user_handle: Option<String>,
+}
+
+impl SoftwareAuthenticator {
+    fn new() -> Self {
+        Self {
+            key: SigningKey::random(&mut OsRng),
+            credential_id: uuid::Uuid::new_v4().as_bytes().to_vec(),
+            origin: ORIGIN.to_string(),
+            sign_count: 0,
+            user_handle: None,
+        }
+    }
+
+    fn credential_id(&self) -> String {
+        b64(&self.credential_id)
+    }
+
+    fn client_data(&self, kind: &str, challenge: &str) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "type": kind,
+            "challenge": challenge,
+            "origin": self.origin,
+            "crossOrigin": false
+        }))
+        .unwrap()
+    }
+
+    fn cose_key(&self) -> Cbor {
+        let point = self.key.verifying_key().to_encoded_point(false);
+        Cbor::Map(vec![
+            (Cbor::Integer(1.into()), Cbor::Integer(2.into())),
+            (Cbor::Integer(3.into()), Cbor::Integer((-7).into())),
+            (Cbor::Integer((-1).into()), Cbor::Integer(1.into())),
+            (Cbor::Integer((-2).into()), Cbor::Bytes(point.x().unwrap().to_vec())),
+            (Cbor::Integer((-3).into()), Cbor::Bytes(point.y().unwrap().to_vec())),
+        ])
+    }
+
+    /// `navigator.credentials.create()` for the given creation options.
+    fn create(&mut self, options: &Value) -> Value {
+        self.user_handle = options["user"]["id"].as_str().map(str::to_string);
+        let client_data = self.client_data("webauthn.create", options["challenge"].as_str().unwrap());
+        let mut auth_data = Sha256::digest(options["rp"]["id"].as_str().unwrap().as_bytes()).to_vec();
+        auth_data.push(0x45); // user present, user verified, attested credential data
+        auth_data.extend_from_slice(&self.sign_count.to_be_bytes());
+        auth_data.extend_from_slice(&[0u8; 16]);
+        auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+        auth_data.extend_from_slice(&self.credential_id);
+        auth_data.extend_from_slice(&cbor(&self.cose_key()));
+        let attestation = Cbor::Map(vec![
+            (Cbor::Text("fmt".into()), Cbor::Text("none".into())),
+            (Cbor::Text("attStmt".into()), Cbor::Map(vec![])),
+            (Cbor::Text("authData".into()), Cbor::Bytes(auth_data)),
+        ]);
+        json!({
+            "id": self.credential_id(),
+            "type": "public-key",
+            "response": {
+                "clientDataJSON": b64(client_data),
+                "attestationObject": b64(cbor(&attestation))
+            }
+        })
+    }
+
+    /// `navigator.credentials.get()`, returned as a `webauthn` token request.
+    fn get(&mut self, options: &Value) -> Value {
+        self.sign_count += 1;
+        let client_data = self.client_data("webauthn.get", options["challenge"].as_str().unwrap());
+        let mut auth_data = Sha256::digest(options["rpId"].as_str().unwrap().as_bytes()).to_vec();
+        auth_data.push(0x05);
+        auth_data.extend_from_slice(&self.sign_count.to_be_bytes());
+        let mut signed = auth_data.clone();
+        signed.extend_from_slice(&Sha256::digest(&client_data));
+        let signature: Signature = self.key.sign(&signed);
+        json!({
+            "grant_type": "webauthn",
+            "credential_id": self.credential_id(),
+            "client_data_json": b64(client_data),
+            "authenticator_data": b64(auth_data),
+            "signature": b64(signature.to_der().as_bytes()),
+            "user_handle": self.user_handle
+        })
+    }
+}
+
+fn webauthn_config() -> Arc<Config> {
+    dotenv::dotenv().ok();
+    Arc::new(Config {
+        issuer: ORIGIN.to_string(),
+        webauthn_rp_id: RP_ID.to_string(),
+        webauthn_origin: ORIGIN.to_string(),
+        ..Config::from_env()
+    })
+}
+
+async fn register<F>(filter: &F, config: &Config, authenticator: &mut SoftwareAuthenticator) -> String
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let admin = bearer(filter, config, json!({ "grant_type": "user", "username": "admin", "password": "password" })).await;
+    let res = post(filter, &path(config, "auth/webauthn/register/options"), Some(&admin), json!({})).await;
+    assert_eq!(res.status(), 200);
+    let options = body(&res);
+    assert_eq!(options["rp"]["id"], RP_ID);
+    assert_eq!(options["pubKeyCredParams"][0]["alg"], -7);
+    assert_eq!(options["user"]["name"], "admin");
+
+    let credential = authenticator.create(&options);
+    let res = post(filter, &path(config, "auth/webauthn/register"), Some(&admin), credential.clone()).await;
+    assert_eq!(res.status(), 201);
+    assert_eq!(body(&res)["id"], authenticator.credential_id().as_str());
+
+    let res = post(filter, &path(config, "auth/webauthn/register"), Some(&admin), credential).await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(body(&res)["details"][0]["error_code"], 2004);
+    admin
+}
+
+async fn login_options<F>(filter: &F, config: &Config, username: Option<&str>) -> Value
+where
+    F: Filter<Extract = (warp::reply::Response,), Error = std::convert::Infallible> + Clone + 'static,
+{
+    let res = post(filter, &path(config, "auth/webauthn/login/options"), None, json!({ "username": username })).await;
+    assert_eq!(res.status(), 200);
+    body(&res)
+}
+
+#[tokio::test]
+async fn passkey_registration_and_login() {
+    let config = webauthn_config();
+    let filter = filter(&config);
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&filter, &config, &mut authenticator).await;
+
+    let options = login_options(&filter, &config, Some("admin")).await;
+    assert_eq!(options["rpId"], RP_ID);
+    assert_eq!(options["allowCredentials"][0]["id"], authenticator.credential_id().as_str());
+
+    let assertion = authenticator.get(&options);
+    let res = post(&filter, &path(&config, "auth/token"), None, assertion.clone()).await;
+    assert_eq!(res.status(), 200);
+    let token = format!("Bearer {}", body(&res)["token"].as_str().unwrap());
+
+    let res = warp::test::request()
+        .method("GET")
+        .path(&path(&config, "auth/sessions"))
+        .header("Authorization", &token)
+        .reply(&filter)
+        .await;
+    assert_eq!(res.status(), 200);
+    let sessions = body(&res);
+    let current = sessions.as_array().unwrap().iter().find(|s| s["current"] == true).unwrap();
+    assert_eq!(current["grant_type"], "user");
+
+    // The challenge was consumed by the first login.
+    let res = post(&filter, &path(&config, "auth/token"), None, assertion).await;
+    assert_eq!(res.status(), 401);
+
+    // Discoverable login without a username works too.
+    let options = login_options(&filter, &config, None).await;
+    assert_eq!(options["allowCredentials"], json!([]));
+    let res = post(&filter, &path(&config, "auth/token"), None, authenticator.get(&options)).await;
+    assert_eq!(res.status(), 200);
+}
+
+#[tokio::test]
+async fn rejects_cloned_and_foreign_assertions() {
+    let config = webauthn_config();
+    let filter = filter(&config);
+    let mut authenticator = SoftwareAuthenticator::new();
+    register(&filter, &config, &mut authenticator).await;
+
+    let options = login_options(&filter, &config, Some("admin")).await;
+    let res = post(&filter, &path(&config, "auth/token"), None, authenticator.get(&options)).await;
+    assert_eq!(res.status(), 200);
+
+    // A copy of the authenticator reuses an old counter value.
+    authenticator.sign_count = 0;
+    let options = login_options(&filter, &config, Some("admin")).await;
+    let res = post(&filter, &path(&config, "auth/token"), None, authenticator.get(&options)).await;
+    assert_eq!(res.status(), 401);
+
+    authenticator.sign_count = 10;
+    authenticator.origin = "https://evil.example.com".to_string();
+    let options = login_options(&filter, &config, Some("admin")).await;
+    let res = post(&filter, &path(&config, "auth/token"), None, authenticator.get(&options)).await;
+    assert_eq!(res.status(), 401);
+
+    authenticator.origin = ORIGIN.to_string();
+    let options = login_options(&filter, &config, Some("admin")).await;
+    let mut assertion = authenticator.get(&options);
+    assertion["signature"] = json!(b64(b"not a signature"));
+    let res = post(&filter, &path(&config, "auth/token"), None, assertion).await;
+    assert_eq!(res.status(), 401);
+
+    let stranger = SoftwareAuthenticator::new();
+    let options = login_options(&filter, &config, Some("admin")).await;
+    let mut assertion = authenticator.get(&options);
+    assertion["credential_id"] = json!(stranger.credential_id());
+    let res = post(&filter, &path(&config, "auth/token"), None, assertion).await;
+    assert_eq!(res.status(), 401);
+}
+
+#[tokio::test]
+async fn only_users_register_passkeys() {
+    let config = webauthn_config();
+    let filter = filter(&config);
+    let client = bearer(
+        &filter,
+        &config,
+        json!({ "grant_type": "client", "client_id": "client", "client_secret": "secret" }),
+    )
+    .await;
+    let res = post(&filter, &path(&config, "auth/webauthn/register/options"), Some(&client), json!({})).await;
+    assert_eq!(res.status(), 403);
+
+    let res = post(&filter, &path(&config, "auth/webauthn/register/options"), None, json!({})).await;
+    assert_eq!(res.status(), 401);
+}
@@ -1 +1,2 @@
-pub mod base_validator;
\ No newline at end of file
+pub mod password_validator;
+pub mod scim_validator;
@@ -0,0 +1,46 @@
+use unicode_normalization::UnicodeNormalization;
+
+use crate::config::PasswordPolicy;
+use crate::errors::error_codes::ErrorCodes;
+use crate::errors::ApiError;
+use crate::middleware::validator::{CharacterClass, Rule};
+
+/// NFKC form of a password, so the same characters typed on different
+/// keyboards or input methods hash the same way.
+pub fn normalize_password(password: &str) -> String {
+    password.nfkc().collect()
+}
+
+/// Checks a new password for `username` against the policy and returns the
+/// normalized password to store.
+pub fn validate_password(policy: &PasswordPolicy, password: &str, username: &str) -> Result<String, ApiError> {
+    let password = normalize_password(password);
+    let mut rule = Rule::new(Some(&password), Some("password".to_string()), None)
+        .min_length(policy.min_length)
+        .with_error_code(ErrorCodes::PasswordTooShort)
+        .max_length(policy.max_length)
+        .with_error_code(ErrorCodes::PasswordTooLong);
+    let classes = [
+        (policy.require_uppercase, CharacterClass::Uppercase, ErrorCodes::PasswordMissingUppercase),
+        (policy.require_lowercase, CharacterClass::Lowercase, ErrorCodes::PasswordMissingLowercase),
+        (policy.require_digit, CharacterClass::Digit, ErrorCodes::PasswordMissingDigit),
+        (policy.require_symbol, CharacterClass::Symbol, ErrorCodes::PasswordMissingSymbol),
+    ];
+    for (required, class, error_code) in classes {
+        if required {
+            rule = rule.has_character_class(class).with_error_code(error_code);
+        }
+    }
+    if policy.disallow_username {
+        rule = rule
+            .not_containing(&normalize_password(username))
+            .with_error_code(ErrorCodes::PasswordContainsUsername);
+    }
+    if policy.reject_common {
+        rule = rule
+            .not_common_password()
+            .with_error_code(ErrorCodes::PasswordTooCommon);
+    }
+    rule.check()?;
+    Ok(password)
+}
@@ -0,0 +1,76 @@
+use warp::path::FullPath;
+use warp::{Filter, Rejection};
+
+use crate::config::BodyLimits;
+use crate::errors::error_codes::ErrorCodes;
+use crate::middleware::body::json_body;
+use crate::middleware::validator::{instance, Rule, Validator};
+use crate::models::scim_model::{ScimGroupDto, ScimUserDto};
+use crate::repositories::credentials_repository::CredentialRepository;
+
+/// Body of `POST /Users`: the `userName` must be set and not taken yet.
+pub fn validate_new_scim_user<C>(credentials: C, limits: BodyLimits) -> impl Filter<Extract = (ScimUserDto,), Error = Rejection> + Clone
+where
+    C: CredentialRepository + Clone + 'static,
+{
+    let credentials = warp::any().map(move || credentials.clone());
+    json_body(limits)
+        .and(warp::path::full())
+        .and(credentials)
+        .and_then(|user: ScimUserDto, path: FullPath, credentials: C| async move {
+            let user_name = Rule::new(Some(&user.user_name), Some("userName".to_string()), None)
+                .not_empty()
+                .with_error_code(ErrorCodes::ScimInvalidValue)
+                .custom_async(move |user_name: &String| {
+                    let credentials = credentials.clone();
+                    let user_name = user_name.clone();
+                    async move {
+                        credentials
+                            .find_user_by_username(&user_name)
+                            .await
+                            .map(|_| ErrorCodes::ScimUniqueness)
+                    }
+                });
+            Validator::new(instance(&path))
+                .rule_async(user_name)
+                .await
+                .validate()?;
+            Ok::<_, Rejection>(user)
+        })
+}
+
+/// Body of `POST` and `PUT /Groups`: every member must be an existing user.
+pub fn validate_scim_group<C>(credentials: C, limits: BodyLimits) -> impl Filter<Extract = (ScimGroupDto,), Error = Rejection> + Clone
+where
+    C: CredentialRepository + Clone + 'static,
+{
+    let credentials = warp::any().map(move || credentials.clone());
+    json_body(limits)
+        .and(warp::path::full())
+        .and(credentials)
+        .and_then(|group: ScimGroupDto, path: FullPath, credentials: C| async move {
+            let mut validator = Validator::new(instance(&path)).rule(
+                Rule::new(Some(&group.display_name), Some("displayName".to_string()), None)
+                    .not_empty()
+                    .with_error_code(ErrorCodes::ScimInvalidValue),
+            );
+            validator = validator.enter("members");
+            for (index, member) in group.members.iter().enumerate() {
+                let credentials = credentials.clone();
+                let exists = Rule::new(Some(&member.value), Some("value".to_string()), None).custom_async(move |id: &String| {
+                    let credentials = credentials.clone();
+                    let id = id.clone();
+                    async move {
+                        credentials
+                            .find_user(&id)
+                            .await
+                            .is_none()
+                            .then_some(ErrorCodes::ScimInvalidValue)
+                    }
+                });
+                validator = validator.enter(&index.to_string()).rule_async(exists).await.leave();
+            }
+            validator.leave().validate()?;
+            Ok::<_, Rejection>(group)
+        })
+}
@@ -0,0 +1,413 @@
+//! `#[derive(Validate)]` for request DTOs.
+//!
+//! Each `#[validate(...)]` entry on a field becomes a call on
+//! `middleware::validator::Rule`, so any builder the rule type offers can be
+//! used by name. `code = X` sets the `ErrorCodes` variant of that rule. All
+//! fields are checked through a `Validator`, so every invalid field is
+//! reported at once:
+//!
+//! ```ignore
+//! #[derive(Deserialize, Validate)]
+//! pub struct CreateMessageModelDto {
+//!     #[validate(not_null(code = NotNull), max_length(32, code = MaxSize))]
+//!     pub content: Option<String>,
+//! }
+//! ```
+//!
+//! `nested` validates a field that is itself a `Validate` type, or a list or
+//! map of them, and `each(...)` applies its rules to every element of a list
+//! or map:
+//!
+//! ```ignore
+//! #[validate(max_items(5), nested)]
+//! pub attachments: Vec<AttachmentDto>,
+//! #[validate(unique_items, each(not_empty, max_length(20)))]
+//! pub tags: Vec<String>,
+//! ```
+//!
+//! `when(predicate)` and `unless(predicate)` make a field's rules
+//! conditional on a function or closure of `&Self`, and rules ending in
+//! `_field` compare the field with a sibling named by its Rust name:
+//!
+//! ```ignore
+//! #[validate(when(Self::is_client), not_null(code = NotNull))]
+//! pub client_secret: Option<String>,
+//! #[validate(after_field(start))]
+//! pub end: NaiveDate,
+//! ```
+//!
+//! `normalize(...)` rewrites a text field before any rule runs, in the order
+//! given: `trim`, `collapse_whitespace`, `nfc`, `strip_control` and
+//! `lowercase`. Nested DTOs are normalized with their parent:
+//!
+//! ```ignore
+//! #[validate(normalize(trim, lowercase), email)]
+//! pub email: Option<String>,
+//! ```
+//!
+//! Errors name the field by its JSON Pointer, such as `/attachments/2/name`,
+//! following serde's `rename` and `rename_all`. The same rules are described
+//! by `Validate::schema` for the OpenAPI document.
+
+use proc_macro::TokenStream;
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, parse_macro_input, Data, DeriveInput, Expr, Fields, Ident, LitStr, Token, Type};
+
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// One builder call, such as `max_length(32, code = MaxSize)`.
+struct RuleCall {
+    method: Ident,
+    args: Vec<Expr>,
+    code: Option<Ident>,
+}
+
+impl Parse for RuleCall {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let method: Ident = input.parse()?;
+        RuleCall::parse_args(method, input)
+    }
+}
+
+/// One entry of `#[validate(...)]`: a rule, `nested`, `each(...)` with
+/// the rules of every element, a `when` or `unless` predicate, or
+/// `normalize(...)` with the `Normalizer` variants to apply.
+enum Entry {
+    Rule(RuleCall),
+    Nested,
+    Each(Vec<RuleCall>),
+    When(Expr),
+    Unless(Expr),
+    Normalize(Vec<Ident>),
+}
+
+/// The `Normalizer` variant of a `normalize(...)` item.
+fn normalizer(item: &Ident) -> syn::Result<Ident> {
+    let variant = match item.to_string().as_str() {
+        "trim" => "Trim",
+        "collapse_whitespace" => "CollapseWhitespace",
+        "nfc" => "Nfc",
+        "strip_control" => "StripControl",
+        "lowercase" => "Lowercase",
+        _ => {
+            return Err(syn::Error::new(
+                item.span(),
+                "expected `trim`, `collapse_whitespace`, `nfc`, `strip_control` or `lowercase`",
+            ))
+        }
+    };
+    Ok(Ident::new(variant, item.span()))
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let method: Ident = input.parse()?;
+        if method == "nested" && !input.peek(syn::token::Paren) {
+            return Ok(Entry::Nested);
+        }
+        if method == "each" && input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let calls = Punctuated::<RuleCall, Token![,]>::parse_terminated(&content)?;
+            return Ok(Entry::Each(calls.into_iter().collect()));
+        }
+        if method == "normalize" && input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let items = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            let normalizers = items.iter().map(normalizer).collect::<syn::Result<_>>()?;
+            return Ok(Entry::Normalize(normalizers));
+        }
+        if (method == "when" || method == "unless") && input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let predicate: Expr = content.parse()?;
+            return Ok(if method == "when" { Entry::When(predicate) } else { Entry::Unless(predicate) });
+        }
+        RuleCall::parse_args(method, input).map(Entry::Rule)
+    }
+}
+
+impl RuleCall {
+    fn parse_args(method: Ident, input: ParseStream) -> syn::Result<Self> {
+        let mut args = Vec::new();
+        let mut code = None;
+        if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            while !content.is_empty() {
+                if content.peek(Ident) && content.peek2(Token![=]) && !content.peek2(Token![==]) {
+                    let key: Ident = content.parse()?;
+                    if key != "code" {
+                        return Err(syn::Error::new(key.span(), "expected `code = ErrorCode`"));
+                    }
+                    content.parse::<Token![=]>()?;
+                    code = Some(content.parse()?);
+                } else {
+                    args.push(content.parse()?);
+                }
+                if !content.is_empty() {
+                    content.parse::<Token![,]>()?;
+                }
+            }
+        }
+        Ok(RuleCall { method, args, code })
+    }
+
+    /// The builder call. A `_field` rule gets the value and JSON name of the
+    /// sibling it names; schemas have no DTO, so there the value is `None`.
+    fn tokens(&self, siblings: &Siblings, schema: bool) -> syn::Result<TokenStream2> {
+        let method = &self.method;
+        let mut args: Vec<TokenStream2> = self.args.iter().map(|arg| quote! { #arg }).collect();
+        if method.to_string().ends_with("_field") {
+            let sibling = match self.args.as_slice() {
+                [Expr::Path(path)] => path.path.get_ident(),
+                _ => None,
+            };
+            let Some(sibling) = sibling else {
+                return Err(syn::Error::new(method.span(), "expected the name of another field"));
+            };
+            let Some((value, json_name)) = siblings.get(&sibling.to_string()) else {
+                return Err(syn::Error::new(sibling.span(), format!("unknown field `{}`", sibling)));
+            };
+            let value = if schema { quote! { None } } else { value.clone() };
+            args = vec![value, quote! { #json_name }];
+        }
+        let code = self.code.as_ref().map(|code| {
+            quote! { .with_error_code(crate::errors::error_codes::ErrorCodes::#code) }
+        });
+        Ok(quote! { .#method(#(#args),*) #code })
+    }
+}
+
+/// The value of every field as an `Option<&T>`, and its JSON name, by Rust
+/// name.
+type Siblings = HashMap<String, (TokenStream2, String)>;
+
+/// Reads `rename` or `rename_all` from `#[serde(...)]`, skipping the rest.
+/// Of the `(serialize = "...", deserialize = "...")` form, only the
+/// deserialize name matters, since that is what requests are read with.
+fn serde_name(attrs: &[syn::Attribute], key: &str) -> syn::Result<Option<LitStr>> {
+    let mut found = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) && meta.input.peek(syn::token::Paren) {
+                meta.parse_nested_meta(|nested| {
+                    let value = nested.value()?.parse::<LitStr>()?;
+                    if nested.path.is_ident("deserialize") {
+                        found = Some(value);
+                    }
+                    Ok(())
+                })?;
+            } else if meta.path.is_ident(key) {
+                found = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.input.peek(Token![=]) {
+                meta.value()?.parse::<Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                meta.parse_nested_meta(|nested| {
+                    if nested.input.peek(Token![=]) {
+                        nested.value()?.parse::<Expr>()?;
+                    }
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(found)
+}
+
+/// Applies a serde `rename_all` rule to a snake_case field name, or returns
+/// `None` for a rule serde does not have.
+fn rename(field: &str, rule: Option<&str>) -> Option<String> {
+    let mut words = field.split('_').filter(|w| !w.is_empty());
+    let capitalize = |word: &str| -> String {
+        let mut chars = word.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    };
+    let renamed = match rule {
+        None | Some("snake_case") | Some("lowercase") => field.to_string(),
+        Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE") => field.to_uppercase(),
+        Some("camelCase") => {
+            let first = words.next().unwrap_or_default().to_string();
+            first + &words.map(capitalize).collect::<String>()
+        }
+        Some("PascalCase") => words.map(capitalize).collect(),
+        Some("kebab-case") => field.replace('_', "-"),
+        Some("SCREAMING-KEBAB-CASE") => field.replace('_', "-").to_uppercase(),
+        Some(_) => return None,
+    };
+    Some(renamed)
+}
+
+/// The `T` of `Option<T>`, or `None` for other types.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last().filter(|s| s.ident == "Option")?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "Validate needs named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "Validate can only be derived for structs")),
+    };
+    let rename_all = serde_name(&input.attrs, "rename_all")?;
+    if let Some(rule) = rename_all.as_ref().filter(|rule| rename("", Some(&rule.value())).is_none()) {
+        return Err(syn::Error::new_spanned(
+            rule,
+            format!(
+                "unknown rename_all rule \"{}\"; expected one of lowercase, UPPERCASE, PascalCase, camelCase, \
+                 snake_case, SCREAMING_SNAKE_CASE, kebab-case or SCREAMING-KEBAB-CASE",
+                rule.value()
+            ),
+        ));
+    }
+    let rename_all = rename_all.map(|rule| rule.value());
+
+    let mut siblings = Siblings::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let json_name = match serde_name(&field.attrs, "rename")? {
+            Some(renamed) => renamed.value(),
+            None => rename(&ident.to_string(), rename_all.as_deref()).expect("checked rename_all rule"),
+        };
+        let value = match option_inner(&field.ty) {
+            Some(_) => quote! { self.#ident.as_ref() },
+            None => quote! { Some(&self.#ident) },
+        };
+        siblings.insert(ident.to_string(), (value, json_name));
+    }
+
+    let mut checks = Vec::new();
+    let mut schema = Vec::new();
+    let mut normalize = Vec::new();
+    for field in fields {
+        let entries: Vec<Entry> = field
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("validate"))
+            .map(|a| a.parse_args_with(Punctuated::<Entry, Token![,]>::parse_terminated))
+            .collect::<syn::Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let (value, json_name) = &siblings[&ident.to_string()];
+        let ty = &field.ty;
+        let value_ty = option_inner(ty).unwrap_or(ty);
+
+        // Conditions apply to all the field's rules; schemas only need to
+        // know the rules are conditional.
+        let mut conditions = Vec::new();
+        for entry in &entries {
+            match entry {
+                Entry::When(predicate) => conditions.push(quote! { .when((#predicate)(self)) }),
+                Entry::Unless(predicate) => conditions.push(quote! { .unless((#predicate)(self)) }),
+                Entry::Nested if entries.iter().any(|e| matches!(e, Entry::When(_) | Entry::Unless(_))) => {
+                    return Err(syn::Error::new_spanned(ident, "`when` and `unless` cannot be combined with `nested`"));
+                }
+                _ => {}
+            }
+        }
+        let conditional = (!conditions.is_empty()).then(|| quote! { .when(true) });
+
+        let mut calls = Vec::new();
+        let mut schema_calls = Vec::new();
+        for entry in &entries {
+            if let Entry::Rule(call) = entry {
+                calls.push(call.tokens(&siblings, false)?);
+                schema_calls.push(call.tokens(&siblings, true)?);
+            }
+        }
+        if !calls.is_empty() {
+            checks.push(quote! {
+                .rule(
+                    crate::middleware::validator::Rule::new(#value, Some(#json_name.to_string()), None)
+                        #(#conditions)*
+                        #(#calls)*
+                )
+            });
+            schema.push(quote! {
+                .field(#json_name, crate::middleware::validator::Rule::<#value_ty>::new(None, None, None) #conditional #(#schema_calls)*)
+            });
+        }
+        for entry in &entries {
+            match entry {
+                Entry::Nested => {
+                    checks.push(quote! { .nested(#json_name, &self.#ident) });
+                    schema.push(quote! { .nested::<#ty>() });
+                    normalize.push(quote! { crate::middleware::validator::Validate::normalize(&mut self.#ident); });
+                }
+                Entry::Normalize(normalizers) => {
+                    normalize.push(quote! {
+                        crate::middleware::normalizer::Normalize::normalize_with(
+                            &mut self.#ident,
+                            &[#(crate::middleware::normalizer::Normalizer::#normalizers),*],
+                        );
+                    });
+                }
+                Entry::Each(each) => {
+                    let calls = each.iter().map(|call| call.tokens(&siblings, false)).collect::<syn::Result<Vec<_>>>()?;
+                    let schema_calls = each.iter().map(|call| call.tokens(&siblings, true)).collect::<syn::Result<Vec<_>>>()?;
+                    checks.push(quote! { .each(#json_name, &self.#ident, |rule| rule #(#conditions)* #(#calls)*) });
+                    schema.push(quote! {
+                        .each(
+                            #json_name,
+                            crate::middleware::validator::Rule::<<#ty as crate::middleware::validator::Collection>::Item>::new(None, None, None)
+                                #conditional
+                                #(#schema_calls)*
+                        )
+                    });
+                }
+                Entry::Rule(_) | Entry::When(_) | Entry::Unless(_) => {}
+            }
+        }
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics crate::middleware::validator::Validate for #name #ty_generics #where_clause {
+            fn rules(&self, validator: crate::middleware::validator::Validator) -> crate::middleware::validator::Validator {
+                validator
+                    #(#checks)*
+            }
+
+            fn normalize(&mut self) {
+                #(#normalize)*
+            }
+
+            fn schema() -> crate::middleware::validator::Schema {
+                crate::middleware::validator::Schema::default()
+                    #(#schema)*
+            }
+        }
+    })
+}